@@ -1,13 +1,43 @@
 //! Nephyra: Smart System Assistant
 
 mod modules {
+    pub mod config;
+    pub mod config_merge;
     pub mod core;
+    pub mod cpu_freq;
+    pub mod cpu_info;
+    pub mod cpu_vulns;
     pub mod kernel_check;
     pub mod hardware_info;
+    pub mod hardware_snapshot;
+    pub mod log_rotation;
+    pub mod memory_info;
+    pub mod gpu_info;
+    pub mod devices;
+    pub mod netlink;
+    pub mod network_info;
+    pub mod virtualization;
+    pub mod dmi_info;
+    pub mod firmware_health;
     pub mod power_status;
+    pub mod power_profile;
+    pub mod power_measure;
+    pub mod power_tunables;
+    pub mod sleep_check;
     pub mod system_report;
     pub mod bootloader_check;
+    pub mod battery_threshold;
     pub mod package_check;
+    pub mod package_manager;
+    pub mod optimized_repos;
+    pub mod os_release;
+    pub mod sensors;
+    pub mod smbios;
+    pub mod storage_health;
+    pub mod state;
+    pub mod security_advisories;
+    pub mod sysfs;
+    pub mod universal_packages;
 }
 
 use std::env;
@@ -22,24 +52,39 @@ fn main() {
         println!("Available modules:");
         println!("  core");
         println!("  kernel");
-        println!("  hardware");
-        println!("  power");
+        println!("  hardware [--log <path>] [--format text|jsonl]");
+        println!("  hardware diff [--accept]");
+        println!("  power [profile <performance|balanced|powersave> | profile --restore] [--dry-run]");
+        println!("  power thresholds [<start> <end>] [--persist udev|systemd] [--dry-run]");
+        println!("  power measure [--duration 30s] [--interval 1s]");
+        println!("  power tunables [--apply | --restore] [--dry-run]");
+        println!("  power sleep");
+        println!("  sensors [--watch [seconds]]");
+        println!("  storage");
+        println!("  memory");
+        println!("  network");
+        println!("  firmware");
         println!("  report");
-        println!("  packages");
+        println!("  packages [--offline]");
         return;
     }
 
     match args[1].as_str() {
         "core" => modules::core::run(),
         "kernel" => modules::kernel_check::run(),
-        "hardware" => modules::hardware_info::run(),
-        "power" => modules::power_status::run(),
+        "hardware" => modules::hardware_info::run(&args[2..]),
+        "power" => modules::power_status::run(&args[2..]),
+        "sensors" => modules::sensors::run(&args[2..]),
+        "storage" => modules::storage_health::run(),
+        "memory" => modules::memory_info::run(),
+        "network" => modules::network_info::run(),
+        "firmware" => modules::firmware_health::run(),
         "report" => modules::system_report::run(),
         "bootloader" => modules::bootloader_check::run(),
-        "packages" => modules::package_check::run(),
+        "packages" => modules::package_check::run(&args[2..]),
         _ => {
             eprintln!("❌ Unknown module: {}", args[1]);
-            println!("Try: core, kernel, hardware, power, report, packages, kernel_suggest");
+            println!("Try: core, kernel, hardware, power, sensors, storage, memory, network, firmware, report, packages, kernel_suggest");
         }
     }
 }
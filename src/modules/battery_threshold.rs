@@ -0,0 +1,314 @@
+// battery_threshold.rs
+// Show and set battery charge thresholds, with vendor quirks and optional boot persistence
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::power_status::{self, POWER_SUPPLY_DIR};
+use super::sysfs;
+
+const START_ATTR: &str = "charge_control_start_threshold";
+const END_ATTR: &str = "charge_control_end_threshold";
+const UDEV_RULE_PATH: &str = "/etc/udev/rules.d/99-nephyra-charge-threshold.rules";
+const SYSTEMD_UNIT_NAME: &str = "nephyra-charge-threshold.service";
+const SYSTEMD_UNIT_DIR: &str = "/etc/systemd/system";
+
+/// Vendor-specific limits on what the platform driver accepts.
+#[derive(Debug, Clone)]
+struct ThresholdQuirk {
+    vendor: &'static str,
+    /// Only these end values are accepted (e.g. LG/Toshiba firmware toggles between two modes).
+    allowed_end: Option<&'static [u64]>,
+    start_range: (u64, u64),
+    end_range: (u64, u64),
+    /// Minimum distance between start and end.
+    min_gap: u64,
+    note: Option<&'static str>,
+}
+
+impl Default for ThresholdQuirk {
+    fn default() -> Self {
+        ThresholdQuirk {
+            vendor: "generic",
+            allowed_end: None,
+            start_range: (0, 99),
+            end_range: (1, 100),
+            min_gap: 1,
+            note: None,
+        }
+    }
+}
+
+fn detect_quirk() -> ThresholdQuirk {
+    let vendor = sysfs::read_trimmed("/sys/class/dmi/id/sys_vendor").unwrap_or_default().to_lowercase();
+    let product = sysfs::read_trimmed("/sys/class/dmi/id/product_version").unwrap_or_default().to_lowercase();
+    if vendor.contains("lenovo") && product.contains("thinkpad") {
+        ThresholdQuirk {
+            vendor: "ThinkPad",
+            note: Some("Some ThinkPads reset thresholds after a full battery drain; re-apply if they disappear."),
+            ..Default::default()
+        }
+    } else if vendor.contains("dell") {
+        ThresholdQuirk {
+            vendor: "Dell",
+            start_range: (50, 95),
+            end_range: (55, 100),
+            min_gap: 5,
+            note: Some("Dell firmware only honours custom thresholds while charge_types is set to Custom."),
+            ..Default::default()
+        }
+    } else if vendor.contains("lg electronics") {
+        ThresholdQuirk {
+            vendor: "LG",
+            allowed_end: Some(&[80, 100]),
+            ..Default::default()
+        }
+    } else if vendor.contains("toshiba") || vendor.contains("dynabook") {
+        ThresholdQuirk {
+            vendor: "Toshiba/Dynabook",
+            allowed_end: Some(&[80, 100]),
+            ..Default::default()
+        }
+    } else if vendor.contains("sony") {
+        ThresholdQuirk {
+            vendor: "Sony",
+            allowed_end: Some(&[50, 80, 100]),
+            ..Default::default()
+        }
+    } else if vendor.contains("micro-star") {
+        ThresholdQuirk {
+            vendor: "MSI",
+            end_range: (10, 100),
+            min_gap: 10,
+            note: Some("msi-ec derives the start threshold as end - 10; only the end value is significant."),
+            ..Default::default()
+        }
+    } else if vendor.contains("asustek") {
+        ThresholdQuirk {
+            vendor: "ASUS",
+            // Older firmware silently ignores anything else; these work on every generation.
+            allowed_end: Some(&[40, 60, 80, 100]),
+            ..Default::default()
+        }
+    } else if vendor.contains("framework") {
+        ThresholdQuirk {
+            vendor: "Framework",
+            note: Some("Framework EC firmware before 3.05 ignores the start threshold."),
+            ..Default::default()
+        }
+    } else if vendor.contains("huawei") {
+        ThresholdQuirk { vendor: "Huawei", ..Default::default() }
+    } else {
+        ThresholdQuirk::default()
+    }
+}
+
+fn validate(quirk: &ThresholdQuirk, start: Option<u64>, end: u64) -> Result<(), String> {
+    if let Some(allowed) = quirk.allowed_end {
+        if !allowed.contains(&end) {
+            let values: Vec<String> = allowed.iter().map(|v| v.to_string()).collect();
+            return Err(format!("{} firmware only accepts an end threshold of {}", quirk.vendor, values.join(" or ")));
+        }
+    } else if end < quirk.end_range.0 || end > quirk.end_range.1 {
+        return Err(format!("End threshold must be between {} and {} on {}", quirk.end_range.0, quirk.end_range.1, quirk.vendor));
+    }
+    if let Some(start) = start {
+        if start < quirk.start_range.0 || start > quirk.start_range.1 {
+            return Err(format!(
+                "Start threshold must be between {} and {} on {}",
+                quirk.start_range.0, quirk.start_range.1, quirk.vendor
+            ));
+        }
+        if start + quirk.min_gap > end {
+            return Err(format!("Start threshold must be at least {} below the end threshold", quirk.min_gap));
+        }
+    }
+    Ok(())
+}
+
+/// Batteries that expose at least an end threshold.
+fn threshold_batteries() -> Vec<(String, PathBuf)> {
+    power_status::scan_power_supplies()
+        .into_iter()
+        .filter(|s| s.battery.as_ref().is_some_and(|b| !b.peripheral))
+        .map(|s| {
+            let path = Path::new(POWER_SUPPLY_DIR).join(&s.name);
+            (s.name, path)
+        })
+        .filter(|(_, path)| path.join(END_ATTR).exists())
+        .collect()
+}
+
+/// Order the writes so the driver never sees start >= end in between.
+fn ordered_writes(path: &Path, start: Option<u64>, end: u64) -> Vec<(PathBuf, u64)> {
+    let end_write = (path.join(END_ATTR), end);
+    let Some(start) = start.filter(|_| path.join(START_ATTR).exists()) else {
+        return vec![end_write];
+    };
+    let start_write = (path.join(START_ATTR), start);
+    let current_end = sysfs::read_u64(path.join(END_ATTR)).unwrap_or(100);
+    if start >= current_end {
+        vec![end_write, start_write]
+    } else {
+        vec![start_write, end_write]
+    }
+}
+
+fn udev_rule(batteries: &[(String, PathBuf)], start: Option<u64>, end: u64) -> String {
+    let mut rule = String::from("# Installed by nephyra: battery charge thresholds\n");
+    for (name, path) in batteries {
+        let mut attrs = Vec::new();
+        if let Some(start) = start.filter(|_| path.join(START_ATTR).exists()) {
+            attrs.push(format!("ATTR{{{}}}=\"{}\"", START_ATTR, start));
+        }
+        attrs.push(format!("ATTR{{{}}}=\"{}\"", END_ATTR, end));
+        rule.push_str(&format!(
+            "ACTION==\"add\", SUBSYSTEM==\"power_supply\", KERNEL==\"{}\", {}\n",
+            name,
+            attrs.join(", ")
+        ));
+    }
+    rule
+}
+
+fn systemd_unit(batteries: &[(String, PathBuf)], start: Option<u64>, end: u64) -> String {
+    let mut exec = Vec::new();
+    for (_, path) in batteries {
+        for (attr, value) in ordered_writes(path, start, end) {
+            exec.push(format!("ExecStart=/bin/sh -c 'echo {} > {}'", value, attr.display()));
+        }
+    }
+    format!(
+        "# Installed by nephyra: battery charge thresholds\n\
+         [Unit]\n\
+         Description=Set battery charge thresholds\n\
+         After=multi-user.target suspend.target hibernate.target\n\n\
+         [Service]\n\
+         Type=oneshot\n\
+         {}\n\n\
+         [Install]\n\
+         WantedBy=multi-user.target suspend.target hibernate.target\n",
+        exec.join("\n")
+    )
+}
+
+fn install_persistence(method: &str, batteries: &[(String, PathBuf)], start: Option<u64>, end: u64, dry_run: bool) {
+    let (path, contents) = match method {
+        "udev" => (PathBuf::from(UDEV_RULE_PATH), udev_rule(batteries, start, end)),
+        "systemd" => (Path::new(SYSTEMD_UNIT_DIR).join(SYSTEMD_UNIT_NAME), systemd_unit(batteries, start, end)),
+        other => {
+            eprintln!("❌ Unknown persistence method: {} (use udev or systemd)", other);
+            return;
+        }
+    };
+    if dry_run {
+        println!("Would write {}:\n{}", path.display(), contents);
+        return;
+    }
+    if let Err(e) = fs::write(&path, contents) {
+        eprintln!("❌ Failed to write {}: {}", path.display(), e);
+        return;
+    }
+    println!("💾 Installed {}", path.display());
+    if method == "systemd" {
+        let status = Command::new("systemctl").args(["enable", SYSTEMD_UNIT_NAME]).status();
+        match status {
+            Ok(s) if s.success() => println!("✅ Enabled {}", SYSTEMD_UNIT_NAME),
+            _ => eprintln!("⚠️ Failed to enable {}; run: systemctl enable {}", SYSTEMD_UNIT_NAME, SYSTEMD_UNIT_NAME),
+        }
+    }
+}
+
+fn show(batteries: &[(String, PathBuf)], quirk: &ThresholdQuirk) {
+    println!("🔌 Battery charge thresholds ({} platform)", quirk.vendor);
+    for (name, path) in batteries {
+        let fmt = |attr: &str| sysfs::read_u64(path.join(attr)).map(|v| format!("{}%", v)).unwrap_or_else(|| "unsupported".to_string());
+        println!("  {}: start {}, end {}", name, fmt(START_ATTR), fmt(END_ATTR));
+    }
+    if let Some(note) = quirk.note {
+        println!("ℹ️ {}", note);
+    }
+}
+
+fn usage() {
+    println!("ℹ️ Usage: nephyra power thresholds [<start> <end> | <end>] [--battery <name>] [--persist udev|systemd] [--dry-run]");
+}
+
+/// Entry point for `nephyra power thresholds ...`.
+pub fn run(args: &[String]) {
+    let dry_run = args.iter().any(|a| a == "--dry-run");
+    let option = |flag: &str| args.iter().position(|a| a == flag).and_then(|i| args.get(i + 1)).cloned();
+    let battery_filter = option("--battery");
+    let persist = option("--persist");
+    let positional: Vec<&String> = args
+        .iter()
+        .enumerate()
+        .filter(|(i, a)| !a.starts_with("--") && (*i == 0 || !matches!(args[i - 1].as_str(), "--battery" | "--persist")))
+        .map(|(_, a)| a)
+        .collect();
+    let mut values = Vec::new();
+    for arg in positional {
+        match arg.trim_end_matches('%').parse::<u64>() {
+            Ok(v) => values.push(v),
+            Err(_) => {
+                eprintln!("❌ Invalid threshold: {}", arg);
+                usage();
+                return;
+            }
+        }
+    }
+
+    let mut batteries = threshold_batteries();
+    if let Some(filter) = &battery_filter {
+        batteries.retain(|(name, _)| name == filter);
+    }
+    if batteries.is_empty() {
+        println!("No battery with charge threshold support detected.");
+        return;
+    }
+    let quirk = detect_quirk();
+
+    let (start, end) = match values.as_slice() {
+        [] => {
+            show(&batteries, &quirk);
+            return;
+        }
+        [end] => (None, *end),
+        [start, end] => (Some(*start), *end),
+        _ => {
+            usage();
+            return;
+        }
+    };
+    if let Err(e) = validate(&quirk, start, end) {
+        eprintln!("❌ {}", e);
+        return;
+    }
+    if !dry_run && !sysfs::is_root() {
+        eprintln!("❌ Setting charge thresholds requires root. Re-run with sudo.");
+        return;
+    }
+
+    for (name, path) in &batteries {
+        if start.is_some() && !path.join(START_ATTR).exists() {
+            println!("ℹ️ {} has no start threshold; only the end threshold will be set.", name);
+        }
+        for (attr, value) in ordered_writes(path, start, end) {
+            if dry_run {
+                println!("  Would write {} to {}", value, attr.display());
+                continue;
+            }
+            match sysfs::write_attr(&attr, &value.to_string()) {
+                Ok(()) => println!("  ✅ {} = {}", attr.display(), value),
+                Err(e) => eprintln!("  ❌ {} = {}: {}", attr.display(), value, e),
+            }
+        }
+    }
+    if let Some(note) = quirk.note {
+        println!("ℹ️ {}", note);
+    }
+    if let Some(method) = persist {
+        install_persistence(&method, &batteries, start, end, dry_run);
+    }
+}
@@ -1,6 +1,8 @@
 use std::error::Error;
 use serde::Serialize;
 
+use super::virtualization;
+
 #[derive(Serialize)]
 pub struct BootloaderInfo {
     pub bootloader_type: String,
@@ -9,6 +11,10 @@ pub struct BootloaderInfo {
 }
 
 pub fn run() {
+    if let Some(container) = virtualization::detect_container() {
+        println!("ℹ️ Running inside a {} container: the bootloader belongs to the host.", container);
+        return;
+    }
     match check_bootloader() {
         Ok(info) => {
             println!("Bootloader Information:");
@@ -75,16 +81,19 @@ pub fn check_bootloader() -> Result<BootloaderInfo, Box<dyn Error>> {
 }
 
 pub fn get_summary() -> String {
+    if virtualization::detect_container().is_some() {
+        return "Bootloader: managed by the container host".to_string();
+    }
     match check_bootloader() {
         Ok(info) => {
             let mut summary = format!("Bootloader: {}", info.bootloader_type);
             if let Some(path) = info.config_path {
                 summary.push_str(&format!(" (Config: {})", path));
             }
-            if let Some(extra) = info.extra_info {
-                if !extra.contains("permission denied") {
-                    summary.push_str(&format!(" [{}]", extra));
-                }
+            if let Some(extra) = info.extra_info
+                && !extra.contains("permission denied")
+            {
+                summary.push_str(&format!(" [{}]", extra));
             }
             summary
         }
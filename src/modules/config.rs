@@ -0,0 +1,106 @@
+// config.rs
+// User configuration in ~/.config/nephyra/config.toml, shared by modules that each own a section
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// ~/.config/nephyra/config.toml, where preferences were kept before $XDG_CONFIG_HOME was honoured.
+fn legacy_path() -> PathBuf {
+    let home = env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".config/nephyra/config.toml")
+}
+
+/// $XDG_CONFIG_HOME/nephyra/config.toml, defaulting to ~/.config/nephyra/config.toml.
+pub fn config_path() -> PathBuf {
+    if let Ok(dir) = env::var("XDG_CONFIG_HOME")
+        && !dir.is_empty()
+    {
+        return PathBuf::from(dir).join("nephyra/config.toml");
+    }
+    legacy_path()
+}
+
+/// The file to read: the legacy location while `config_path` does not exist yet. The next
+/// `save` writes its contents to `config_path`.
+fn source_path() -> PathBuf {
+    existing_or_legacy(config_path(), legacy_path())
+}
+
+fn existing_or_legacy(path: PathBuf, legacy: PathBuf) -> PathBuf {
+    if !path.exists() && legacy.exists() { legacy } else { path }
+}
+
+fn read_config() -> Option<String> {
+    fs::read_to_string(source_path()).ok()
+}
+
+fn load_table() -> toml::Table {
+    read_config().and_then(|s| s.parse().ok()).unwrap_or_default()
+}
+
+/// Deserialize the whole file (unknown keys and other modules' sections are ignored).
+pub fn load<T: DeserializeOwned + Default>() -> T {
+    read_config().and_then(|s| toml::from_str(&s).ok()).unwrap_or_default()
+}
+
+/// Deserialize one `[section]`, falling back to defaults when it is missing or invalid; an
+/// invalid section is reported, since one bad value discards the whole section.
+pub fn load_section<T: DeserializeOwned + Default>(section: &str) -> T {
+    let Some(value) = load_table().remove(section) else {
+        return T::default();
+    };
+    match value.try_into() {
+        Ok(settings) => settings,
+        Err(e) => {
+            let reason = e.to_string().split_whitespace().collect::<Vec<_>>().join(" ");
+            eprintln!("⚠️ Ignoring [{}] in {}: {}", section, source_path().display(), reason);
+            T::default()
+        }
+    }
+}
+
+/// Write the top-level keys of `value` into the config file, keeping everything else in it.
+pub fn save<T: Serialize>(value: &T) -> io::Result<PathBuf> {
+    let path = config_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut table = load_table();
+    let toml::Value::Table(fields) = toml::Value::try_from(value).map_err(io::Error::other)? else {
+        return Err(io::Error::other("configuration must serialize to a table"));
+    };
+    table.extend(fields);
+    fs::write(&path, toml::to_string_pretty(&table).map_err(io::Error::other)?)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::sysfs;
+
+    #[test]
+    fn legacy_fallback() {
+        let dir = sysfs::scratch_dir("config");
+        let path = dir.join("xdg/nephyra/config.toml");
+        let legacy = dir.join("home/.config/nephyra/config.toml");
+        let _ = fs::remove_dir_all(dir.join("xdg"));
+        let _ = fs::remove_dir_all(dir.join("home"));
+
+        assert_eq!(existing_or_legacy(path.clone(), legacy.clone()), path, "neither exists: the new location");
+        fs::create_dir_all(legacy.parent().unwrap()).unwrap();
+        fs::write(&legacy, "use_cases = [\"gaming\"]\n").unwrap();
+        assert_eq!(existing_or_legacy(path.clone(), legacy.clone()), legacy, "only the legacy file exists");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "").unwrap();
+        assert_eq!(existing_or_legacy(path.clone(), legacy.clone()), path, "the new file wins once written");
+        assert_eq!(existing_or_legacy(legacy.clone(), legacy.clone()), legacy, "unset XDG_CONFIG_HOME is the legacy path");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
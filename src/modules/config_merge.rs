@@ -0,0 +1,654 @@
+// config_merge.rs
+// Unmerged configuration files left by package upgrades (.pacnew, .rpmnew, .dpkg-dist, ._cfg…) and an
+// interactive keep/replace/merge workflow for them
+
+use std::env;
+use std::fs;
+use std::fs::{DirBuilder, OpenOptions};
+use std::io::{self, IsTerminal, Write};
+use std::os::unix::fs::{self as unix_fs, DirBuilderExt, MetadataExt, OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use chrono::Local;
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::{Confirm, Editor, Select};
+
+use super::state;
+
+const CONFIG_ROOT: &str = "/etc";
+/// Resolved packaged versions, kept as the base for the next three-way merge (like dispatch-conf).
+const ARCHIVE_DIR: &str = "config-archive";
+const CONTEXT_LINES: usize = 3;
+const DIFF_PREVIEW_LINES: usize = 30;
+/// Line diff is quadratic; larger pairs are shown as a full replacement.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PendingKind {
+    /// A new packaged default the live file has not picked up yet.
+    NewVersion,
+    /// The user's previous file, saved when the package replaced it with its default.
+    SavedCopy,
+}
+
+/// Suffix → kind, for every package manager that leaves such files behind.
+const SUFFIXES: [(&str, PendingKind); 11] = [
+    (".pacnew", PendingKind::NewVersion),
+    (".pacsave", PendingKind::SavedCopy),
+    (".rpmnew", PendingKind::NewVersion),
+    (".rpmsave", PendingKind::SavedCopy),
+    (".dpkg-dist", PendingKind::NewVersion),
+    (".dpkg-new", PendingKind::NewVersion),
+    (".dpkg-old", PendingKind::SavedCopy),
+    (".ucf-dist", PendingKind::NewVersion),
+    (".ucf-old", PendingKind::SavedCopy),
+    (".apk-new", PendingKind::NewVersion),
+    (".apk-old", PendingKind::SavedCopy),
+];
+
+#[derive(Debug, Clone)]
+pub struct PendingConfig {
+    pub pending: PathBuf,
+    pub live: PathBuf,
+    pub kind: PendingKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChangeClass {
+    Identical,
+    /// Only indentation, spacing or blank lines differ.
+    Whitespace,
+    /// Only comment lines differ.
+    Comments,
+    Real,
+    /// The saved copy's package no longer installs the live file.
+    LiveMissing,
+    /// Not readable as text (permissions or binary content).
+    Unreadable,
+}
+
+impl ChangeClass {
+    fn label(self) -> &'static str {
+        match self {
+            ChangeClass::Identical => "identical, safe to delete",
+            ChangeClass::Whitespace => "whitespace-only change",
+            ChangeClass::Comments => "comment-only change",
+            ChangeClass::Real => "real change",
+            ChangeClass::LiveMissing => "live file no longer exists",
+            ChangeClass::Unreadable => "unreadable, run as root",
+        }
+    }
+
+    fn marker(self) -> &'static str {
+        match self {
+            ChangeClass::Identical | ChangeClass::Whitespace | ChangeClass::Comments => "ℹ️",
+            ChangeClass::Real | ChangeClass::LiveMissing => "⚠️",
+            ChangeClass::Unreadable => "❌",
+        }
+    }
+}
+
+/// The live file a pending file belongs to, e.g. /etc/pacman.conf for /etc/pacman.conf.pacnew or
+/// /etc/ssh/sshd_config for portage's /etc/ssh/._cfg0000_sshd_config.
+pub fn live_path(path: &Path) -> Option<(PathBuf, PendingKind)> {
+    let name = path.file_name()?.to_string_lossy().to_string();
+    if let Some(rest) = name.strip_prefix("._cfg")
+        && rest.len() > 5
+        && rest[..4].chars().all(|c| c.is_ascii_digit())
+        && rest.as_bytes()[4] == b'_'
+    {
+        return Some((path.with_file_name(&rest[5..]), PendingKind::NewVersion));
+    }
+    SUFFIXES.iter().find_map(|(suffix, kind)| {
+        let live = name.strip_suffix(suffix).filter(|l| !l.is_empty())?;
+        Some((path.with_file_name(live), *kind))
+    })
+}
+
+/// Walk `root` for pending configuration files, without following symlinks.
+pub fn find_pending(root: &Path) -> Vec<PendingConfig> {
+    let mut found = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                dirs.push(path);
+            } else if file_type.is_file()
+                && let Some((live, kind)) = live_path(&path)
+            {
+                found.push(PendingConfig { pending: path, live, kind });
+            }
+        }
+    }
+    found.sort_by(|a, b| a.pending.cmp(&b.pending));
+    found
+}
+
+/// Lines with whitespace collapsed and blank lines dropped.
+fn normalized(text: &str) -> Vec<String> {
+    text.lines().map(|l| l.split_whitespace().collect::<Vec<_>>().join(" ")).filter(|l| !l.is_empty()).collect()
+}
+
+fn is_comment(line: &str) -> bool {
+    line.starts_with('#') || line.starts_with(';') || line.starts_with("//")
+}
+
+pub fn classify(live: &str, candidate: &str) -> ChangeClass {
+    if live == candidate {
+        return ChangeClass::Identical;
+    }
+    let (live, candidate) = (normalized(live), normalized(candidate));
+    if live == candidate {
+        return ChangeClass::Whitespace;
+    }
+    let code = |lines: Vec<String>| lines.into_iter().filter(|l| !is_comment(l)).collect::<Vec<_>>();
+    if code(live) == code(candidate) { ChangeClass::Comments } else { ChangeClass::Real }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffOp<'a> {
+    Equal(&'a str),
+    Delete(&'a str),
+    Insert(&'a str),
+}
+
+/// Line diff via longest common subsequence.
+pub fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<DiffOp<'a>> {
+    let (n, m) = (old.len(), new.len());
+    if n.saturating_mul(m) > MAX_DIFF_CELLS {
+        return old.iter().map(|l| DiffOp::Delete(l)).chain(new.iter().map(|l| DiffOp::Insert(l))).collect();
+    }
+    // lcs[i][j]: common lines of old[i..] and new[j..]
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[i] == new[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut ops = Vec::with_capacity(n + m);
+    while i < n || j < m {
+        if i < n && j < m && old[i] == new[j] {
+            ops.push(DiffOp::Equal(old[i]));
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            // Deletions first, like diff -u
+            ops.push(DiffOp::Delete(old[i]));
+            i += 1;
+        } else {
+            ops.push(DiffOp::Insert(new[j]));
+            j += 1;
+        }
+    }
+    ops
+}
+
+/// Index ranges of `ops` forming unified-diff hunks with `context` lines around each change.
+fn hunk_ranges(ops: &[DiffOp], context: usize) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (index, op) in ops.iter().enumerate() {
+        if matches!(op, DiffOp::Equal(_)) {
+            continue;
+        }
+        let start = index.saturating_sub(context);
+        let end = (index + context + 1).min(ops.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+    ranges
+}
+
+/// `diff -u` style output between two texts.
+pub fn unified_diff(old_name: &str, new_name: &str, old: &str, new: &str) -> String {
+    let (old_lines, new_lines): (Vec<&str>, Vec<&str>) = (old.lines().collect(), new.lines().collect());
+    let ops = diff_lines(&old_lines, &new_lines);
+    let mut out = format!("--- {}\n+++ {}\n", old_name, new_name);
+    // Line numbers (1-based) reached at each op index
+    let (mut old_no, mut new_no) = (1, 1);
+    let mut position = Vec::with_capacity(ops.len());
+    for op in &ops {
+        position.push((old_no, new_no));
+        match op {
+            DiffOp::Equal(_) => {
+                old_no += 1;
+                new_no += 1;
+            }
+            DiffOp::Delete(_) => old_no += 1,
+            DiffOp::Insert(_) => new_no += 1,
+        }
+    }
+    for (start, end) in hunk_ranges(&ops, CONTEXT_LINES) {
+        let slice = &ops[start..end];
+        let old_len = slice.iter().filter(|o| !matches!(o, DiffOp::Insert(_))).count();
+        let new_len = slice.iter().filter(|o| !matches!(o, DiffOp::Delete(_))).count();
+        // An empty side is numbered by the line before it, as diff does
+        let first = |line: usize, len: usize| if len == 0 { line - 1 } else { line };
+        out.push_str(&format!("@@ -{},{} +{},{} @@\n", first(position[start].0, old_len), old_len, first(position[start].1, new_len), new_len));
+        for op in slice {
+            match op {
+                DiffOp::Equal(l) => out.push_str(&format!(" {}\n", l)),
+                DiffOp::Delete(l) => out.push_str(&format!("-{}\n", l)),
+                DiffOp::Insert(l) => out.push_str(&format!("+{}\n", l)),
+            }
+        }
+    }
+    out
+}
+
+fn read_text(path: &Path) -> Option<String> {
+    String::from_utf8(fs::read(path).ok()?).ok()
+}
+
+fn classify_pending(p: &PendingConfig) -> ChangeClass {
+    if !p.live.exists() {
+        return ChangeClass::LiveMissing;
+    }
+    match (read_text(&p.live), read_text(&p.pending)) {
+        (Some(live), Some(candidate)) => classify(&live, &candidate),
+        _ => ChangeClass::Unreadable,
+    }
+}
+
+fn archive_path(live: &Path) -> PathBuf {
+    state::state_dir().join(ARCHIVE_DIR).join(live.strip_prefix("/").unwrap_or(live))
+}
+
+/// Store a copy of `source` in the archive. Copies of /etc can hold secrets, so the archive is
+/// private to its owner and each copy keeps the mode of the file it was taken from.
+fn write_archived(path: &Path, source: &Path, content: &str) -> io::Result<()> {
+    let mode = fs::metadata(source)?.permissions().mode() & 0o777;
+    let root = state::state_dir().join(ARCHIVE_DIR);
+    if let Some(parent) = path.parent() {
+        DirBuilder::new().recursive(true).mode(0o700).create(parent)?;
+    }
+    // Archives created before this was private keep their old mode otherwise
+    fs::set_permissions(&root, fs::Permissions::from_mode(0o700))?;
+    let mut file = OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(path)?;
+    file.set_permissions(fs::Permissions::from_mode(mode))?;
+    file.write_all(content.as_bytes())
+}
+
+/// Remember the packaged version as the merge base for next time.
+fn archive_base(p: &PendingConfig, packaged: &str) {
+    if p.kind != PendingKind::NewVersion {
+        return;
+    }
+    if let Err(e) = write_archived(&archive_path(&p.live), &p.pending, packaged) {
+        eprintln!("⚠️ Failed to archive {} as a merge base: {}", p.live.display(), e);
+    }
+}
+
+/// Copy the live file next to the archived base before it is overwritten.
+fn backup_live(live: &Path, content: &str) -> io::Result<PathBuf> {
+    let mut path = archive_path(live).into_os_string();
+    path.push(format!(".{}", Local::now().format("%Y%m%d%H%M%S")));
+    let path = PathBuf::from(path);
+    write_archived(&path, live, content)?;
+    Ok(path)
+}
+
+/// Three-way merge with diff3: changes from `base` to `candidate` applied onto `live`.
+/// Returns the merged text and whether conflict markers remain.
+fn merge_three_way(live: &Path, base: &Path, candidate: &Path) -> io::Result<(String, bool)> {
+    let output = Command::new("diff3")
+        .arg("-m")
+        .args(["-L", "current", "-L", "base", "-L", "new"])
+        .args([live, base, candidate])
+        .output()?;
+    // 0: clean, 1: conflicts, 2: trouble
+    match output.status.code() {
+        Some(0) | Some(1) => Ok((String::from_utf8_lossy(&output.stdout).to_string(), output.status.code() == Some(1))),
+        _ => Err(io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string())),
+    }
+}
+
+/// Two-way merge without a base: walk each block of changes and pick a side.
+fn merge_by_hunk(live: &str, candidate: &str) -> dialoguer::Result<Option<String>> {
+    let (old_lines, new_lines): (Vec<&str>, Vec<&str>) = (live.lines().collect(), candidate.lines().collect());
+    let ops = diff_lines(&old_lines, &new_lines);
+    let mut merged: Vec<&str> = Vec::new();
+    let mut index = 0;
+    while index < ops.len() {
+        if let DiffOp::Equal(line) = ops[index] {
+            merged.push(line);
+            index += 1;
+            continue;
+        }
+        let end = ops[index..].iter().position(|o| matches!(o, DiffOp::Equal(_))).map(|p| index + p).unwrap_or(ops.len());
+        let current: Vec<&str> = ops[index..end].iter().filter_map(|o| if let DiffOp::Delete(l) = o { Some(*l) } else { None }).collect();
+        let new: Vec<&str> = ops[index..end].iter().filter_map(|o| if let DiffOp::Insert(l) = o { Some(*l) } else { None }).collect();
+        println!();
+        for line in merged.iter().rev().take(CONTEXT_LINES).rev() {
+            println!("  {}", line);
+        }
+        for line in &current {
+            println!("- {}", line);
+        }
+        for line in &new {
+            println!("+ {}", line);
+        }
+        let choice = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Which lines should the merged file have?")
+            .items(&["Keep current (-)", "Take new (+)", "Both (current first)", "Abort merge"])
+            .default(0)
+            .interact()?;
+        match choice {
+            0 => merged.extend(&current),
+            1 => merged.extend(&new),
+            2 => merged.extend(current.iter().chain(&new)),
+            _ => return Ok(None),
+        }
+        index = end;
+    }
+    let mut text = merged.join("\n");
+    if candidate.ends_with('\n') || live.ends_with('\n') {
+        text.push('\n');
+    }
+    Ok(Some(text))
+}
+
+/// Write `content` to a temporary file next to `live` with the owner and mode of `template`, then
+/// rename it into place, so nothing ever reads a half-written config.
+fn replace_file(live: &Path, template: &Path, content: &str) -> io::Result<()> {
+    let metadata = fs::metadata(template)?;
+    let name = live.file_name().ok_or_else(|| io::Error::other("not a file path"))?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(name);
+    temp_name.push(format!(".nephyra-{}", std::process::id()));
+    let temp = live.with_file_name(temp_name);
+    let result = OpenOptions::new().write(true).create_new(true).mode(0o600).open(&temp).and_then(|mut file| {
+        file.write_all(content.as_bytes())?;
+        // chown first: it clears setuid/setgid bits the mode may carry
+        unix_fs::fchown(&file, Some(metadata.uid()), Some(metadata.gid()))?;
+        file.set_permissions(metadata.permissions())?;
+        file.sync_all()?;
+        fs::rename(&temp, live)
+    });
+    if result.is_err() {
+        fs::remove_file(&temp).ok();
+    }
+    result
+}
+
+fn write_live(p: &PendingConfig, current: Option<&str>, content: &str) -> bool {
+    // Renaming over a symlink would replace the link itself; let the user edit its target
+    if fs::symlink_metadata(&p.live).is_ok_and(|m| m.file_type().is_symlink()) {
+        let target = fs::read_link(&p.live).map(|t| t.display().to_string()).unwrap_or_default();
+        eprintln!("❌ {} is a symlink to {}; merge {} into the target by hand.", p.live.display(), target, p.pending.display());
+        return false;
+    }
+    if let Some(current) = current {
+        match backup_live(&p.live, current) {
+            Ok(path) => println!("💾 Previous {} saved to {}", p.live.display(), path.display()),
+            Err(e) => {
+                eprintln!("❌ Failed to back up {}: {}", p.live.display(), e);
+                return false;
+            }
+        }
+    }
+    // A restored file takes the owner and mode of the copy it comes from
+    let template = if current.is_some() { &p.live } else { &p.pending };
+    match replace_file(&p.live, template, content) {
+        Ok(()) => true,
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+            eprintln!("❌ Failed to write {}: {} (run as root)", p.live.display(), e);
+            false
+        }
+        Err(e) => {
+            eprintln!("❌ Failed to write {}: {}", p.live.display(), e);
+            false
+        }
+    }
+}
+
+fn remove_pending(p: &PendingConfig) {
+    match fs::remove_file(&p.pending) {
+        Ok(()) => println!("✅ Removed {}", p.pending.display()),
+        Err(e) => eprintln!("❌ Failed to remove {}: {} (run as root)", p.pending.display(), e),
+    }
+}
+
+/// Let an external tool such as `vim -d` or meld do the merge, as pacdiff does with $DIFFPROG.
+fn run_diffprog(diffprog: &str, p: &PendingConfig) {
+    let mut parts = diffprog.split_whitespace();
+    let Some(program) = parts.next() else {
+        return;
+    };
+    if let Err(e) = Command::new(program).args(parts).arg(&p.live).arg(&p.pending).status() {
+        eprintln!("❌ Failed to run {}: {}", diffprog, e);
+    }
+}
+
+fn merge(p: &PendingConfig, live: &str, candidate: &str) -> dialoguer::Result<()> {
+    let base = archive_path(&p.live);
+    let merged = if base.exists() {
+        match merge_three_way(&p.live, &base, &p.pending) {
+            Ok((text, false)) => Some(text),
+            Ok((text, true)) => {
+                println!("⚠️ The merge has conflicts; resolve the <<<<<<< / >>>>>>> blocks in the editor.");
+                Editor::new().edit(&text)?
+            }
+            Err(e) => {
+                eprintln!("⚠️ Three-way merge failed ({}); merging change by change instead.", e);
+                merge_by_hunk(live, candidate)?
+            }
+        }
+    } else {
+        println!("ℹ️ No earlier packaged version archived yet; merging change by change.");
+        merge_by_hunk(live, candidate)?
+    };
+    let Some(merged) = merged else {
+        println!("Merge aborted; nothing changed.");
+        return Ok(());
+    };
+    println!("{}", unified_diff(&p.live.display().to_string(), "merged", live, &merged));
+    if Confirm::with_theme(&ColorfulTheme::default()).with_prompt(format!("Write the merged result to {}?", p.live.display())).default(true).interact()?
+        && write_live(p, Some(live), &merged)
+    {
+        archive_base(p, candidate);
+        remove_pending(p);
+    }
+    Ok(())
+}
+
+fn review(p: &PendingConfig, class: ChangeClass) -> dialoguer::Result<()> {
+    let theme = ColorfulTheme::default();
+    let candidate = read_text(&p.pending).unwrap_or_default();
+    if class == ChangeClass::LiveMissing {
+        let choice = Select::with_theme(&theme)
+            .with_prompt(format!("{}: {} is gone", p.pending.display(), p.live.display()))
+            .items(&["Delete the leftover copy", "Restore it as the live file", "Skip"])
+            .default(2)
+            .interact()?;
+        match choice {
+            0 => remove_pending(p),
+            1 if write_live(p, None, &candidate) => remove_pending(p),
+            _ => {}
+        }
+        return Ok(());
+    }
+    let live = read_text(&p.live).unwrap_or_default();
+    if class == ChangeClass::Identical {
+        if Confirm::with_theme(&theme).with_prompt(format!("Delete the identical {}?", p.pending.display())).default(true).interact()? {
+            archive_base(p, &candidate);
+            remove_pending(p);
+        }
+        return Ok(());
+    }
+
+    let diffprog = env::var("DIFFPROG").ok().filter(|d| !d.trim().is_empty());
+    let (keep, replace) = match p.kind {
+        PendingKind::NewVersion => ("Keep current file (delete the new version)", "Replace with the new version"),
+        PendingKind::SavedCopy => ("Keep current file (delete the saved copy)", "Restore the saved copy"),
+    };
+    let mut items = vec![keep, replace, "Merge", "Show full diff", "Skip"];
+    if diffprog.is_some() {
+        items.insert(3, "Open in $DIFFPROG");
+    }
+    loop {
+        let choice = Select::with_theme(&theme).with_prompt(p.pending.display().to_string()).items(&items).default(items.len() - 1).interact()?;
+        match items[choice] {
+            "Merge" => return merge(p, &live, &candidate),
+            "Show full diff" => println!("{}", unified_diff(&p.live.display().to_string(), &p.pending.display().to_string(), &live, &candidate)),
+            "Open in $DIFFPROG" => {
+                run_diffprog(diffprog.as_deref().unwrap_or_default(), p);
+                if Confirm::with_theme(&theme).with_prompt(format!("Done merging; delete {}?", p.pending.display())).default(false).interact()? {
+                    archive_base(p, &candidate);
+                    remove_pending(p);
+                }
+                return Ok(());
+            }
+            "Skip" => return Ok(()),
+            item if item == keep => {
+                archive_base(p, &candidate);
+                remove_pending(p);
+                return Ok(());
+            }
+            _ => {
+                if write_live(p, Some(&live), &candidate) {
+                    archive_base(p, &candidate);
+                    remove_pending(p);
+                }
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// List pending configuration files under /etc with a diff preview, then offer to resolve them.
+pub fn run() {
+    println!("\n🗂️ Unmerged configuration files");
+    let pending = find_pending(Path::new(CONFIG_ROOT));
+    if pending.is_empty() {
+        println!("✅ No .pacnew, .rpmnew, .dpkg-dist or similar files under {}.", CONFIG_ROOT);
+        return;
+    }
+    let classified: Vec<(PendingConfig, ChangeClass)> = pending
+        .into_iter()
+        .map(|p| {
+            let class = classify_pending(&p);
+            (p, class)
+        })
+        .collect();
+    for (p, class) in &classified {
+        println!("{} {} ({})", class.marker(), p.pending.display(), class.label());
+        if matches!(class, ChangeClass::Real | ChangeClass::Whitespace | ChangeClass::Comments)
+            && let (Some(live), Some(candidate)) = (read_text(&p.live), read_text(&p.pending))
+        {
+            let diff = unified_diff(&p.live.display().to_string(), &p.pending.display().to_string(), &live, &candidate);
+            let lines: Vec<&str> = diff.lines().collect();
+            for line in lines.iter().take(DIFF_PREVIEW_LINES) {
+                println!("    {}", line);
+            }
+            if lines.len() > DIFF_PREVIEW_LINES {
+                println!("    … {} more diff lines", lines.len() - DIFF_PREVIEW_LINES);
+            }
+        }
+    }
+    let real = classified.iter().filter(|(_, c)| *c == ChangeClass::Real).count();
+    println!("\n{} pending file(s), {} with real changes.", classified.len(), real);
+
+    if !io::stdin().is_terminal() {
+        println!("💡 Run `nephyra packages` in a terminal to keep, replace or merge them.");
+        return;
+    }
+    let result = Confirm::with_theme(&ColorfulTheme::default()).with_prompt("Review them now?").default(false).interact().and_then(|review_now| {
+        if review_now {
+            for (p, class) in classified.iter().filter(|(_, c)| *c != ChangeClass::Unreadable) {
+                review(p, *class)?;
+            }
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        eprintln!("❌ Review interrupted: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::sysfs;
+
+    #[test]
+    fn replace_file_keeps_mode() {
+        let dir = sysfs::scratch_dir("replace");
+        let live = dir.join("sshd_config");
+        fs::write(&live, "old\n").unwrap();
+        fs::set_permissions(&live, fs::Permissions::from_mode(0o640)).unwrap();
+        replace_file(&live, &live, "new\n").unwrap();
+        assert_eq!(fs::read_to_string(&live).unwrap(), "new\n");
+        assert_eq!(fs::metadata(&live).unwrap().permissions().mode() & 0o7777, 0o640);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1, "no temporary file left behind");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn live_paths() {
+        let live = |p: &str| live_path(Path::new(p)).map(|(path, kind)| (path.display().to_string(), kind));
+        assert_eq!(live("/etc/pacman.conf.pacnew"), Some(("/etc/pacman.conf".to_string(), PendingKind::NewVersion)));
+        assert_eq!(live("/etc/fstab.rpmsave"), Some(("/etc/fstab".to_string(), PendingKind::SavedCopy)));
+        assert_eq!(live("/etc/ssh/sshd_config.dpkg-dist"), Some(("/etc/ssh/sshd_config".to_string(), PendingKind::NewVersion)));
+        assert_eq!(live("/etc/ssh/._cfg0000_sshd_config"), Some(("/etc/ssh/sshd_config".to_string(), PendingKind::NewVersion)));
+        assert_eq!(live("/etc/.pacnew"), None);
+        assert_eq!(live("/etc/._cfg00_x"), None);
+        assert_eq!(live("/etc/pacman.conf"), None);
+    }
+
+    #[test]
+    fn classify_changes() {
+        let live = "# Misc options\nColor\nParallelDownloads = 5\n";
+        assert_eq!(classify(live, live), ChangeClass::Identical);
+        assert_eq!(classify(live, "# Misc options\n\nColor\nParallelDownloads   =  5\n"), ChangeClass::Whitespace);
+        assert_eq!(classify(live, "# Misc options, see pacman.conf(5)\nColor\nParallelDownloads = 5\n"), ChangeClass::Comments);
+        assert_eq!(classify(live, "# Misc options\n#Color\nParallelDownloads = 5\n"), ChangeClass::Real);
+    }
+
+    #[test]
+    fn diff_deletes_before_inserts() {
+        let old = ["a", "b", "c"];
+        let new = ["a", "B", "c", "d"];
+        assert_eq!(
+            diff_lines(&old, &new),
+            [DiffOp::Equal("a"), DiffOp::Delete("b"), DiffOp::Insert("B"), DiffOp::Equal("c"), DiffOp::Insert("d")]
+        );
+        assert_eq!(diff_lines(&[], &["x"]), [DiffOp::Insert("x")]);
+    }
+
+    #[test]
+    fn unified_diff_matches_diff_u() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\n";
+        // Recorded from `diff -u --label old --label new`
+        let expected = "\
+--- old
++++ new
+@@ -1,5 +1,5 @@
+ a
+-b
++B
+ c
+ d
+ e
+@@ -10,3 +10,4 @@
+ j
+ k
+ l
++m
+";
+        assert_eq!(unified_diff("old", "new", old, new), expected);
+        assert_eq!(unified_diff("old", "new", "", "x\ny\n"), "--- old\n+++ new\n@@ -0,0 +1,2 @@\n+x\n+y\n");
+        assert_eq!(unified_diff("old", "new", old, old), "--- old\n+++ new\n");
+    }
+}
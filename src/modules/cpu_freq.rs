@@ -0,0 +1,285 @@
+// cpu_freq.rs
+// CPU frequency scaling: driver, governor, EPP and boost state per cpufreq policy
+
+use std::path::Path;
+
+use super::sysfs;
+
+pub const CPUFREQ_DIR: &str = "/sys/devices/system/cpu/cpufreq";
+pub const BOOST_PATH: &str = "/sys/devices/system/cpu/cpufreq/boost";
+pub const INTEL_NO_TURBO_PATH: &str = "/sys/devices/system/cpu/intel_pstate/no_turbo";
+const INTEL_PSTATE_STATUS_PATH: &str = "/sys/devices/system/cpu/intel_pstate/status";
+const AMD_PSTATE_STATUS_PATH: &str = "/sys/devices/system/cpu/amd_pstate/status";
+
+#[derive(Debug, Clone)]
+pub struct CpuFreqPolicy {
+    pub name: String,
+    pub affected_cpus: String,
+    pub driver: String,
+    pub governor: String,
+    pub available_governors: Vec<String>,
+    pub min_khz: Option<u64>,
+    pub max_khz: Option<u64>,
+    pub cur_khz: Option<u64>,
+    pub hw_max_khz: Option<u64>,
+    pub epp: Option<String>,
+    pub available_epp: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CpuFreqInfo {
+    pub policies: Vec<CpuFreqPolicy>,
+    /// Turbo/boost enabled, from cpufreq/boost or the inverse of intel_pstate/no_turbo.
+    pub boost: Option<bool>,
+    pub intel_pstate_mode: Option<String>,
+    pub amd_pstate_mode: Option<String>,
+}
+
+impl CpuFreqInfo {
+    pub fn driver(&self) -> Option<&str> {
+        self.policies.first().map(|p| p.driver.as_str())
+    }
+
+    pub fn governor(&self) -> Option<&str> {
+        self.policies.first().map(|p| p.governor.as_str())
+    }
+
+    pub fn epp(&self) -> Option<&str> {
+        self.policies.first().and_then(|p| p.epp.as_deref())
+    }
+
+    fn governor_available(&self, governor: &str) -> bool {
+        self.policies.iter().all(|p| p.available_governors.iter().any(|g| g == governor))
+    }
+
+    fn epp_available(&self, epp: &str) -> bool {
+        !self.policies.is_empty() && self.policies.iter().all(|p| p.available_epp.iter().any(|e| e == epp))
+    }
+
+    /// Whether the policies disagree on governor or EPP (usually a sign of a half-applied tweak).
+    fn is_mixed(&self) -> bool {
+        self.policies.windows(2).any(|w| w[0].governor != w[1].governor || w[0].epp != w[1].epp)
+    }
+}
+
+fn split_list(value: Option<String>) -> Vec<String> {
+    value.map(|s| s.split_whitespace().map(|v| v.to_string()).collect()).unwrap_or_default()
+}
+
+fn read_policy(path: &Path) -> CpuFreqPolicy {
+    let attr = |name: &str| sysfs::read_trimmed(path.join(name));
+    CpuFreqPolicy {
+        name: path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+        affected_cpus: attr("affected_cpus").unwrap_or_default(),
+        driver: attr("scaling_driver").unwrap_or_else(|| "unknown".to_string()),
+        governor: attr("scaling_governor").unwrap_or_else(|| "unknown".to_string()),
+        available_governors: split_list(attr("scaling_available_governors")),
+        min_khz: sysfs::read_u64(path.join("scaling_min_freq")),
+        max_khz: sysfs::read_u64(path.join("scaling_max_freq")),
+        cur_khz: sysfs::read_u64(path.join("scaling_cur_freq")),
+        hw_max_khz: sysfs::read_u64(path.join("cpuinfo_max_freq")),
+        epp: attr("energy_performance_preference"),
+        available_epp: split_list(attr("energy_performance_available_preferences")),
+    }
+}
+
+pub fn read_cpufreq() -> CpuFreqInfo {
+    let policies: Vec<CpuFreqPolicy> = sysfs::list_dir_prefixed(CPUFREQ_DIR, "policy")
+        .iter()
+        .map(|p| read_policy(p))
+        .collect();
+    let boost = sysfs::read_u64(BOOST_PATH)
+        .map(|v| v != 0)
+        .or_else(|| sysfs::read_u64(INTEL_NO_TURBO_PATH).map(|v| v == 0));
+    CpuFreqInfo {
+        policies,
+        boost,
+        intel_pstate_mode: sysfs::read_trimmed(INTEL_PSTATE_STATUS_PATH),
+        amd_pstate_mode: sysfs::read_trimmed(AMD_PSTATE_STATUS_PATH),
+    }
+}
+
+fn format_khz(khz: Option<u64>) -> String {
+    match khz {
+        Some(k) if k >= 1_000_000 => format!("{:.2} GHz", k as f64 / 1_000_000.0),
+        Some(k) => format!("{} MHz", k / 1000),
+        None => "?".to_string(),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Recommendation {
+    pub setting: &'static str,
+    pub current: String,
+    pub recommended: String,
+    pub reason: String,
+}
+
+impl Recommendation {
+    pub fn is_satisfied(&self) -> bool {
+        self.current == self.recommended
+    }
+}
+
+/// Suggest governor, EPP and boost settings for the current power source and use cases.
+pub fn recommend(info: &CpuFreqInfo, on_ac: Option<bool>, use_cases: &[String]) -> Vec<Recommendation> {
+    let mut recs = Vec::new();
+    let Some(driver) = info.driver() else {
+        return recs;
+    };
+    let has_case = |needle: &str| use_cases.iter().any(|c| c.to_lowercase().contains(needle));
+    let on_battery = on_ac == Some(false);
+    let latency_sensitive = has_case("audio") || has_case("gaming");
+
+    // intel_pstate and amd-pstate in active mode only offer performance/powersave; EPP does the real tuning.
+    let epp_driven = info.policies.iter().all(|p| !p.available_epp.is_empty());
+    let (governor, governor_reason) = if epp_driven {
+        if has_case("audio") && !on_battery {
+            ("performance", "Low-latency audio work benefits from keeping cores at full speed on AC.")
+        } else {
+            ("powersave", "With an EPP-capable driver the powersave governor lets the hardware scale dynamically.")
+        }
+    } else if on_battery {
+        ("schedutil", "schedutil follows scheduler load and saves energy on battery.")
+    } else if latency_sensitive {
+        ("performance", "Gaming/audio workloads favour the performance governor on AC power.")
+    } else {
+        ("schedutil", "schedutil is the best general-purpose governor for this driver.")
+    };
+    if info.governor_available(governor) {
+        recs.push(Recommendation {
+            setting: "Governor",
+            current: info.governor().unwrap_or("unknown").to_string(),
+            recommended: governor.to_string(),
+            reason: governor_reason.to_string(),
+        });
+    }
+
+    if epp_driven && governor != "performance" {
+        let (epp, epp_reason) = if on_battery {
+            ("balance_power", "On battery, balance_power trades a little responsiveness for runtime.")
+        } else if latency_sensitive {
+            ("performance", "On AC, gaming/audio benefit from the performance energy preference.")
+        } else if has_case("server") {
+            ("balance_performance", "Servers on AC should favour throughput while still idling efficiently.")
+        } else {
+            ("balance_performance", "On AC, balance_performance keeps the desktop responsive.")
+        };
+        if info.epp_available(epp) {
+            recs.push(Recommendation {
+                setting: "Energy Performance Preference",
+                current: info.epp().unwrap_or("unknown").to_string(),
+                recommended: epp.to_string(),
+                reason: epp_reason.to_string(),
+            });
+        }
+    }
+
+    if let Some(boost) = info.boost {
+        let (want, reason) = if on_battery && !latency_sensitive {
+            (false, "Disabling boost on battery noticeably lowers temperature and power draw.")
+        } else {
+            (true, "Boost should stay enabled on AC for best single-thread performance.")
+        };
+        let label = |b: bool| if b { "enabled" } else { "disabled" }.to_string();
+        recs.push(Recommendation {
+            setting: "Boost",
+            current: label(boost),
+            recommended: label(want),
+            reason: reason.to_string(),
+        });
+    }
+
+    if driver == "acpi-cpufreq" && is_amd_cpu() {
+        recs.push(Recommendation {
+            setting: "Scaling driver",
+            current: driver.to_string(),
+            recommended: "amd-pstate-epp".to_string(),
+            reason: "Zen 2 and newer CPUs with CPPC scale more efficiently with amd_pstate=active on the kernel command line.".to_string(),
+        });
+    } else if info.amd_pstate_mode.as_deref() == Some("passive") {
+        recs.push(Recommendation {
+            setting: "amd_pstate mode",
+            current: "passive".to_string(),
+            recommended: "active".to_string(),
+            reason: "Active mode exposes EPP hints so the firmware can manage frequency itself.".to_string(),
+        });
+    }
+
+    recs
+}
+
+fn is_amd_cpu() -> bool {
+    std::fs::read_to_string("/proc/cpuinfo")
+        .map(|s| s.contains("AuthenticAMD"))
+        .unwrap_or(false)
+}
+
+pub fn run(on_ac: Option<bool>, use_cases: &[String]) {
+    let info = read_cpufreq();
+    println!("\n⚙️ CPU Frequency Scaling");
+    if info.policies.is_empty() {
+        println!("  cpufreq: Not available (no scaling driver loaded or running in a VM)");
+        return;
+    }
+    println!("  Driver   : {}", info.driver().unwrap_or("unknown"));
+    if let Some(mode) = &info.intel_pstate_mode {
+        println!("  intel_pstate mode: {}", mode);
+    }
+    if let Some(mode) = &info.amd_pstate_mode {
+        println!("  amd_pstate mode  : {}", mode);
+    }
+    if let Some(boost) = info.boost {
+        println!("  Boost    : {}", if boost { "enabled" } else { "disabled" });
+    }
+    for policy in &info.policies {
+        println!("  {} (CPUs {}):", policy.name, policy.affected_cpus);
+        println!("    Governor : {} (available: {})", policy.governor, policy.available_governors.join(", "));
+        println!(
+            "    Frequency: {} (range {} - {}, hardware max {})",
+            format_khz(policy.cur_khz),
+            format_khz(policy.min_khz),
+            format_khz(policy.max_khz),
+            format_khz(policy.hw_max_khz)
+        );
+        if let Some(epp) = &policy.epp {
+            println!("    EPP      : {} (available: {})", epp, policy.available_epp.join(", "));
+        }
+    }
+    if info.is_mixed() {
+        println!("  ⚠️ Policies use different governors or EPP values.");
+    }
+
+    let recs = recommend(&info, on_ac, use_cases);
+    if !recs.is_empty() {
+        let source = match on_ac {
+            Some(true) => "AC power",
+            Some(false) => "battery",
+            None => "unknown power source",
+        };
+        println!("\n💡 Recommendations ({}, use cases: {}):", source, use_cases.join(", "));
+        for rec in recs {
+            if rec.is_satisfied() {
+                println!("  ✅ {}: {}", rec.setting, rec.current);
+            } else {
+                println!("  ⚠️ {}: {} → {}", rec.setting, rec.current, rec.recommended);
+                println!("     {}", rec.reason);
+            }
+        }
+    }
+}
+
+pub fn get_summary() -> String {
+    let info = read_cpufreq();
+    let Some(driver) = info.driver() else {
+        return "CPU Scaling: Not available".to_string();
+    };
+    let mut parts = vec![driver.to_string(), info.governor().unwrap_or("unknown").to_string()];
+    if let Some(epp) = info.epp() {
+        parts.push(format!("EPP {}", epp));
+    }
+    if let Some(boost) = info.boost {
+        parts.push(format!("boost {}", if boost { "on" } else { "off" }));
+    }
+    format!("CPU Scaling: {}", parts.join(" / "))
+}
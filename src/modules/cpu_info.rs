@@ -0,0 +1,354 @@
+// cpu_info.rs
+// CPU identity and topology from /proc/cpuinfo and /sys/devices/system/cpu (no lscpu, locale independent)
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use super::sysfs;
+
+const CPU_DIR: &str = "/sys/devices/system/cpu";
+const NODE_DIR: &str = "/sys/devices/system/node";
+// Intel hybrid CPUs register one PMU per core type.
+const INTEL_PCORE_PMU: &str = "/sys/devices/cpu_core/cpus";
+const INTEL_ECORE_PMU: &str = "/sys/devices/cpu_atom/cpus";
+
+/// Flags worth calling out in the summary, in display order.
+const NOTABLE_FLAGS: [&str; 12] = [
+    "sse4_2", "avx", "avx2", "fma", "avx512f", "avx512_bf16", "avx_vnni", "amx_tile", "aes", "sha_ni", "vmx", "svm",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct CoreCluster {
+    pub name: String,
+    pub cpus: Vec<u32>,
+    pub cores: usize,
+    pub max_khz: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CacheInfo {
+    pub level: u32,
+    pub kind: String,
+    pub size: String,
+    /// Number of distinct instances of this cache across the system.
+    pub instances: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CpuInfo {
+    pub model: String,
+    pub vendor: String,
+    pub family: Option<u32>,
+    pub model_id: Option<u32>,
+    pub stepping: Option<u32>,
+    pub microcode: Option<String>,
+    pub flags: Vec<String>,
+    pub sockets: usize,
+    pub physical_cores: usize,
+    pub threads: usize,
+    pub clusters: Vec<CoreCluster>,
+    pub caches: Vec<CacheInfo>,
+    pub numa_nodes: Vec<(u32, Vec<u32>)>,
+    /// Distinct per-core maximum frequencies in kHz and how many CPUs reach each.
+    pub max_freqs_khz: Vec<(u64, usize)>,
+}
+
+impl CpuInfo {
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+
+    /// The x86-64 psABI microarchitecture level (1-4) supported by this CPU, or None on other architectures.
+    pub fn x86_64_level(&self) -> Option<u8> {
+        x86_64_level(&self.flags)
+    }
+}
+
+/// Compute the x86-64 psABI level from /proc/cpuinfo flags. `abm` is the flag name for LZCNT.
+pub fn x86_64_level(flags: &[String]) -> Option<u8> {
+    let has = |names: &[&str]| names.iter().all(|n| flags.iter().any(|f| f == n));
+    if !has(&["lm", "cmov", "cx8", "fpu", "fxsr", "mmx", "syscall", "sse2"]) {
+        return None;
+    }
+    let v2 = has(&["cx16", "lahf_lm", "popcnt", "sse4_1", "sse4_2", "ssse3"]);
+    let v3 = v2 && has(&["avx", "avx2", "bmi1", "bmi2", "f16c", "fma", "abm", "movbe", "xsave"]);
+    let v4 = v3 && has(&["avx512f", "avx512bw", "avx512cd", "avx512dq", "avx512vl"]);
+    Some(if v4 {
+        4
+    } else if v3 {
+        3
+    } else if v2 {
+        2
+    } else {
+        1
+    })
+}
+
+fn cpuinfo_field<'a>(block: &'a str, key: &str) -> Option<&'a str> {
+    block.lines().find_map(|line| {
+        let (k, v) = line.split_once(':')?;
+        if k.trim() == key { Some(v.trim()) } else { None }
+    })
+}
+
+/// Online CPU directories (cpu0, cpu1, ...), skipping cpufreq/cpuidle and other entries.
+fn cpu_dirs() -> Vec<(u32, std::path::PathBuf)> {
+    let mut cpus: Vec<(u32, std::path::PathBuf)> = sysfs::list_dir_prefixed(CPU_DIR, "cpu")
+        .into_iter()
+        .filter_map(|p| {
+            let n = p.file_name()?.to_string_lossy().strip_prefix("cpu")?.parse().ok()?;
+            // cpu0 often has no "online" file because it cannot be offlined.
+            let online = sysfs::read_u64(p.join("online")).unwrap_or(1) == 1;
+            if online && p.join("topology").exists() { Some((n, p)) } else { None }
+        })
+        .collect();
+    cpus.sort_by_key(|(n, _)| *n);
+    cpus
+}
+
+fn read_caches(cpus: &[(u32, std::path::PathBuf)]) -> Vec<CacheInfo> {
+    // (level, type, size) -> distinct shared_cpu_list values
+    let mut seen: BTreeMap<(u32, String, String), Vec<String>> = BTreeMap::new();
+    for (_, dir) in cpus {
+        for index in sysfs::list_dir_prefixed(dir.join("cache"), "index") {
+            let Some(level) = sysfs::read_u64(index.join("level")) else {
+                continue;
+            };
+            let kind = sysfs::read_trimmed(index.join("type")).unwrap_or_default();
+            let size = sysfs::read_trimmed(index.join("size")).unwrap_or_default();
+            let shared = sysfs::read_trimmed(index.join("shared_cpu_list")).unwrap_or_default();
+            let entry = seen.entry((level as u32, kind, size)).or_default();
+            if !entry.contains(&shared) {
+                entry.push(shared);
+            }
+        }
+    }
+    seen.into_iter()
+        .map(|((level, kind, size), shared)| CacheInfo { level, kind, size, instances: shared.len() })
+        .collect()
+}
+
+fn read_numa_nodes() -> Vec<(u32, Vec<u32>)> {
+    sysfs::list_dir_prefixed(NODE_DIR, "node")
+        .into_iter()
+        .filter_map(|p| {
+            let id = p.file_name()?.to_string_lossy().strip_prefix("node")?.parse().ok()?;
+            let cpus = sysfs::parse_cpu_list(&sysfs::read_trimmed(p.join("cpulist"))?);
+            Some((id, cpus))
+        })
+        .collect()
+}
+
+fn cluster(name: String, cpus: Vec<u32>) -> CoreCluster {
+    let cpu_path = |c: &u32, attr: &str| Path::new(CPU_DIR).join(format!("cpu{}/{}", c, attr));
+    let max_khz = cpus.iter().filter_map(|c| sysfs::read_u64(cpu_path(c, "cpufreq/cpuinfo_max_freq"))).max();
+    let mut cores: Vec<String> = cpus
+        .iter()
+        .filter_map(|c| sysfs::read_trimmed(cpu_path(c, "topology/core_cpus_list")))
+        .collect();
+    cores.sort();
+    cores.dedup();
+    CoreCluster { name, cores: cores.len(), cpus, max_khz }
+}
+
+fn read_clusters(cpus: &[(u32, std::path::PathBuf)]) -> Vec<CoreCluster> {
+    let pmu = |path: &str| sysfs::read_trimmed(path).map(|l| sysfs::parse_cpu_list(&l)).filter(|l| !l.is_empty());
+    if let (Some(p), Some(e)) = (pmu(INTEL_PCORE_PMU), pmu(INTEL_ECORE_PMU)) {
+        return vec![cluster("P-cores".to_string(), p), cluster("E-cores".to_string(), e)];
+    }
+    // ARM big.LITTLE and friends expose a relative cpu_capacity per core.
+    let mut by_capacity: BTreeMap<u64, Vec<u32>> = BTreeMap::new();
+    for (n, dir) in cpus {
+        if let Some(cap) = sysfs::read_u64(dir.join("cpu_capacity")) {
+            by_capacity.entry(cap).or_default().push(*n);
+        }
+    }
+    if by_capacity.len() > 1 {
+        return by_capacity
+            .into_iter()
+            .rev()
+            .map(|(cap, list)| cluster(format!("capacity {}", cap), list))
+            .collect();
+    }
+    Vec::new()
+}
+
+pub fn read_cpu_info() -> CpuInfo {
+    let cpuinfo = fs::read_to_string("/proc/cpuinfo").unwrap_or_default();
+    let first = cpuinfo.split("\n\n").next().unwrap_or("");
+    // ARM kernels use "Hardware"/"CPU part" instead of "model name".
+    let model = cpuinfo_field(first, "model name")
+        .or_else(|| cpuinfo_field(&cpuinfo, "Hardware"))
+        .unwrap_or("Unknown")
+        .to_string();
+    let flags = cpuinfo_field(first, "flags")
+        .or_else(|| cpuinfo_field(first, "Features"))
+        .map(|f| f.split_whitespace().map(|s| s.to_string()).collect())
+        .unwrap_or_default();
+
+    let cpus = cpu_dirs();
+    let mut packages: Vec<String> = Vec::new();
+    let mut cores: Vec<(String, String)> = Vec::new();
+    let mut freqs: BTreeMap<u64, usize> = BTreeMap::new();
+    for (_, dir) in &cpus {
+        let package = sysfs::read_trimmed(dir.join("topology/physical_package_id")).unwrap_or_default();
+        // core_cpus_list identifies SMT siblings even where core_id is reused across dies.
+        let core = sysfs::read_trimmed(dir.join("topology/core_cpus_list"))
+            .or_else(|| sysfs::read_trimmed(dir.join("topology/thread_siblings_list")))
+            .unwrap_or_default();
+        if !packages.contains(&package) {
+            packages.push(package.clone());
+        }
+        if !cores.contains(&(package.clone(), core.clone())) {
+            cores.push((package, core));
+        }
+        if let Some(f) = sysfs::read_u64(dir.join("cpufreq/cpuinfo_max_freq")) {
+            *freqs.entry(f).or_default() += 1;
+        }
+    }
+
+    CpuInfo {
+        model,
+        vendor: cpuinfo_field(first, "vendor_id")
+            .or_else(|| cpuinfo_field(first, "CPU implementer"))
+            .unwrap_or("Unknown")
+            .to_string(),
+        family: cpuinfo_field(first, "cpu family").and_then(|v| v.parse().ok()),
+        model_id: cpuinfo_field(first, "model").and_then(|v| v.parse().ok()),
+        stepping: cpuinfo_field(first, "stepping").and_then(|v| v.parse().ok()),
+        microcode: cpuinfo_field(first, "microcode").map(|s| s.to_string()),
+        flags,
+        sockets: packages.len(),
+        physical_cores: cores.len(),
+        threads: cpus.len(),
+        clusters: read_clusters(&cpus),
+        caches: read_caches(&cpus),
+        numa_nodes: read_numa_nodes(),
+        max_freqs_khz: freqs.into_iter().rev().collect(),
+    }
+}
+
+fn format_ghz(khz: u64) -> String {
+    format!("{:.2} GHz", khz as f64 / 1_000_000.0)
+}
+
+fn format_cpu_ranges(cpus: &[u32]) -> String {
+    let mut ranges: Vec<String> = Vec::new();
+    let mut iter = cpus.iter().peekable();
+    while let Some(&start) = iter.next() {
+        let mut end = start;
+        while iter.peek().is_some_and(|&&n| n == end + 1) {
+            end = *iter.next().unwrap_or(&end);
+        }
+        ranges.push(if start == end { start.to_string() } else { format!("{}-{}", start, end) });
+    }
+    ranges.join(",")
+}
+
+/// Print the detailed CPU section used by `nephyra hardware`.
+pub fn print_details(info: &CpuInfo) {
+    println!("\n💻 CPU: {}", info.model);
+    let mut ident = vec![info.vendor.clone()];
+    if let (Some(f), Some(m), Some(s)) = (info.family, info.model_id, info.stepping) {
+        ident.push(format!("family {} model {} stepping {}", f, m, s));
+    }
+    if let Some(mc) = &info.microcode {
+        ident.push(format!("microcode {}", mc));
+    }
+    println!("   {}", ident.join(", "));
+    println!(
+        "🧮 Topology: {} socket(s), {} physical core(s), {} thread(s)",
+        info.sockets, info.physical_cores, info.threads
+    );
+    for cluster in &info.clusters {
+        let freq = cluster.max_khz.map(|k| format!(", up to {}", format_ghz(k))).unwrap_or_default();
+        println!(
+            "   {}: {} cores / {} threads (CPUs {}){}",
+            cluster.name,
+            cluster.cores,
+            cluster.cpus.len(),
+            format_cpu_ranges(&cluster.cpus),
+            freq
+        );
+    }
+    match info.max_freqs_khz.as_slice() {
+        [] => {}
+        [(khz, _)] => println!("   Max frequency: {}", format_ghz(*khz)),
+        freqs => {
+            let list: Vec<String> = freqs.iter().map(|(k, n)| format!("{} ×{}", format_ghz(*k), n)).collect();
+            println!("   Per-core max frequency: {}", list.join(", "));
+        }
+    }
+    if !info.caches.is_empty() {
+        let caches: Vec<String> = info
+            .caches
+            .iter()
+            .map(|c| {
+                let suffix = match c.kind.as_str() {
+                    "Data" => "d",
+                    "Instruction" => "i",
+                    _ => "",
+                };
+                format!("L{}{} {} ×{}", c.level, suffix, c.size, c.instances)
+            })
+            .collect();
+        println!("   Caches: {}", caches.join(", "));
+    }
+    if info.numa_nodes.len() > 1 {
+        for (id, cpus) in &info.numa_nodes {
+            println!("   NUMA node {}: CPUs {}", id, format_cpu_ranges(cpus));
+        }
+    } else if !info.numa_nodes.is_empty() {
+        println!("   NUMA: single node");
+    }
+    let notable: Vec<&str> = NOTABLE_FLAGS.iter().copied().filter(|f| info.has_flag(f)).collect();
+    let level = info.x86_64_level().map(|l| format!("x86-64-v{}", l));
+    if level.is_some() || !notable.is_empty() {
+        println!("   ISA: {}{}", level.map(|l| format!("{}; ", l)).unwrap_or_default(), notable.join(" "));
+    }
+}
+
+/// One-line CPU description for summaries: model plus cores/threads.
+pub fn summary(info: &CpuInfo) -> String {
+    let hybrid = if info.clusters.len() == 2 && info.clusters[0].name == "P-cores" {
+        format!(" ({}P+{}E)", info.clusters[0].cores, info.clusters[1].cores)
+    } else {
+        String::new()
+    };
+    let level = info.x86_64_level().map(|l| format!(" | x86-64-v{}", l)).unwrap_or_default();
+    format!("{} | Cores: {}C/{}T{}{}", info.model, info.physical_cores, info.threads, hybrid, level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASELINE: &str = "fpu cx8 cmov mmx fxsr sse sse2 syscall lm";
+    const V2: &str = "cx16 lahf_lm popcnt sse4_1 sse4_2 ssse3";
+    const V3: &str = "avx avx2 bmi1 bmi2 f16c fma abm movbe xsave";
+    const V4: &str = "avx512f avx512bw avx512cd avx512dq avx512vl";
+
+    fn level(sets: &[&str]) -> Option<u8> {
+        let flags: Vec<String> = sets.iter().flat_map(|s| s.split_whitespace()).map(|f| f.to_string()).collect();
+        x86_64_level(&flags)
+    }
+
+    #[test]
+    fn x86_64_levels() {
+        assert_eq!(level(&[BASELINE]), Some(1));
+        assert_eq!(level(&[BASELINE, V2]), Some(2));
+        assert_eq!(level(&[BASELINE, V2, V3]), Some(3));
+        assert_eq!(level(&[BASELINE, V2, V3, V4]), Some(4));
+        assert_eq!(level(&["fp asimd evtstrm aes pmull sha1 sha2 crc32"]), None, "not x86-64");
+    }
+
+    #[test]
+    fn levels_need_every_flag() {
+        // AVX-512 without v3 (or v3 without v2) does not skip a level
+        assert_eq!(level(&[BASELINE, V3, V4]), Some(1));
+        assert_eq!(level(&[BASELINE, V2, &V3.replace("movbe", ""), V4]), Some(2));
+        assert_eq!(level(&[BASELINE, V2, V3, &V4.replace("avx512vl", "")]), Some(3));
+        assert_eq!(level(&[&BASELINE.replace("lm", ""), V2]), None);
+    }
+}
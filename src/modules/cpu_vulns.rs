@@ -0,0 +1,214 @@
+// cpu_vulns.rs
+// CPU vulnerability status from /sys/devices/system/cpu/vulnerabilities, tied to cmdline opt-outs and microcode
+
+use std::fs;
+
+use super::cpu_info::CpuInfo;
+use super::sysfs;
+
+const VULNERABILITIES_DIR: &str = "/sys/devices/system/cpu/vulnerabilities";
+
+/// Kernel parameters that turn off the mitigation for one vulnerability (None = all of them).
+const CMDLINE_OPT_OUTS: [(&str, Option<&str>); 19] = [
+    ("mitigations=off", None),
+    ("nospectre_v1", Some("spectre_v1")),
+    ("nospectre_v2", Some("spectre_v2")),
+    ("spectre_v2=off", Some("spectre_v2")),
+    ("nopti", Some("meltdown")),
+    ("pti=off", Some("meltdown")),
+    ("mds=off", Some("mds")),
+    ("tsx_async_abort=off", Some("tsx_async_abort")),
+    ("mmio_stale_data=off", Some("mmio_stale_data")),
+    ("l1tf=off", Some("l1tf")),
+    ("retbleed=off", Some("retbleed")),
+    ("spec_rstack_overflow=off", Some("spec_rstack_overflow")),
+    ("gather_data_sampling=off", Some("gather_data_sampling")),
+    ("reg_file_data_sampling=off", Some("reg_file_data_sampling")),
+    ("srbds=off", Some("srbds")),
+    ("nospec_store_bypass_disable", Some("spec_store_bypass")),
+    ("spec_store_bypass_disable=off", Some("spec_store_bypass")),
+    ("indirect_target_selection=off", Some("indirect_target_selection")),
+    ("tsa=off", Some("tsa")),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VulnStatus {
+    NotAffected,
+    Mitigated,
+    /// A mitigation is active but the kernel reports part of it as vulnerable (e.g. "BHI: Vulnerable").
+    PartiallyMitigated,
+    Vulnerable,
+    Unknown,
+}
+
+#[derive(Debug, Clone)]
+pub struct Vulnerability {
+    /// sysfs file name, e.g. "spectre_v2".
+    pub id: String,
+    pub status: VulnStatus,
+    /// Raw kernel description.
+    pub detail: String,
+    /// Kernel parameter that disabled the mitigation, if any.
+    pub opt_out: Option<String>,
+}
+
+impl Vulnerability {
+    pub fn display_name(&self) -> String {
+        match self.id.as_str() {
+            "spectre_v1" => "Spectre v1".to_string(),
+            "spectre_v2" => "Spectre v2".to_string(),
+            "meltdown" => "Meltdown".to_string(),
+            "mds" => "MDS".to_string(),
+            "l1tf" => "L1TF (Foreshadow)".to_string(),
+            "retbleed" => "Retbleed".to_string(),
+            "spec_rstack_overflow" => "SRSO (Inception)".to_string(),
+            "gather_data_sampling" => "GDS (Downfall)".to_string(),
+            "spec_store_bypass" => "Speculative Store Bypass".to_string(),
+            "tsx_async_abort" => "TAA".to_string(),
+            "mmio_stale_data" => "MMIO Stale Data".to_string(),
+            "srbds" => "SRBDS".to_string(),
+            "itlb_multihit" => "iTLB multihit".to_string(),
+            "reg_file_data_sampling" => "RFDS".to_string(),
+            "indirect_target_selection" => "ITS".to_string(),
+            "tsa" => "TSA".to_string(),
+            "old_microcode" => "Old microcode".to_string(),
+            "ghostwrite" => "GhostWrite".to_string(),
+            "vmscape" => "VMScape".to_string(),
+            other => other.replace('_', " "),
+        }
+    }
+
+    /// Whether updated microcode is what's missing for this mitigation.
+    pub fn needs_microcode(&self) -> bool {
+        let detail = self.detail.to_lowercase();
+        self.status != VulnStatus::NotAffected && (self.id == "old_microcode" || detail.contains("no microcode"))
+    }
+}
+
+pub fn classify(detail: &str) -> VulnStatus {
+    if detail.starts_with("Not affected") {
+        VulnStatus::NotAffected
+    } else if detail.starts_with("Vulnerable") {
+        VulnStatus::Vulnerable
+    } else if detail.starts_with("Mitigation") {
+        if detail.to_lowercase().contains("vulnerable") {
+            VulnStatus::PartiallyMitigated
+        } else {
+            VulnStatus::Mitigated
+        }
+    } else {
+        VulnStatus::Unknown
+    }
+}
+
+fn cmdline_opt_out(cmdline: &[&str], id: &str) -> Option<String> {
+    CMDLINE_OPT_OUTS
+        .iter()
+        .find(|(param, target)| cmdline.contains(param) && target.is_none_or(|t| t == id))
+        .map(|(param, _)| param.to_string())
+}
+
+pub fn read_vulnerabilities() -> Vec<Vulnerability> {
+    let cmdline = fs::read_to_string("/proc/cmdline").unwrap_or_default();
+    let params: Vec<&str> = cmdline.split_whitespace().collect();
+    sysfs::list_dir_prefixed(VULNERABILITIES_DIR, "")
+        .into_iter()
+        .filter_map(|path| {
+            let id = path.file_name()?.to_string_lossy().to_string();
+            let detail = sysfs::read_trimmed(&path)?;
+            let status = classify(&detail);
+            let opt_out = if status == VulnStatus::NotAffected { None } else { cmdline_opt_out(&params, &id) };
+            Some(Vulnerability { id, status, detail, opt_out })
+        })
+        .collect()
+}
+
+/// Vulnerabilities left open without the user asking for it on the kernel command line. Those
+/// waiting only for a microcode update are left out: a different kernel does not fix them.
+pub fn unintended_exposures(vulns: &[Vulnerability]) -> usize {
+    vulns.iter().filter(|v| v.status == VulnStatus::Vulnerable && v.opt_out.is_none() && !v.needs_microcode()).count()
+}
+
+pub fn microcode_package(vendor: &str) -> &'static str {
+    match vendor {
+        "GenuineIntel" => "intel-ucode / intel-microcode",
+        "AuthenticAMD" => "amd-ucode / amd64-microcode",
+        _ => "your CPU microcode package",
+    }
+}
+
+/// Print the vulnerability section used by `nephyra hardware`.
+pub fn print_report(cpu: &CpuInfo, vulns: &[Vulnerability]) {
+    if vulns.is_empty() {
+        return;
+    }
+    println!("\n🛡️ CPU Vulnerabilities:");
+    for v in vulns {
+        let (icon, label) = match v.status {
+            VulnStatus::NotAffected => ("✅", "not affected"),
+            VulnStatus::Mitigated => ("✅", "mitigated"),
+            VulnStatus::PartiallyMitigated => ("⚠️", "partially mitigated"),
+            VulnStatus::Vulnerable => ("❌", "vulnerable"),
+            VulnStatus::Unknown => ("❔", "unknown"),
+        };
+        if v.status == VulnStatus::NotAffected {
+            println!("  {} {}: {}", icon, v.display_name(), label);
+        } else {
+            println!("  {} {}: {} ({})", icon, v.display_name(), label, v.detail);
+        }
+    }
+
+    let opted_out: Vec<&str> = vulns.iter().filter_map(|v| v.opt_out.as_deref()).collect();
+    if !opted_out.is_empty() {
+        let mut params = opted_out.clone();
+        params.sort();
+        params.dedup();
+        println!("ℹ️ Mitigations disabled on the kernel command line by: {}", params.join(" "));
+    }
+    if vulns.iter().any(|v| v.needs_microcode()) {
+        println!(
+            "⚠️ Some mitigations need newer microcode (current revision {}). Update {} and regenerate the initramfs.",
+            cpu.microcode.as_deref().unwrap_or("unknown"),
+            microcode_package(&cpu.vendor)
+        );
+    }
+    let exposed = unintended_exposures(vulns);
+    if exposed > 0 {
+        println!("❌ {} vulnerability(ies) are unmitigated although no mitigation was disabled on purpose.", exposed);
+    }
+}
+
+/// One-line counts for summaries.
+pub fn summary(vulns: &[Vulnerability]) -> String {
+    let count = |status: VulnStatus| vulns.iter().filter(|v| v.status == status).count();
+    format!(
+        "CPU Vulnerabilities: {} vulnerable, {} partially mitigated, {} mitigated, {} not affected",
+        count(VulnStatus::Vulnerable),
+        count(VulnStatus::PartiallyMitigated),
+        count(VulnStatus::Mitigated),
+        count(VulnStatus::NotAffected)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vuln(id: &str, detail: &str, opt_out: Option<&str>) -> Vulnerability {
+        Vulnerability { id: id.to_string(), status: classify(detail), detail: detail.to_string(), opt_out: opt_out.map(|o| o.to_string()) }
+    }
+
+    #[test]
+    fn microcode_gaps_are_not_kernel_exposures() {
+        let vulns = [
+            vuln("spectre_v1", "Mitigation: usercopy/swapgs barriers and __user pointer sanitization", None),
+            vuln("gather_data_sampling", "Vulnerable: No microcode", None),
+            vuln("old_microcode", "Vulnerable", None),
+            vuln("retbleed", "Vulnerable", Some("retbleed=off")),
+            vuln("mds", "Vulnerable: Clear CPU buffers attempted, no microcode; SMT vulnerable", None),
+            vuln("spectre_v2", "Vulnerable", None),
+        ];
+        assert!(vulns[1].needs_microcode() && vulns[2].needs_microcode() && vulns[4].needs_microcode());
+        assert_eq!(unintended_exposures(&vulns), 1, "only spectre_v2 is open without a reason");
+    }
+}
@@ -0,0 +1,516 @@
+// devices.rs
+// Native PCI and USB enumeration over /sys/bus, with hwdata names, modalias module candidates and IOMMU groups
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use super::sysfs;
+
+const PCI_DEVICES_DIR: &str = "/sys/bus/pci/devices";
+const USB_DEVICES_DIR: &str = "/sys/bus/usb/devices";
+const PCI_IDS_PATHS: [&str; 3] = ["/usr/share/hwdata/pci.ids", "/usr/share/misc/pci.ids", "/usr/share/pci.ids"];
+const USB_IDS_PATHS: [&str; 3] = ["/usr/share/hwdata/usb.ids", "/usr/share/misc/usb.ids", "/usr/share/usb.ids"];
+/// PCI bridges (host, ISA, "other") usually have no driver by design.
+const PCI_BRIDGE_CLASS: u32 = 0x06;
+
+#[derive(Debug, Clone, Default)]
+struct IdVendor {
+    name: String,
+    devices: HashMap<u16, String>,
+}
+
+/// Vendor, device and class names parsed from a pci.ids or usb.ids file.
+#[derive(Debug, Clone, Default)]
+pub struct IdDatabase {
+    vendors: HashMap<u16, IdVendor>,
+    classes: HashMap<u8, (String, HashMap<u8, String>)>,
+}
+
+impl IdDatabase {
+    fn load(paths: &[&str]) -> Self {
+        paths.iter().find_map(|p| fs::read_to_string(p).ok()).map(|s| Self::parse(&s)).unwrap_or_default()
+    }
+
+    pub fn parse(content: &str) -> Self {
+        enum Section {
+            Vendors,
+            Classes,
+            Other,
+        }
+        let mut db = IdDatabase::default();
+        let mut section = Section::Vendors;
+        let mut vendor: Option<u16> = None;
+        let mut class: Option<u8> = None;
+        for line in content.lines() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let depth = line.len() - line.trim_start_matches('\t').len();
+            let Some((id, name)) = line.trim_start_matches('\t').split_once("  ") else {
+                continue;
+            };
+            match (depth, &section) {
+                (0, _) => {
+                    if let Some(class_id) = id.strip_prefix("C ") {
+                        section = Section::Classes;
+                        class = u8::from_str_radix(class_id.trim(), 16).ok();
+                        if let Some(c) = class {
+                            db.classes.insert(c, (name.to_string(), HashMap::new()));
+                        }
+                    } else if let (Ok(v), true) = (u16::from_str_radix(id, 16), id.len() == 4) {
+                        section = Section::Vendors;
+                        vendor = Some(v);
+                        db.vendors.insert(v, IdVendor { name: name.to_string(), devices: HashMap::new() });
+                    } else {
+                        // usb.ids has further sections (AT, HID, R, ...) that we don't need
+                        section = Section::Other;
+                    }
+                }
+                (1, Section::Vendors) => {
+                    if let (Some(v), Ok(d)) = (vendor, u16::from_str_radix(id, 16))
+                        && let Some(entry) = db.vendors.get_mut(&v)
+                    {
+                        entry.devices.insert(d, name.to_string());
+                    }
+                }
+                (1, Section::Classes) => {
+                    if let (Some(c), Ok(sub)) = (class, u8::from_str_radix(id, 16))
+                        && let Some((_, subclasses)) = db.classes.get_mut(&c)
+                    {
+                        subclasses.insert(sub, name.to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+        db
+    }
+
+    pub fn vendor_name(&self, vendor: u16) -> Option<&str> {
+        self.vendors.get(&vendor).map(|v| v.name.as_str())
+    }
+
+    pub fn device_name(&self, vendor: u16, device: u16) -> Option<&str> {
+        self.vendors.get(&vendor)?.devices.get(&device).map(|s| s.as_str())
+    }
+
+    /// Subclass name if known, else the base class name.
+    pub fn class_name(&self, class: u8, subclass: u8) -> Option<&str> {
+        let (name, subclasses) = self.classes.get(&class)?;
+        Some(subclasses.get(&subclass).map(|s| s.as_str()).unwrap_or(name))
+    }
+}
+
+/// Module alias patterns from modules.alias for the running kernel.
+#[derive(Debug, Clone, Default)]
+pub struct ModuleAliases {
+    /// Patterns are split into chars once; every device is matched against all of them.
+    aliases: Vec<(Vec<char>, String)>,
+}
+
+impl ModuleAliases {
+    pub fn load() -> Self {
+        let release = fs::read_to_string("/proc/sys/kernel/osrelease").unwrap_or_default();
+        let path = Path::new("/lib/modules").join(release.trim()).join("modules.alias");
+        let content = fs::read_to_string(path).unwrap_or_default();
+        let aliases = content
+            .lines()
+            .filter_map(|l| {
+                let mut parts = l.split_whitespace();
+                if parts.next() != Some("alias") {
+                    return None;
+                }
+                Some((parts.next()?, parts.next()?))
+            })
+            .filter(|(pattern, _)| pattern.starts_with("pci:") || pattern.starts_with("usb:"))
+            .map(|(pattern, module)| (pattern.chars().collect(), module.to_string()))
+            .collect();
+        ModuleAliases { aliases }
+    }
+
+    /// Modules whose alias pattern matches the device modalias, in modules.alias order.
+    pub fn candidates(&self, modalias: &str) -> Vec<String> {
+        let text: Vec<char> = modalias.chars().collect();
+        let mut modules: Vec<String> = Vec::new();
+        for (pattern, module) in &self.aliases {
+            if glob_match(pattern, &text) && !modules.contains(module) {
+                modules.push(module.clone());
+            }
+        }
+        modules
+    }
+}
+
+/// If `p[pi]` starts a single-character token (`?`, a `[...]` class or a literal) matching `c`,
+/// the index after that token.
+fn match_char(p: &[char], pi: usize, c: char) -> Option<usize> {
+    match p[pi] {
+        '?' => Some(pi + 1),
+        '[' => {
+            let mut i = pi + 1;
+            let negated = matches!(p.get(i), Some('!') | Some('^'));
+            if negated {
+                i += 1;
+            }
+            let mut found = false;
+            let mut first = true;
+            while i < p.len() && (first || p[i] != ']') {
+                first = false;
+                if p.get(i + 1) == Some(&'-') && p.get(i + 2).is_some_and(|&hi| hi != ']') {
+                    found |= (p[i]..=p[i + 2]).contains(&c);
+                    i += 3;
+                } else {
+                    found |= p[i] == c;
+                    i += 1;
+                }
+            }
+            if i >= p.len() {
+                // No closing bracket: a literal '['
+                return (c == '[').then_some(pi + 1);
+            }
+            (found != negated).then_some(i + 1)
+        }
+        literal => (literal == c).then_some(pi + 1),
+    }
+}
+
+/// Shell-style matching with `*`, `?` and `[...]` classes, as used by modalias patterns
+/// (e.g. "usb:v0BDAp8179d*dc*dsc*dp*ic*isc*ip*in*" or "pci:v00008086d*sv*sd*bc03sc[0-2]*i*").
+pub fn glob_match(p: &[char], t: &[char]) -> bool {
+    let (mut pi, mut ti) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && p[pi] == '*' {
+            backtrack = Some((pi, ti));
+            pi += 1;
+        } else if pi < p.len()
+            && let Some(next) = match_char(p, pi, t[ti])
+        {
+            pi = next;
+            ti += 1;
+        } else if let Some((star, matched)) = backtrack {
+            pi = star + 1;
+            ti = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+#[derive(Debug, Clone)]
+pub struct PciDevice {
+    pub slot: String,
+    pub vendor_id: u16,
+    pub device_id: u16,
+    pub subsystem: Option<(u16, u16)>,
+    /// 24-bit class code: base class, subclass, programming interface.
+    pub class: u32,
+    pub vendor_name: Option<String>,
+    pub device_name: Option<String>,
+    pub class_name: Option<String>,
+    pub driver: Option<String>,
+    pub modules: Vec<String>,
+    pub iommu_group: Option<String>,
+    pub link: Option<String>,
+    pub max_link: Option<String>,
+}
+
+impl PciDevice {
+    pub fn name(&self) -> String {
+        let vendor = self.vendor_name.clone().unwrap_or_else(|| format!("{:04x}", self.vendor_id));
+        let device = self.device_name.clone().unwrap_or_else(|| format!("{:04x}", self.device_id));
+        format!("{} {} [{:04x}:{:04x}]", vendor, device, self.vendor_id, self.device_id)
+    }
+
+    /// Missing drivers matter except on bridges, which typically work without one.
+    pub fn needs_driver(&self) -> bool {
+        self.driver.is_none() && self.class >> 16 != PCI_BRIDGE_CLASS
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UsbInterface {
+    pub name: String,
+    pub class: u8,
+    pub driver: Option<String>,
+    pub modules: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UsbDevice {
+    /// sysfs name such as "1-2.3".
+    pub port: String,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub class: u8,
+    pub speed_mbps: Option<String>,
+    pub vendor_name: Option<String>,
+    pub product_name: Option<String>,
+    pub serial: Option<String>,
+    pub interfaces: Vec<UsbInterface>,
+}
+
+impl UsbDevice {
+    pub fn name(&self) -> String {
+        let vendor = self.vendor_name.clone().unwrap_or_else(|| format!("{:04x}", self.vendor_id));
+        let product = self.product_name.clone().unwrap_or_else(|| format!("{:04x}", self.product_id));
+        format!("{} {} [{:04x}:{:04x}]", vendor, product, self.vendor_id, self.product_id)
+    }
+
+    pub fn needs_driver(&self) -> bool {
+        self.interfaces.iter().any(|i| i.driver.is_none())
+    }
+}
+
+fn read_hex<T: TryFrom<u32>>(path: &Path) -> Option<T> {
+    let value = sysfs::read_trimmed(path)?;
+    u32::from_str_radix(value.trim_start_matches("0x"), 16).ok()?.try_into().ok()
+}
+
+fn pci_link(dev: &Path, prefix: &str) -> Option<String> {
+    let speed = sysfs::read_trimmed(dev.join(format!("{}_link_speed", prefix))).filter(|s| !s.starts_with("Unknown"))?;
+    match sysfs::read_trimmed(dev.join(format!("{}_link_width", prefix))).filter(|w| w != "0") {
+        Some(width) => Some(format!("{} x{}", speed, width)),
+        None => Some(speed),
+    }
+}
+
+pub fn scan_pci(db: &IdDatabase, aliases: &ModuleAliases) -> Vec<PciDevice> {
+    sysfs::list_dir_prefixed(PCI_DEVICES_DIR, "")
+        .into_iter()
+        .filter_map(|dev| {
+            let vendor_id = read_hex(&dev.join("vendor"))?;
+            let device_id = read_hex(&dev.join("device"))?;
+            let class: u32 = read_hex(&dev.join("class")).unwrap_or(0);
+            let subsystem = match (read_hex(&dev.join("subsystem_vendor")), read_hex(&dev.join("subsystem_device"))) {
+                (Some(v), Some(d)) => Some((v, d)),
+                _ => None,
+            };
+            let modalias = sysfs::read_trimmed(dev.join("modalias")).unwrap_or_default();
+            Some(PciDevice {
+                slot: dev.file_name()?.to_string_lossy().to_string(),
+                vendor_name: db.vendor_name(vendor_id).map(|s| s.to_string()),
+                device_name: db.device_name(vendor_id, device_id).map(|s| s.to_string()),
+                class_name: db.class_name((class >> 16) as u8, (class >> 8) as u8).map(|s| s.to_string()),
+                vendor_id,
+                device_id,
+                subsystem,
+                class,
+                driver: sysfs::link_name(dev.join("driver")),
+                modules: aliases.candidates(&modalias),
+                iommu_group: sysfs::link_name(dev.join("iommu_group")),
+                link: pci_link(&dev, "current"),
+                max_link: pci_link(&dev, "max"),
+            })
+        })
+        .collect()
+}
+
+pub fn scan_usb(db: &IdDatabase, aliases: &ModuleAliases) -> Vec<UsbDevice> {
+    sysfs::list_dir_prefixed(USB_DEVICES_DIR, "")
+        .into_iter()
+        .filter_map(|dev| {
+            let port = dev.file_name()?.to_string_lossy().to_string();
+            // Interfaces ("1-2:1.0") are read through their device; root hubs ("usb1") are skipped
+            if port.contains(':') || port.starts_with("usb") {
+                return None;
+            }
+            let vendor_id = read_hex(&dev.join("idVendor"))?;
+            let product_id = read_hex(&dev.join("idProduct"))?;
+            let interfaces = sysfs::list_dir_prefixed(&dev, &format!("{}:", port))
+                .into_iter()
+                .filter_map(|intf| {
+                    let modalias = sysfs::read_trimmed(intf.join("modalias")).unwrap_or_default();
+                    Some(UsbInterface {
+                        name: intf.file_name()?.to_string_lossy().to_string(),
+                        class: read_hex(&intf.join("bInterfaceClass")).unwrap_or(0),
+                        driver: sysfs::link_name(intf.join("driver")),
+                        modules: aliases.candidates(&modalias),
+                    })
+                })
+                .collect();
+            Some(UsbDevice {
+                vendor_name: db
+                    .vendor_name(vendor_id)
+                    .map(|s| s.to_string())
+                    .or_else(|| sysfs::read_trimmed(dev.join("manufacturer"))),
+                product_name: db
+                    .device_name(vendor_id, product_id)
+                    .map(|s| s.to_string())
+                    .or_else(|| sysfs::read_trimmed(dev.join("product"))),
+                serial: sysfs::read_trimmed(dev.join("serial")).filter(|s| !s.is_empty()),
+                class: read_hex(&dev.join("bDeviceClass")).unwrap_or(0),
+                speed_mbps: sysfs::read_trimmed(dev.join("speed")),
+                port,
+                vendor_id,
+                product_id,
+                interfaces,
+            })
+        })
+        .collect()
+}
+
+/// Scan both buses, loading the hwdata name databases and module aliases once.
+pub fn read_devices() -> (Vec<PciDevice>, Vec<UsbDevice>) {
+    let aliases = ModuleAliases::load();
+    let pci = scan_pci(&IdDatabase::load(&PCI_IDS_PATHS), &aliases);
+    let usb = scan_usb(&IdDatabase::load(&USB_IDS_PATHS), &aliases);
+    (pci, usb)
+}
+
+/// Full per-device listing for the hardware log.
+pub fn log_text(pci: &[PciDevice], usb: &[UsbDevice]) -> String {
+    let mut out = String::from("[pci devices]\n");
+    for d in pci {
+        out.push_str(&format!("{} {} ({:06x}", d.slot, d.name(), d.class));
+        if let Some(class) = &d.class_name {
+            out.push_str(&format!(" {}", class));
+        }
+        out.push_str(")\n");
+        if let Some((v, s)) = d.subsystem {
+            out.push_str(&format!("  subsystem: {:04x}:{:04x}\n", v, s));
+        }
+        out.push_str(&format!("  driver: {}\n", d.driver.as_deref().unwrap_or("none")));
+        if !d.modules.is_empty() {
+            out.push_str(&format!("  modules: {}\n", d.modules.join(", ")));
+        }
+        if let Some(group) = &d.iommu_group {
+            out.push_str(&format!("  iommu group: {}\n", group));
+        }
+        if let Some(link) = &d.link {
+            out.push_str(&format!("  link: {} (max {})\n", link, d.max_link.as_deref().unwrap_or("unknown")));
+        }
+    }
+    out.push_str("\n[usb devices]\n");
+    for d in usb {
+        out.push_str(&format!("{} {} class {:02x}", d.port, d.name(), d.class));
+        if let Some(speed) = &d.speed_mbps {
+            out.push_str(&format!(", {} Mb/s", speed));
+        }
+        out.push('\n');
+        for intf in &d.interfaces {
+            out.push_str(&format!(
+                "  {} class {:02x} driver: {}",
+                intf.name,
+                intf.class,
+                intf.driver.as_deref().unwrap_or("none")
+            ));
+            if !intf.modules.is_empty() {
+                out.push_str(&format!(" (modules: {})", intf.modules.join(", ")));
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn candidate_hint(modules: &[String]) -> String {
+    if modules.is_empty() {
+        "no matching kernel module".to_string()
+    } else {
+        format!("candidate modules: {}", modules.join(", "))
+    }
+}
+
+/// Device counts plus every device that has no driver bound.
+pub fn print_summary(pci: &[PciDevice], usb: &[UsbDevice]) {
+    println!("\n🔌 Devices: {} PCI, {} USB", pci.len(), usb.len());
+    for d in pci.iter().filter(|d| d.needs_driver()) {
+        println!("  ⚠️ No driver: {} {} ({})", d.slot, d.name(), candidate_hint(&d.modules));
+    }
+    for d in usb.iter().filter(|d| d.needs_driver()) {
+        let mut modules: Vec<String> = Vec::new();
+        for intf in d.interfaces.iter().filter(|i| i.driver.is_none()) {
+            modules.extend(intf.modules.iter().filter(|m| !modules.contains(m)).cloned().collect::<Vec<_>>());
+        }
+        println!("  ⚠️ No driver: USB {} {} ({})", d.port, d.name(), candidate_hint(&modules));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        glob_match(&pattern.chars().collect::<Vec<_>>(), &text.chars().collect::<Vec<_>>())
+    }
+
+    const PCI_IDS: &str = "\
+# List of PCI ID's
+#
+10de  NVIDIA Corporation
+\t2684  AD102 [GeForce RTX 4090]
+\t\t1458 4104  GeForce RTX 4090 Gaming OC 24G
+8086  Intel Corporation
+\ta36d  Cannon Lake PCH USB 3.1 xHCI Host Controller
+
+# List of known device classes, subclasses and programming interfaces
+C 03  Display controller
+\t00  VGA compatible controller
+\t\t00  VGA controller
+\t02  3D controller
+C 0c  Serial bus controller
+\t03  USB controller
+\t\t30  XHCI
+";
+
+    const USB_IDS: &str = "\
+046d  Logitech, Inc.
+\tc52b  Unifying Receiver
+0bda  Realtek Semiconductor Corp.
+\t8179  RTL8188EUS 802.11n Wireless Network Adapter
+C 09  Hub
+\t00  Unused
+AT 0409  English (US)
+HID 00  Undefined
+R 00  Undefined
+";
+
+    #[test]
+    fn pci_ids_database() {
+        let db = IdDatabase::parse(PCI_IDS);
+        assert_eq!(db.vendor_name(0x10DE), Some("NVIDIA Corporation"));
+        assert_eq!(db.device_name(0x10DE, 0x2684), Some("AD102 [GeForce RTX 4090]"));
+        assert_eq!(db.device_name(0x8086, 0xA36D), Some("Cannon Lake PCH USB 3.1 xHCI Host Controller"));
+        assert_eq!(db.device_name(0x10DE, 0x1458), None, "subsystem lines are not devices");
+        assert_eq!(db.class_name(0x03, 0x00), Some("VGA compatible controller"));
+        assert_eq!(db.class_name(0x0C, 0x03), Some("USB controller"));
+        assert_eq!(db.class_name(0x03, 0x80), Some("Display controller"), "unknown subclass falls back to the class");
+        assert_eq!(db.class_name(0x02, 0x00), None);
+    }
+
+    #[test]
+    fn usb_ids_database() {
+        let db = IdDatabase::parse(USB_IDS);
+        assert_eq!(db.vendor_name(0x046D), Some("Logitech, Inc."));
+        assert_eq!(db.device_name(0x0BDA, 0x8179), Some("RTL8188EUS 802.11n Wireless Network Adapter"));
+        assert_eq!(db.class_name(0x09, 0x00), Some("Unused"));
+        assert_eq!(db.vendors.len(), 2, "AT, HID and R sections are skipped");
+    }
+
+    #[test]
+    fn glob_wildcards() {
+        assert!(matches("usb:v0BDAp8179d*dc*dsc*dp*ic*isc*ip*in*", "usb:v0BDAp8179d0000dc00dsc00dp00icFFiscFFipFFin00"));
+        assert!(!matches("usb:v0BDAp8179d*", "usb:v0BDAp8178d0000"));
+        assert!(matches("pci:v*d*sv*sd*bc0Csc03i30*", "pci:v00008086d0000A36Dsv00001028sd0000085Cbc0Csc03i30"));
+        assert!(matches("a?c", "abc"));
+        assert!(!matches("a?c", "ac"));
+        assert!(matches("*", ""));
+    }
+
+    #[test]
+    fn glob_bracket_classes() {
+        assert!(matches("usb:v05ACp*d0[0-2]*", "usb:v05ACp12A8d0150dc00"));
+        assert!(!matches("usb:v05ACp*d0[0-2]*", "usb:v05ACp12A8d0350dc00"));
+        assert!(matches("pci:v*d*sv*sd*bc03sc[0-2]*", "pci:v000010DEd00002684sv00001458sd00004104bc03sc00i00"));
+        assert!(matches("d[0-9A-F]x", "dBx"));
+        assert!(matches("d[!0-9]x", "dzx"));
+        assert!(!matches("d[!0-9]x", "d5x"));
+        assert!(matches("d[]a]x", "d]x"));
+        assert!(matches("d[a-]x", "d-x"));
+        assert!(matches("d[ab", "d[ab"), "an unclosed bracket is literal");
+    }
+}
@@ -0,0 +1,255 @@
+// dmi_info.rs
+// System identity from DMI/SMBIOS: vendor, model, BIOS, chassis and the derived form factor
+
+use std::fs;
+use std::path::Path;
+
+use super::power_status;
+use super::smbios;
+use super::sysfs;
+
+const DMI_ID_DIR: &str = "/sys/class/dmi/id";
+const SMBIOS_BIOS: u8 = 0;
+const SMBIOS_SYSTEM: u8 = 1;
+const SMBIOS_BOARD: u8 = 2;
+const SMBIOS_CHASSIS: u8 = 3;
+
+/// Product names of handheld PCs that report themselves as notebooks.
+const HANDHELD_PRODUCTS: [&str; 8] = ["jupiter", "galileo", "rog ally", "legion go", "ayaneo", "gpd win", "onexplayer", "claw"];
+/// DMI vendor/product substrings of virtual machines and their systemd-detect-virt identifiers.
+const VM_VENDORS: [(&str, &str); 10] = [
+    ("kvm", "kvm"),
+    ("amazon ec2", "amazon"),
+    ("qemu", "qemu"),
+    ("vmware", "vmware"),
+    ("virtualbox", "oracle"),
+    ("innotek", "oracle"),
+    ("xen", "xen"),
+    ("bochs", "bochs"),
+    ("parallels", "parallels"),
+    ("microsoft corporation virtual", "microsoft"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FormFactor {
+    Laptop,
+    Desktop,
+    Server,
+    VirtualMachine,
+    Handheld,
+    Unknown,
+}
+
+impl FormFactor {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FormFactor::Laptop => "laptop",
+            FormFactor::Desktop => "desktop",
+            FormFactor::Server => "server",
+            FormFactor::VirtualMachine => "virtual machine",
+            FormFactor::Handheld => "handheld",
+            FormFactor::Unknown => "unknown",
+        }
+    }
+
+    /// Whether the machine usually runs on battery and is thermally constrained.
+    pub fn is_portable(&self) -> bool {
+        matches!(self, FormFactor::Laptop | FormFactor::Handheld)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DmiInfo {
+    pub sys_vendor: Option<String>,
+    pub product_name: Option<String>,
+    pub product_version: Option<String>,
+    pub board_vendor: Option<String>,
+    pub board_name: Option<String>,
+    pub bios_vendor: Option<String>,
+    pub bios_version: Option<String>,
+    pub bios_date: Option<String>,
+    /// SMBIOS chassis type code (3 = desktop, 10 = notebook, 23 = rack mount, ...).
+    pub chassis_type: Option<u8>,
+}
+
+/// DMI strings firmware vendors fill with placeholders.
+fn meaningful(value: Option<String>) -> Option<String> {
+    value.filter(|v| {
+        let lower = v.to_lowercase();
+        !v.is_empty() && !lower.contains("to be filled") && lower != "default string" && lower != "system product name"
+    })
+}
+
+fn read_id(name: &str) -> Option<String> {
+    meaningful(sysfs::read_trimmed(Path::new(DMI_ID_DIR).join(name)))
+}
+
+/// Fill fields that sysfs did not provide from the raw SMBIOS table (readable by root only).
+fn fill_from_table(info: &mut DmiInfo) {
+    for s in smbios::read_table() {
+        let text = |offset: usize| meaningful(s.string(offset).map(|v| v.to_string()));
+        match s.kind {
+            SMBIOS_BIOS => {
+                info.bios_vendor = info.bios_vendor.take().or_else(|| text(0x04));
+                info.bios_version = info.bios_version.take().or_else(|| text(0x05));
+                info.bios_date = info.bios_date.take().or_else(|| text(0x08));
+            }
+            SMBIOS_SYSTEM => {
+                info.sys_vendor = info.sys_vendor.take().or_else(|| text(0x04));
+                info.product_name = info.product_name.take().or_else(|| text(0x05));
+                info.product_version = info.product_version.take().or_else(|| text(0x06));
+            }
+            SMBIOS_BOARD => {
+                info.board_vendor = info.board_vendor.take().or_else(|| text(0x04));
+                info.board_name = info.board_name.take().or_else(|| text(0x05));
+            }
+            // Bit 7 of the chassis type is the chassis lock flag
+            SMBIOS_CHASSIS => info.chassis_type = info.chassis_type.or_else(|| s.byte(0x05).map(|t| t & 0x7F)),
+            _ => {}
+        }
+    }
+}
+
+pub fn read_dmi_info() -> DmiInfo {
+    let mut info = DmiInfo {
+        sys_vendor: read_id("sys_vendor"),
+        product_name: read_id("product_name"),
+        product_version: read_id("product_version"),
+        board_vendor: read_id("board_vendor"),
+        board_name: read_id("board_name"),
+        bios_vendor: read_id("bios_vendor"),
+        bios_version: read_id("bios_version"),
+        bios_date: read_id("bios_date"),
+        chassis_type: sysfs::read_u64(Path::new(DMI_ID_DIR).join("chassis_type")).map(|t| t as u8),
+    };
+    if sysfs::is_root() {
+        fill_from_table(&mut info);
+    }
+    info
+}
+
+pub fn chassis_name(code: u8) -> &'static str {
+    match code {
+        1 => "Other",
+        3 => "Desktop",
+        4 => "Low Profile Desktop",
+        5 => "Pizza Box",
+        6 => "Mini Tower",
+        7 => "Tower",
+        8 => "Portable",
+        9 => "Laptop",
+        10 => "Notebook",
+        11 => "Hand Held",
+        12 => "Docking Station",
+        13 => "All in One",
+        14 => "Sub Notebook",
+        15 => "Space-saving",
+        16 => "Lunch Box",
+        17 => "Main Server Chassis",
+        23 => "Rack Mount Chassis",
+        24 => "Sealed-case PC",
+        25 => "Multi-system Chassis",
+        28 => "Blade",
+        29 => "Blade Enclosure",
+        30 => "Tablet",
+        31 => "Convertible",
+        32 => "Detachable",
+        33 => "IoT Gateway",
+        34 => "Embedded PC",
+        35 => "Mini PC",
+        36 => "Stick PC",
+        _ => "Unknown",
+    }
+}
+
+impl DmiInfo {
+    /// The hypervisor named by the DMI strings, as a systemd-detect-virt identifier.
+    pub fn virtual_vendor(&self) -> Option<&'static str> {
+        let ident = format!(
+            "{} {} {}",
+            self.sys_vendor.as_deref().unwrap_or(""),
+            self.product_name.as_deref().unwrap_or(""),
+            self.bios_vendor.as_deref().unwrap_or("")
+        )
+        .to_lowercase();
+        VM_VENDORS.iter().find(|(needle, _)| ident.contains(needle)).map(|(_, id)| *id)
+    }
+
+    pub fn is_virtual(&self) -> bool {
+        self.virtual_vendor().is_some()
+    }
+
+    pub fn form_factor(&self) -> FormFactor {
+        let product = format!("{} {}", self.product_name.as_deref().unwrap_or(""), self.product_version.as_deref().unwrap_or(""))
+            .to_lowercase();
+        if HANDHELD_PRODUCTS.iter().any(|p| product.contains(p)) {
+            return FormFactor::Handheld;
+        }
+        if self.is_virtual() {
+            return FormFactor::VirtualMachine;
+        }
+        match self.chassis_type {
+            Some(8 | 9 | 10 | 14 | 31 | 32) => FormFactor::Laptop,
+            Some(11 | 30) => FormFactor::Handheld,
+            Some(17 | 23 | 25 | 28 | 29) => FormFactor::Server,
+            Some(3..=7 | 13 | 15 | 16 | 24 | 34..=36) => FormFactor::Desktop,
+            // "Other"/"Unknown" chassis: a system battery is the best remaining hint
+            _ => {
+                let has_battery = power_status::scan_power_supplies()
+                    .iter()
+                    .any(|s| s.battery.as_ref().is_some_and(|b| !b.peripheral));
+                if has_battery {
+                    FormFactor::Laptop
+                } else if fs::metadata(DMI_ID_DIR).is_ok() {
+                    FormFactor::Desktop
+                } else {
+                    FormFactor::Unknown
+                }
+            }
+        }
+    }
+}
+
+/// The machine's form factor; used by power and kernel recommendations.
+pub fn form_factor() -> FormFactor {
+    read_dmi_info().form_factor()
+}
+
+/// Print the system identity section used by `nephyra hardware`.
+pub fn print_details(info: &DmiInfo) {
+    let join = |a: &Option<String>, b: &Option<String>| {
+        [a.as_deref(), b.as_deref()].into_iter().flatten().collect::<Vec<&str>>().join(" ")
+    };
+    let system = join(&info.sys_vendor, &info.product_name);
+    println!("\n🏷️ System: {}", if system.is_empty() { "Unknown" } else { &system });
+    if let Some(version) = &info.product_version {
+        println!("   Version: {}", version);
+    }
+    let board = join(&info.board_vendor, &info.board_name);
+    if !board.is_empty() {
+        println!("   Board: {}", board);
+    }
+    if info.bios_version.is_some() || info.bios_date.is_some() {
+        println!(
+            "   BIOS: {} {} ({})",
+            info.bios_vendor.as_deref().unwrap_or(""),
+            info.bios_version.as_deref().unwrap_or("unknown"),
+            info.bios_date.as_deref().unwrap_or("unknown date")
+        );
+    }
+    if let Some(code) = info.chassis_type {
+        println!("   Chassis: {}", chassis_name(code));
+    }
+    println!("   Form factor: {}", info.form_factor().label());
+}
+
+/// One-line system identity for summaries.
+pub fn summary(info: &DmiInfo) -> String {
+    format!(
+        "System: {} {} ({}) | BIOS: {}",
+        info.sys_vendor.as_deref().unwrap_or("Unknown"),
+        info.product_name.as_deref().unwrap_or(""),
+        info.form_factor().label(),
+        info.bios_version.as_deref().unwrap_or("unknown")
+    )
+}
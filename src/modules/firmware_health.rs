@@ -0,0 +1,457 @@
+// firmware_health.rs
+// Failed firmware loads from the kernel log, mapped to providing packages, plus fwupd device firmware
+
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Read};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::process::Command;
+
+use serde_json::Value;
+
+use super::os_release;
+use super::virtualization;
+
+const KMSG_PATH: &str = "/dev/kmsg";
+const FIRMWARE_DIRS: [&str; 2] = ["/lib/firmware/updates", "/lib/firmware"];
+/// Compressed variants the kernel's firmware loader accepts.
+const FIRMWARE_SUFFIXES: [&str; 3] = ["", ".zst", ".xz"];
+
+/// Firmware path prefix → providing package on (Arch, Debian, Fedora, openSUSE).
+const FIRMWARE_PACKAGES: [(&str, &str, &str, &str, &str); 20] = [
+    ("iwlwifi-", "linux-firmware-intel", "firmware-iwlwifi", "iwlwifi-mvm-firmware", "kernel-firmware-iwlwifi"),
+    ("intel/ibt-", "linux-firmware-intel", "firmware-iwlwifi", "linux-firmware", "kernel-firmware-bluetooth"),
+    ("intel/sof", "sof-firmware", "firmware-sof-signed", "alsa-sof-firmware", "sof-firmware"),
+    ("i915/", "linux-firmware-intel", "firmware-misc-nonfree", "intel-gpu-firmware", "kernel-firmware-i915"),
+    ("xe/", "linux-firmware-intel", "firmware-misc-nonfree", "intel-gpu-firmware", "kernel-firmware-i915"),
+    ("amdgpu/", "linux-firmware-amdgpu", "firmware-amd-graphics", "amd-gpu-firmware", "kernel-firmware-amdgpu"),
+    ("radeon/", "linux-firmware-radeon", "firmware-amd-graphics", "amd-gpu-firmware", "kernel-firmware-radeon"),
+    ("amd-ucode/", "linux-firmware-amdgpu", "amd64-microcode", "linux-firmware", "kernel-firmware-amdgpu"),
+    ("nvidia/", "linux-firmware-nvidia", "firmware-misc-nonfree", "nvidia-gpu-firmware", "kernel-firmware-nvidia"),
+    ("rtw88/", "linux-firmware-realtek", "firmware-realtek", "realtek-firmware", "kernel-firmware-realtek"),
+    ("rtw89/", "linux-firmware-realtek", "firmware-realtek", "realtek-firmware", "kernel-firmware-realtek"),
+    ("rtl_nic/", "linux-firmware-realtek", "firmware-realtek", "realtek-firmware", "kernel-firmware-realtek"),
+    ("rtl_bt/", "linux-firmware-realtek", "firmware-realtek", "realtek-firmware", "kernel-firmware-bluetooth"),
+    ("rtlwifi/", "linux-firmware-realtek", "firmware-realtek", "realtek-firmware", "kernel-firmware-realtek"),
+    ("ath10k/", "linux-firmware-atheros", "firmware-atheros", "atheros-firmware", "kernel-firmware-ath10k"),
+    ("ath11k/", "linux-firmware-atheros", "firmware-atheros", "atheros-firmware", "kernel-firmware-ath11k"),
+    ("ath12k/", "linux-firmware-atheros", "firmware-atheros", "atheros-firmware", "kernel-firmware-ath12k"),
+    ("qca/", "linux-firmware-atheros", "firmware-atheros", "atheros-firmware", "kernel-firmware-bluetooth"),
+    ("brcm/", "linux-firmware-broadcom", "firmware-brcm80211", "brcmfmac-firmware", "kernel-firmware-brcm"),
+    ("mediatek/", "linux-firmware-mediatek", "firmware-misc-nonfree", "mt7xxx-firmware", "kernel-firmware-mediatek"),
+];
+/// Catch-all packages when no prefix matches.
+const FALLBACK_PACKAGES: (&str, &str, &str, &str) = ("linux-firmware", "firmware-linux-nonfree", "linux-firmware", "kernel-firmware-all");
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FirmwareState {
+    /// Still missing from the firmware directories.
+    Missing,
+    /// Installed since the failed load; a reboot or driver reload picks it up.
+    PresentNow,
+    /// A versioned file the driver probes before falling back to an older one that exists.
+    Optional,
+}
+
+#[derive(Debug, Clone)]
+pub struct FirmwareFailure {
+    pub driver: Option<String>,
+    pub device: Option<String>,
+    pub file: String,
+    pub state: FirmwareState,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FwupdDevice {
+    pub id: String,
+    pub name: String,
+    pub vendor: Option<String>,
+    pub version: Option<String>,
+    pub updatable: bool,
+    /// Versions newer than the installed one, from the locally cached metadata.
+    pub updates: Vec<String>,
+}
+
+/// Kernel log lines: /dev/kmsg first, then journalctl and dmesg (both may need privileges).
+fn kernel_log() -> Vec<String> {
+    if let Ok(mut kmsg) = OpenOptions::new().read(true).custom_flags(libc::O_NONBLOCK).open(KMSG_PATH) {
+        let mut lines = Vec::new();
+        let mut buf = vec![0u8; 8192];
+        // Each read returns one record: "prio,seq,usec,flags;message"
+        loop {
+            match kmsg.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    let record = String::from_utf8_lossy(&buf[..n]);
+                    if let Some((_, message)) = record.split_once(';') {
+                        lines.push(message.lines().next().unwrap_or("").to_string());
+                    }
+                }
+                // EPIPE means records were overwritten while reading; keep going
+                Err(e) if e.kind() == ErrorKind::BrokenPipe => continue,
+                Err(_) => break,
+            }
+        }
+        if !lines.is_empty() {
+            return lines;
+        }
+    }
+    for (cmd, args) in [("journalctl", &["-k", "-b", "-o", "cat", "--no-pager"][..]), ("dmesg", &[][..])] {
+        if let Ok(output) = Command::new(cmd).args(args).output()
+            && output.status.success()
+        {
+            return String::from_utf8_lossy(&output.stdout).lines().map(|l| l.to_string()).collect();
+        }
+    }
+    Vec::new()
+}
+
+fn firmware_exists(file: &str) -> bool {
+    FIRMWARE_DIRS
+        .iter()
+        .any(|dir| FIRMWARE_SUFFIXES.iter().any(|suffix| Path::new(dir).join(format!("{}{}", file, suffix)).exists()))
+}
+
+/// Whether another version of a versioned firmware file ("iwlwifi-so-a0-gf-a0-89.ucode") is installed.
+fn older_version_exists(file: &str) -> bool {
+    older_version_in(&FIRMWARE_DIRS, file)
+}
+
+fn older_version_in<P: AsRef<Path>>(dirs: &[P], file: &str) -> bool {
+    let (stem, ext) = file.rsplit_once('.').unwrap_or((file, ""));
+    let Some((base, version)) = stem.rsplit_once('-') else {
+        return false;
+    };
+    if version.is_empty() || !version.chars().all(|c| c.is_ascii_digit()) {
+        return false;
+    }
+    let (subdir, prefix) = match base.rsplit_once('/') {
+        Some((dir, name)) => (Some(dir), name),
+        None => (None, base),
+    };
+    dirs.iter().any(|dir| {
+        let dir = subdir.map(|s| dir.as_ref().join(s)).unwrap_or_else(|| dir.as_ref().to_path_buf());
+        std::fs::read_dir(dir)
+            .map(|rd| {
+                rd.flatten().any(|e| {
+                    let name = e.file_name().to_string_lossy().to_string();
+                    name.starts_with(&format!("{}-", prefix)) && name.contains(&format!(".{}", ext))
+                })
+            })
+            .unwrap_or(false)
+    })
+}
+
+/// Extract failed firmware loads from kernel log lines, one entry per file.
+pub fn parse_firmware_failures(lines: &[String]) -> Vec<(Option<String>, Option<String>, String)> {
+    let mut failures: Vec<(Option<String>, Option<String>, String)> = Vec::new();
+    for line in lines {
+        // "iwlwifi 0000:00:14.3: Direct firmware load for X failed with error -2"
+        // "amdgpu 0000:03:00.0: firmware: failed to load X (-2)"
+        let (prefix, file) = if let Some((prefix, rest)) = line.split_once("Direct firmware load for ") {
+            (prefix, rest.split_whitespace().next())
+        } else if let Some((prefix, rest)) = line.split_once("firmware: failed to load ") {
+            (prefix, rest.split_whitespace().next())
+        } else {
+            continue;
+        };
+        let Some(file) = file else {
+            continue;
+        };
+        if failures.iter().any(|(_, _, f)| f == file) {
+            continue;
+        }
+        // dmesg prefixes "[    3.141592] "; journalctl and /dev/kmsg do not
+        let prefix = prefix.trim_start().strip_prefix('[').and_then(|p| p.split_once(']')).map_or(prefix, |(_, rest)| rest);
+        let mut origin = prefix.trim().trim_end_matches(':').split_whitespace();
+        let driver = origin.next().map(|d| d.to_string());
+        let device = origin.next().map(|d| d.trim_end_matches(':').to_string());
+        failures.push((driver, device, file.to_string()));
+    }
+    failures
+}
+
+pub fn read_failures() -> Vec<FirmwareFailure> {
+    parse_firmware_failures(&kernel_log())
+        .into_iter()
+        .map(|(driver, device, file)| {
+            let state = if firmware_exists(&file) {
+                FirmwareState::PresentNow
+            } else if older_version_exists(&file) {
+                FirmwareState::Optional
+            } else {
+                FirmwareState::Missing
+            };
+            FirmwareFailure { driver, device, file, state }
+        })
+        .collect()
+}
+
+/// The package that ships `file` on this distribution, plus the install command when the
+/// distribution is one we know the package names for.
+pub fn providing_package(file: &str) -> (String, Option<String>) {
+    let release = os_release::read();
+    let entry = FIRMWARE_PACKAGES.iter().find(|(prefix, ..)| file.starts_with(prefix));
+    let (arch, debian, fedora, suse) = entry.map(|e| (e.1, e.2, e.3, e.4)).unwrap_or(FALLBACK_PACKAGES);
+    let (package, install) = if release.is_like("arch") {
+        (arch, "sudo pacman -S")
+    } else if release.is_like("debian") || release.is_like("ubuntu") {
+        (debian, "sudo apt install")
+    } else if release.is_like("fedora") || release.is_like("rhel") {
+        (fedora, "sudo dnf install")
+    } else if release.is_like("suse") || release.id.starts_with("opensuse") {
+        (suse, "sudo zypper install")
+    } else {
+        // Upstream names, which most distributions keep but not all
+        return (arch.to_string(), None);
+    };
+    (package.to_string(), Some(format!("{} {}", install, package)))
+}
+
+/// Updates from the local metadata cache only; `nephyra` never triggers a network refresh.
+const FWUPD_UPDATES_ARGS: [&str; 4] = ["get-updates", "--json", "--no-metadata-check", "--no-unreported-check"];
+
+fn fwupdmgr_json(args: &[&str]) -> Option<Value> {
+    // Exit status 2 means "nothing to do"; the JSON on stdout is valid either way
+    let output = Command::new("fwupdmgr").args(args).output().ok()?;
+    serde_json::from_slice(&output.stdout).ok()
+}
+
+/// Parse `fwupdmgr get-devices --json` and merge releases from `get-updates --json`.
+pub fn parse_fwupd(devices: &Value, updates: Option<&Value>) -> Vec<FwupdDevice> {
+    let mut result: Vec<FwupdDevice> = devices["Devices"]
+        .as_array()
+        .map(|list| {
+            list.iter()
+                .map(|d| FwupdDevice {
+                    id: d["DeviceId"].as_str().unwrap_or_default().to_string(),
+                    name: d["Name"].as_str().unwrap_or("Unknown device").to_string(),
+                    vendor: d["Vendor"].as_str().map(|v| v.to_string()),
+                    version: d["Version"].as_str().map(|v| v.to_string()),
+                    updatable: d["Flags"].as_array().is_some_and(|f| f.iter().any(|f| f == "updatable")),
+                    updates: Vec::new(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    for d in updates.and_then(|u| u["Devices"].as_array()).into_iter().flatten() {
+        let id = d["DeviceId"].as_str().unwrap_or_default();
+        let versions: Vec<String> = d["Releases"]
+            .as_array()
+            .map(|r| r.iter().filter_map(|r| r["Version"].as_str().map(|v| v.to_string())).collect())
+            .unwrap_or_default();
+        if let Some(device) = result.iter_mut().find(|dev| dev.id == id) {
+            device.updates = versions;
+        }
+    }
+    result
+}
+
+/// Device firmware known to fwupd, optionally with updates from the local metadata cache (no network refresh).
+pub fn read_fwupd(check_updates: bool) -> Option<Vec<FwupdDevice>> {
+    let devices = fwupdmgr_json(&["get-devices", "--json", "--no-unreported-check"])?;
+    let updates = if check_updates {
+        fwupdmgr_json(&FWUPD_UPDATES_ARGS)
+    } else {
+        None
+    };
+    Some(parse_fwupd(&devices, updates.as_ref()))
+}
+
+/// Entry point for `nephyra firmware`.
+pub fn run() {
+    println!("🧩 Firmware health");
+    if let Some(container) = virtualization::detect_container() {
+        println!("ℹ️ Running inside a {} container: firmware is loaded by the host kernel.", container);
+        return;
+    }
+    let failures = read_failures();
+    let missing: Vec<&FirmwareFailure> = failures.iter().filter(|f| f.state == FirmwareState::Missing).collect();
+    if failures.is_empty() {
+        println!("✅ No failed firmware loads in the kernel log.");
+    }
+    for failure in &failures {
+        let origin = match (&failure.driver, &failure.device) {
+            (Some(driver), Some(device)) => format!("{} {}", driver, device),
+            (Some(driver), None) => driver.clone(),
+            _ => "unknown driver".to_string(),
+        };
+        match failure.state {
+            FirmwareState::Missing => {
+                println!("❌ {} ({}) is missing", failure.file, origin);
+                match providing_package(&failure.file) {
+                    (package, Some(install)) => println!("   📦 Provided by {}: {}", package, install),
+                    (package, None) => println!("   📦 Provided by {} (package name varies)", package),
+                }
+            }
+            FirmwareState::PresentNow => println!("⚠️ {} ({}) is installed now; reboot or reload the driver", failure.file, origin),
+            FirmwareState::Optional => println!("ℹ️ {} ({}) not present; the driver fell back to an older version", failure.file, origin),
+        }
+    }
+    if !missing.is_empty() && os_release::read().is_like("debian") {
+        println!("ℹ️ Debian firmware packages need the non-free-firmware component enabled in APT sources.");
+    }
+    match read_fwupd(true) {
+        Some(devices) => {
+            println!("\n🔧 Device firmware (fwupd):");
+            for device in devices.iter().filter(|d| d.version.is_some()) {
+                println!(
+                    "   {} {}: {}{}",
+                    device.vendor.as_deref().unwrap_or(""),
+                    device.name,
+                    device.version.as_deref().unwrap_or("unknown"),
+                    if device.updatable { "" } else { " (not updatable)" }
+                );
+                if let Some(latest) = device.updates.first() {
+                    println!("      ⬆️ Update available: {}", latest);
+                }
+            }
+            if devices.iter().any(|d| !d.updates.is_empty()) {
+                println!("💡 Apply updates with: fwupdmgr update");
+            } else {
+                println!("   No updates in the cached metadata (refresh with `fwupdmgr refresh`).");
+            }
+        }
+        None => println!("\nℹ️ fwupd is not available; install fwupd to check device firmware versions."),
+    }
+}
+
+pub fn get_summary() -> String {
+    if virtualization::detect_container().is_some() {
+        return "Firmware: managed by the container host".to_string();
+    }
+    let missing = read_failures().iter().filter(|f| f.state == FirmwareState::Missing).count();
+    // get-updates describes every device it lists, so one fwupdmgr call is enough here
+    let updates = fwupdmgr_json(&FWUPD_UPDATES_ARGS).map(|u| parse_fwupd(&u, Some(&u)).iter().filter(|d| !d.updates.is_empty()).count());
+    let mut summary = if missing == 0 { "Firmware: ✅ no missing files".to_string() } else { format!("Firmware: ❌ {} missing file(s)", missing) };
+    if let Some(n) = updates.filter(|n| *n > 0) {
+        summary.push_str(&format!(", {} update(s) available", n));
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::sysfs;
+    use std::fs;
+
+    const KERNEL_LOG: &str = "\
+iwlwifi 0000:00:14.3: Direct firmware load for iwlwifi-so-a0-gf-a0-89.ucode failed with error -2
+iwlwifi 0000:00:14.3: Direct firmware load for iwlwifi-so-a0-gf-a0-88.ucode failed with error -2
+iwlwifi 0000:00:14.3: Direct firmware load for iwlwifi-so-a0-gf-a0-89.ucode failed with error -2
+amdgpu 0000:03:00.0: firmware: failed to load amdgpu/psp_13_0_4_toc.bin (-2)
+[    4.512345] bluetooth hci0: Direct firmware load for rtl_bt/rtl8852cu_fw.bin failed with error -2
+firmware_class: see https://wiki.debian.org/Firmware for information about missing firmware
+usb 1-4: new high-speed USB device number 3 using xhci_hcd";
+
+    const FWUPD_DEVICES: &str = r#"{
+  "Devices" : [
+    {
+      "Name" : "UEFI dbx",
+      "DeviceId" : "362301da643102b9f38477387e2193e57abaa590",
+      "Version" : "371",
+      "Vendor" : "Microsoft",
+      "Flags" : ["internal", "updatable", "registered", "needs-reboot"]
+    },
+    {
+      "Name" : "Samsung SSD 980 PRO 1TB",
+      "DeviceId" : "71b677ca0f1bc2c5b804fa1d59e52064ce589293",
+      "Version" : "5B2QGXA7",
+      "Vendor" : "Samsung",
+      "Flags" : ["internal", "registered"]
+    },
+    {
+      "DeviceId" : "c2ca1d8b7a2a4b8f3e2b5b2c9f8b8e4d8c1e2f3a",
+      "Flags" : ["updatable"]
+    }
+  ]
+}"#;
+
+    const FWUPD_UPDATES: &str = r#"{
+  "Devices" : [
+    {
+      "Name" : "UEFI dbx",
+      "DeviceId" : "362301da643102b9f38477387e2193e57abaa590",
+      "Releases" : [
+        { "Version" : "376", "Summary" : "UEFI Secure Boot Forbidden Signature Database" },
+        { "Version" : "372" }
+      ]
+    },
+    {
+      "DeviceId" : "0000000000000000000000000000000000000000",
+      "Releases" : [{ "Version" : "9.9" }]
+    }
+  ]
+}"#;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn firmware_failures() {
+        let failures = parse_firmware_failures(&lines(KERNEL_LOG));
+        let expected = [
+            ("iwlwifi", "0000:00:14.3", "iwlwifi-so-a0-gf-a0-89.ucode"),
+            ("iwlwifi", "0000:00:14.3", "iwlwifi-so-a0-gf-a0-88.ucode"),
+            ("amdgpu", "0000:03:00.0", "amdgpu/psp_13_0_4_toc.bin"),
+            ("bluetooth", "hci0", "rtl_bt/rtl8852cu_fw.bin"),
+        ];
+        assert_eq!(failures.len(), expected.len(), "repeated loads of one file are reported once");
+        for ((driver, device, file), (want_driver, want_device, want_file)) in failures.iter().zip(expected) {
+            assert_eq!(driver.as_deref(), Some(want_driver));
+            assert_eq!(device.as_deref(), Some(want_device));
+            assert_eq!(file, want_file);
+        }
+    }
+
+    #[test]
+    fn firmware_failures_without_device() {
+        let failures = parse_firmware_failures(&lines("platform: firmware: failed to load regulatory.db (-2)"));
+        assert_eq!(failures, [(Some("platform".to_string()), None, "regulatory.db".to_string())]);
+        assert!(parse_firmware_failures(&lines("Direct firmware load for ")).is_empty());
+    }
+
+    #[test]
+    fn fwupd_devices_and_updates() {
+        let devices: Value = serde_json::from_str(FWUPD_DEVICES).unwrap();
+        let updates: Value = serde_json::from_str(FWUPD_UPDATES).unwrap();
+
+        let without = parse_fwupd(&devices, None);
+        assert_eq!(without.len(), 3);
+        assert!(without.iter().all(|d| d.updates.is_empty()));
+
+        let merged = parse_fwupd(&devices, Some(&updates));
+        assert_eq!(merged.len(), 3, "updates for unknown devices are ignored");
+        let dbx = &merged[0];
+        assert_eq!(dbx.name, "UEFI dbx");
+        assert_eq!(dbx.vendor.as_deref(), Some("Microsoft"));
+        assert_eq!(dbx.version.as_deref(), Some("371"));
+        assert!(dbx.updatable);
+        assert_eq!(dbx.updates, ["376", "372"]);
+        let ssd = &merged[1];
+        assert!(!ssd.updatable);
+        assert!(ssd.updates.is_empty());
+        assert_eq!(merged[2].name, "Unknown device");
+        assert_eq!(merged[2].version, None);
+    }
+
+    #[test]
+    fn older_firmware_versions() {
+        let dir = sysfs::scratch_dir("firmware");
+        fs::create_dir_all(dir.join("intel")).unwrap();
+        fs::write(dir.join("iwlwifi-so-a0-gf-a0-86.ucode"), "").unwrap();
+        fs::write(dir.join("intel/ibt-0040-0041.sfi"), "").unwrap();
+        let dirs = [dir.as_path()];
+
+        assert!(older_version_in(&dirs, "iwlwifi-so-a0-gf-a0-89.ucode"));
+        assert!(!older_version_in(&dirs, "iwlwifi-ty-a0-gf-a0-89.ucode"), "a different device prefix");
+        assert!(!older_version_in(&dirs, "iwlwifi-so-a0-gf-a0-89.pnvm"), "a different extension");
+        assert!(older_version_in(&dirs, "intel/ibt-0040-0042.sfi"), "versions inside a subdirectory");
+        assert!(!older_version_in(&dirs, "amdgpu/psp_13_0_4_toc.bin"), "not a versioned name");
+        assert!(!older_version_in(&dirs, "iwlwifi-so-a0-gf-a0-latest.ucode"), "the suffix is not a number");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,332 @@
+// gpu_info.rs
+// Enumerate GPUs from /sys/class/drm, with driver versions, Vulkan ICDs and hybrid graphics detection
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use serde_json::Value;
+
+use super::power_profile;
+use super::sysfs;
+
+const DRM_DIR: &str = "/sys/class/drm";
+const VULKAN_ICD_DIRS: [&str; 2] = ["/usr/share/vulkan/icd.d", "/etc/vulkan/icd.d"];
+const NVIDIA_VERSION_PATH: &str = "/sys/module/nvidia/version";
+const VGA_SWITCHEROO_PATH: &str = "/sys/kernel/debug/vgaswitcheroo/switch";
+const SWITCHEROO_CONTROL_BUS_NAME: &str = "net.hadess.SwitcherooControl";
+
+#[derive(Debug, Clone)]
+pub struct Gpu {
+    /// DRM card name, e.g. "card1".
+    pub card: String,
+    /// PCI address or platform device name.
+    pub bus_id: String,
+    pub vendor_id: Option<u16>,
+    pub device_id: Option<u16>,
+    pub driver: Option<String>,
+    pub vram_bytes: Option<u64>,
+    /// e.g. "16.0 GT/s PCIe x16" (current) and the maximum the link supports.
+    pub link: Option<String>,
+    pub max_link: Option<String>,
+    /// Runtime PM state: active, suspended, ...
+    pub runtime_status: Option<String>,
+    /// The GPU the firmware used for the boot console.
+    pub boot_vga: bool,
+    /// Connected display outputs, e.g. "eDP-1".
+    pub outputs: Vec<String>,
+}
+
+impl Gpu {
+    pub fn vendor(&self) -> &'static str {
+        match self.vendor_id {
+            Some(0x8086) => "Intel",
+            Some(0x1002) => "AMD",
+            Some(0x10de) => "NVIDIA",
+            Some(0x1af4) => "virtio",
+            Some(0x15ad) => "VMware",
+            Some(0x1234) => "QEMU",
+            Some(0x1414) => "Microsoft",
+            Some(0x1a03) => "ASPEED",
+            Some(0x102b) => "Matrox",
+            Some(0x5143) => "Qualcomm",
+            _ => "Unknown",
+        }
+    }
+
+    /// Vendor and PCI IDs, e.g. "NVIDIA [10de:2684]".
+    pub fn model(&self) -> String {
+        match (self.vendor_id, self.device_id) {
+            (Some(v), Some(d)) => format!("{} [{:04x}:{:04x}]", self.vendor(), v, d),
+            _ => self.vendor().to_string(),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        format!("{} ({})", self.model(), self.driver.as_deref().unwrap_or("no driver"))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GraphicsStack {
+    pub mesa_version: Option<String>,
+    pub nvidia_version: Option<String>,
+    /// Installed Vulkan ICD manifests: (file name, driver library).
+    pub vulkan_icds: Vec<(String, String)>,
+}
+
+fn read_hex_id(path: &Path) -> Option<u16> {
+    let value = sysfs::read_trimmed(path)?;
+    u16::from_str_radix(value.trim_start_matches("0x"), 16).ok()
+}
+
+fn format_link(speed: Option<String>, width: Option<String>) -> Option<String> {
+    let speed = speed?;
+    // Unknown or powered-down links report "Unknown" / "0".
+    if speed.starts_with("Unknown") {
+        return None;
+    }
+    Some(match width.filter(|w| w != "0") {
+        Some(width) => format!("{} x{}", speed, width),
+        None => speed,
+    })
+}
+
+fn connected_outputs(card: &str) -> Vec<String> {
+    let prefix = format!("{}-", card);
+    sysfs::list_dir_prefixed(DRM_DIR, &prefix)
+        .into_iter()
+        .filter(|conn| sysfs::read_trimmed(conn.join("status")).as_deref() == Some("connected"))
+        .filter_map(|conn| Some(conn.file_name()?.to_string_lossy().strip_prefix(&prefix)?.to_string()))
+        .collect()
+}
+
+/// All DRM cards (card0, card1, ...), skipping connectors and render nodes.
+pub fn read_gpus() -> Vec<Gpu> {
+    sysfs::list_dir_prefixed(DRM_DIR, "card")
+        .into_iter()
+        .filter_map(|path| {
+            let card = path.file_name()?.to_string_lossy().to_string();
+            if card.contains('-') {
+                return None;
+            }
+            let dev = path.join("device");
+            Some(Gpu {
+                bus_id: fs::canonicalize(&dev)
+                    .ok()
+                    .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+                    .unwrap_or_default(),
+                vendor_id: read_hex_id(&dev.join("vendor")),
+                device_id: read_hex_id(&dev.join("device")),
+                driver: sysfs::link_name(dev.join("driver")),
+                vram_bytes: sysfs::read_u64(dev.join("mem_info_vram_total")),
+                link: format_link(
+                    sysfs::read_trimmed(dev.join("current_link_speed")),
+                    sysfs::read_trimmed(dev.join("current_link_width")),
+                ),
+                max_link: format_link(
+                    sysfs::read_trimmed(dev.join("max_link_speed")),
+                    sysfs::read_trimmed(dev.join("max_link_width")),
+                ),
+                runtime_status: sysfs::read_trimmed(dev.join("power/runtime_status")),
+                boot_vga: sysfs::read_u64(dev.join("boot_vga")) == Some(1),
+                outputs: connected_outputs(&card),
+                card,
+            })
+        })
+        .collect()
+}
+
+/// The Mesa version in `glxinfo -B` or `vulkaninfo --summary` output. Only the version lines are
+/// looked at: device and driver names ("Mesa Intel(R) UHD Graphics", "Intel open-source Mesa
+/// driver") mention Mesa too.
+pub fn parse_mesa_version(text: &str) -> Option<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| {
+            l.starts_with("OpenGL core profile version string:") || l.starts_with("OpenGL version string:") || l.starts_with("driverInfo")
+        })
+        .find_map(|l| l.split_once("Mesa ")?.1.split_whitespace().next().map(|v| v.to_string()))
+}
+
+/// Mesa version from glxinfo or vulkaninfo, when either tool is installed.
+fn mesa_version() -> Option<String> {
+    let from_output = |cmd: &str, args: &[&str]| -> Option<String> {
+        let output = Command::new(cmd).args(args).output().ok()?;
+        parse_mesa_version(&String::from_utf8_lossy(&output.stdout))
+    };
+    from_output("glxinfo", &["-B"]).or_else(|| from_output("vulkaninfo", &["--summary"]))
+}
+
+fn vulkan_icds() -> Vec<(String, String)> {
+    let mut icds = Vec::new();
+    for dir in VULKAN_ICD_DIRS {
+        for file in sysfs::list_dir_prefixed(dir, "") {
+            let name = file.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let library = fs::read_to_string(&file)
+                .ok()
+                .and_then(|s| serde_json::from_str::<Value>(&s).ok())
+                .and_then(|json| json["ICD"]["library_path"].as_str().map(|s| s.to_string()))
+                .unwrap_or_default();
+            icds.push((name, library));
+        }
+    }
+    icds
+}
+
+pub fn read_graphics_stack(gpus: &[Gpu]) -> GraphicsStack {
+    let uses_mesa = gpus.iter().any(|g| g.driver.as_deref().is_some_and(|d| d != "nvidia"));
+    GraphicsStack {
+        mesa_version: if uses_mesa { mesa_version() } else { None },
+        nvidia_version: sysfs::read_trimmed(NVIDIA_VERSION_PATH),
+        vulkan_icds: vulkan_icds(),
+    }
+}
+
+/// Description of the hybrid graphics setup, if there is more than one GPU.
+pub fn hybrid_setup(gpus: &[Gpu]) -> Option<String> {
+    let real: Vec<&Gpu> = gpus.iter().filter(|g| g.vendor_id.is_some()).collect();
+    if real.len() < 2 {
+        return None;
+    }
+    let primary = real.iter().find(|g| g.boot_vga).unwrap_or(&real[0]);
+    let offload: Vec<String> = real.iter().filter(|g| g.card != primary.card).map(|g| g.label()).collect();
+    let mut mechanisms = vec!["PRIME render offload"];
+    if Path::new(VGA_SWITCHEROO_PATH).exists() {
+        mechanisms.push("vga_switcheroo");
+    }
+    if power_profile::bus_has_owner(SWITCHEROO_CONTROL_BUS_NAME) {
+        mechanisms.push("switcheroo-control");
+    }
+    Some(format!(
+        "Hybrid graphics: {} drives the displays, {} available for offload ({})",
+        primary.label(),
+        offload.join(", "),
+        mechanisms.join(", ")
+    ))
+}
+
+/// Coarse GPU type for the kernel recommender: vendors joined by "+", e.g. "intel+nvidia".
+pub fn gpu_type(gpus: &[Gpu]) -> Option<String> {
+    let mut vendors: Vec<String> = Vec::new();
+    for gpu in gpus.iter().filter(|g| g.vendor_id.is_some()) {
+        let vendor = gpu.vendor().to_lowercase();
+        if !vendors.contains(&vendor) {
+            vendors.push(vendor);
+        }
+    }
+    if vendors.is_empty() { None } else { Some(vendors.join("+")) }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    let gib = bytes as f64 / 1024.0 / 1024.0 / 1024.0;
+    if gib >= 1.0 { format!("{:.1} GiB", gib) } else { format!("{} MiB", bytes / 1024 / 1024) }
+}
+
+/// Print the GPU section used by `nephyra hardware`.
+pub fn print_details(gpus: &[Gpu], stack: &GraphicsStack) {
+    println!("\n🎮 GPUs:");
+    if gpus.is_empty() {
+        println!("  No DRM devices found.");
+    }
+    for gpu in gpus {
+        let boot = if gpu.boot_vga { ", boot VGA" } else { "" };
+        println!("  {} {} at {}{}", gpu.card, gpu.label(), gpu.bus_id, boot);
+        if let Some(vram) = gpu.vram_bytes {
+            println!("     VRAM: {}", format_bytes(vram));
+        }
+        if let Some(link) = &gpu.link {
+            let max = gpu.max_link.as_ref().filter(|m| *m != link).map(|m| format!(" (max {})", m)).unwrap_or_default();
+            println!("     PCIe link: {}{}", link, max);
+        }
+        if let Some(status) = &gpu.runtime_status {
+            println!("     Runtime PM: {}", status);
+        }
+        if !gpu.outputs.is_empty() {
+            println!("     Connected outputs: {}", gpu.outputs.join(", "));
+        }
+    }
+    if let Some(hybrid) = hybrid_setup(gpus) {
+        println!("  🔀 {}", hybrid);
+    }
+    if let Some(v) = &stack.mesa_version {
+        println!("  Mesa: {}", v);
+    }
+    if let Some(v) = &stack.nvidia_version {
+        println!("  NVIDIA driver: {}", v);
+    }
+    if !stack.vulkan_icds.is_empty() {
+        let names: Vec<&str> = stack.vulkan_icds.iter().map(|(name, _)| name.as_str()).collect();
+        println!("  Vulkan ICDs: {}", names.join(", "));
+    }
+    let missing: Vec<&str> = stack.vulkan_icds.iter().filter(|(_, lib)| lib.is_empty()).map(|(n, _)| n.as_str()).collect();
+    if !missing.is_empty() {
+        println!("  ⚠️ Unreadable Vulkan ICD manifests: {}", missing.join(", "));
+    }
+}
+
+/// One-line GPU list for summaries.
+pub fn summary(gpus: &[Gpu]) -> String {
+    let labels: Vec<String> = gpus.iter().filter(|g| g.vendor_id.is_some()).map(|g| g.label()).collect();
+    if labels.is_empty() { "GPU: none detected".to_string() } else { format!("GPU: {}", labels.join(" + ")) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mesa_version_from_glxinfo() {
+        let intel = "\
+name of display: :0
+display: :0  screen: 0
+direct rendering: Yes
+Extended renderer info (GLX_MESA_query_renderer):
+    Vendor: Intel (0x8086)
+    Device: Mesa Intel(R) UHD Graphics 620 (KBL GT2) (0x5917)
+    Version: 24.1.2
+    Accelerated: yes
+OpenGL vendor string: Intel
+OpenGL renderer string: Mesa Intel(R) UHD Graphics 620 (KBL GT2)
+OpenGL core profile version string: 4.6 (Core Profile) Mesa 24.1.2-arch1.1
+OpenGL core profile shading language version string: 4.60
+OpenGL version string: 4.6 (Compatibility Profile) Mesa 24.1.2-arch1.1
+";
+        assert_eq!(parse_mesa_version(intel).as_deref(), Some("24.1.2-arch1.1"));
+
+        let amd = "\
+OpenGL vendor string: AMD
+OpenGL renderer string: AMD Radeon RX 6700 XT (radeonsi, navi22, LLVM 17.0.6, DRM 3.57, 6.9.7-arch1-1)
+OpenGL version string: 4.6 (Compatibility Profile) Mesa 24.0.9-1ubuntu1
+";
+        assert_eq!(parse_mesa_version(amd).as_deref(), Some("24.0.9-1ubuntu1"));
+        assert_eq!(parse_mesa_version("OpenGL version string: 4.6.0 NVIDIA 550.90.07\n"), None);
+    }
+
+    #[test]
+    fn mesa_version_from_vulkaninfo() {
+        let intel = "\
+Devices:
+========
+GPU0:
+	apiVersion         = 1.3.278
+	driverVersion      = 24.1.2
+	vendorID           = 0x8086
+	deviceID           = 0x5917
+	deviceType         = PHYSICAL_DEVICE_TYPE_INTEGRATED_GPU
+	deviceName         = Intel(R) UHD Graphics 620 (KBL GT2)
+	driverID           = DRIVER_ID_INTEL_OPEN_SOURCE_MESA
+	driverName         = Intel open-source Mesa driver
+	driverInfo         = Mesa 24.1.2-arch1.1
+";
+        assert_eq!(parse_mesa_version(intel).as_deref(), Some("24.1.2-arch1.1"));
+
+        let amd = "\
+	deviceName         = AMD Radeon RX 6700 XT (RADV NAVI22)
+	driverID           = DRIVER_ID_MESA_RADV
+	driverName         = radv
+	driverInfo         = Mesa 24.0.9-1ubuntu1
+";
+        assert_eq!(parse_mesa_version(amd).as_deref(), Some("24.0.9-1ubuntu1"));
+    }
+}
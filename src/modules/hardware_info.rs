@@ -1,8 +1,67 @@
+use std::env;
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::fs::OpenOptions;
-use std::io::Write;
 use chrono::Local;
-use serde_json::Value;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use super::config;
+use super::cpu_info;
+use super::cpu_vulns;
+use super::devices;
+use super::dmi_info;
+use super::gpu_info;
+use super::hardware_snapshot;
+use super::log_rotation;
+use super::memory_info;
+use super::network_info;
+use super::state;
+use super::virtualization;
+
+/// Where the log went before it moved to the state directory.
+const LEGACY_LOG_PATH: &str = "hardware_info.log";
+const LOG_FORMATS: [&str; 3] = ["text", "jsonl", "json"];
+const USAGE: &str = "Try: nephyra hardware [--log <path>] [--format text|jsonl] | diff [--accept]";
+
+/// Hardware log settings, configurable in the `[hardware_log]` section of config.toml.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct LogSettings {
+    /// Log file; defaults to $XDG_STATE_HOME/nephyra/hardware_info.log (.jsonl for JSON Lines).
+    pub path: Option<String>,
+    /// "text" or "jsonl" (one JSON object per run).
+    pub format: String,
+    pub max_size_kib: u64,
+    pub keep: usize,
+    pub compress: bool,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        LogSettings { path: None, format: "text".to_string(), max_size_kib: 1024, keep: 5, compress: false }
+    }
+}
+
+impl LogSettings {
+    fn jsonl(&self) -> bool {
+        self.format.eq_ignore_ascii_case("jsonl") || self.format.eq_ignore_ascii_case("json")
+    }
+
+    fn log_path(&self) -> PathBuf {
+        match &self.path {
+            // Expand a leading "~/" so config files can stay portable
+            Some(path) => match (path.strip_prefix("~/"), env::var("HOME")) {
+                (Some(rest), Ok(home)) => Path::new(&home).join(rest),
+                _ => PathBuf::from(path),
+            },
+            None => state::state_dir().join(if self.jsonl() { "hardware_info.jsonl" } else { "hardware_info.log" }),
+        }
+    }
+
+    fn rotation(&self) -> log_rotation::Rotation {
+        log_rotation::Rotation { max_bytes: self.max_size_kib * 1024, keep: self.keep, compress: self.compress }
+    }
+}
 
 fn run_command(cmd: &str, args: &[&str]) -> Result<String, String> {
     let output = Command::new(cmd)
@@ -18,15 +77,6 @@ fn run_command(cmd: &str, args: &[&str]) -> Result<String, String> {
     }
 }
 
-fn write_log(log_path: &str, data: &str) -> std::io::Result<()> {
-    let mut file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(log_path)?;
-    writeln!(file, "{}", data)?;
-    Ok(())
-}
-
 // Convert kibibytes (KiB) to string with appropriate unit GiB or MiB
 fn format_mem_kib(kib: u64) -> String {
     if kib >= 1024 * 1024 {
@@ -38,45 +88,6 @@ fn format_mem_kib(kib: u64) -> String {
     }
 }
 
-fn parse_basic_cpu_info(lscpu_output: &str) -> (String, String, String) {
-    let mut model = String::from("Unknown");
-    let mut cores = String::from("Unknown");
-    let mut threads = String::from("Unknown");
-
-    for line in lscpu_output.lines() {
-        if line.starts_with("Model name:") {
-            model = line.splitn(2, ':').nth(1).unwrap_or("").trim().to_string();
-        }
-        if line.starts_with("CPU(s):") {
-            cores = line.splitn(2, ':').nth(1).unwrap_or("").trim().to_string();
-        }
-        if line.starts_with("Thread(s) per core:") {
-            threads = line.splitn(2, ':').nth(1).unwrap_or("").trim().to_string();
-        }
-    }
-
-    (model, cores, threads)
-}
-
-fn parse_meminfo() -> Option<(u64, u64)> {
-    if let Ok(content) = std::fs::read_to_string("/proc/meminfo") {
-        let mut total = 0;
-        let mut free = 0;
-        for line in content.lines() {
-            if line.starts_with("MemTotal:") {
-                total = line.split_whitespace().nth(1)?.parse().ok()?;
-            }
-            if line.starts_with("MemAvailable:") {
-                free = line.split_whitespace().nth(1)?.parse().ok()?;
-            }
-        }
-        if total > 0 && free > 0 {
-            return Some((total, free));
-        }
-    }
-    None
-}
-
 fn parse_storage_summary(lsblk_output: &str) -> Vec<String> {
     // We'll grab NAME, SIZE, TYPE, MOUNTPOINT columns
     let mut devices = Vec::new();
@@ -104,9 +115,45 @@ fn parse_storage_summary(lsblk_output: &str) -> Vec<String> {
     devices
 }
 
-pub fn run() {
+pub fn run(args: &[String]) {
+    match args.first().map(|s| s.as_str()) {
+        Some("diff") => hardware_snapshot::run_diff(&args[1..]),
+        _ => {
+            let mut settings: LogSettings = config::load_section("hardware_log");
+            let mut iter = args.iter();
+            while let Some(arg) = iter.next() {
+                match arg.as_str() {
+                    "--log" | "--format" => {
+                        let Some(value) = iter.next().filter(|v| !v.starts_with("--")) else {
+                            eprintln!("❌ {} needs a value", arg);
+                            println!("{}", USAGE);
+                            return;
+                        };
+                        if arg == "--log" {
+                            settings.path = Some(value.clone());
+                        } else {
+                            settings.format = value.clone();
+                        }
+                    }
+                    other => {
+                        eprintln!("❌ Unknown hardware option: {}", other);
+                        println!("{}", USAGE);
+                        return;
+                    }
+                }
+            }
+            if !LOG_FORMATS.iter().any(|f| settings.format.eq_ignore_ascii_case(f)) {
+                eprintln!("❌ Unknown log format '{}': use text or jsonl", settings.format);
+                return;
+            }
+            show_hardware(&settings);
+        }
+    }
+}
+
+fn show_hardware(settings: &LogSettings) {
     println!("🧠 Nephyra: Hardware Info Module");
-    let log_path = "hardware_info.log";
+    let log_path = settings.log_path();
 
     // Timestamp header for log file
     let time_stamp = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
@@ -115,24 +162,19 @@ pub fn run() {
     log_data.push_str(&header);
     log_data.push('\n');
 
-    // Gather lscpu output
-    let lscpu = match run_command("lscpu", &[]) {
-        Ok(output) => {
-            log_data.push_str("[lscpu output]\n");
-            log_data.push_str(&output);
-            output
-        }
-        Err(e) => {
-            log_data.push_str(&format!("[lscpu error] {}\n", e));
-            String::new()
-        }
-    };
-
-    // Parse CPU info for terminal summary
-    let (cpu_model, cpu_cores, cpu_threads) = parse_basic_cpu_info(&lscpu);
+    // CPU identity and topology from /proc/cpuinfo and sysfs
+    let cpu = cpu_info::read_cpu_info();
+    log_data.push_str("[cpu]\n");
+    log_data.push_str(&cpu_info::summary(&cpu));
+    log_data.push_str(&format!("\nflags: {}\n", cpu.flags.join(" ")));
+    let vulns = cpu_vulns::read_vulnerabilities();
+    log_data.push_str("\n[cpu vulnerabilities]\n");
+    for v in &vulns {
+        log_data.push_str(&format!("{}: {}\n", v.id, v.detail));
+    }
 
     // Gather memory info from /proc/meminfo
-    let mem_info = parse_meminfo();
+    let mem_info = memory_info::total_and_available(&memory_info::read_meminfo());
 
     // Gather storage info with lsblk
     let lsblk = match run_command("lsblk", &["-o", "NAME,SIZE,TYPE,MOUNTPOINT"]) {
@@ -161,25 +203,92 @@ pub fn run() {
         }
     };
 
-    // Dump all detected hardware PCI devices (lots of details, so put in log only)
-    match run_command("lspci", &["-v"]) {
-        Ok(output) => {
-            log_data.push_str("\n[lspci -v output]\n");
-            log_data.push_str(&output);
-        }
-        Err(e) => {
-            log_data.push_str(&format!("[lspci error] {}\n", e));
-        }
-    };
+    // Hypervisor and container environment
+    let virt = virtualization::detect();
+    log_data.push_str("\n[virtualization]\n");
+    log_data.push_str(&format!(
+        "{}\nhypervisor: {} ({})\ncontainer: {}\nextension: {}\nnested: {}\n",
+        virtualization::summary(&virt),
+        virt.hypervisor.as_deref().unwrap_or("none"),
+        virt.hypervisor_source.unwrap_or("-"),
+        virt.container.as_deref().unwrap_or("none"),
+        virt.hw_extension.unwrap_or("none"),
+        virt.nested.map(|n| n.to_string()).unwrap_or_else(|| "unknown".to_string())
+    ));
 
-    // Write accumulated log data to file
-    if let Err(e) = write_log(log_path, &log_data) {
-        eprintln!("⚠️ Failed to write hardware log file: {}", e);
+    // Enumerate PCI and USB devices (full details go to the log only)
+    let (pci_devices, usb_devices) = devices::read_devices();
+    log_data.push('\n');
+    log_data.push_str(&devices::log_text(&pci_devices, &usb_devices));
+
+    // Network interfaces, addresses and drivers
+    let interfaces = network_info::read_interfaces();
+    log_data.push('\n');
+    log_data.push_str(&network_info::log_text(&interfaces));
+
+    // Write accumulated log data to file: the text dump or one JSON Lines record
+    let entry = if settings.jsonl() {
+        json!({
+            "timestamp": time_stamp,
+            "cpu": {
+                "model": cpu.model,
+                "vendor": cpu.vendor,
+                "summary": cpu_info::summary(&cpu),
+                "microcode": cpu.microcode,
+                "flags": cpu.flags,
+            },
+            "vulnerabilities": vulns.iter().map(|v| (v.id.clone(), json!(v.detail))).collect::<serde_json::Map<String, Value>>(),
+            "memory_kib": mem_info.map(|(total, available)| json!({ "total": total, "available": available })),
+            "block_devices": storage_summary,
+            "kernel": uname,
+            "virtualization": {
+                "hypervisor": virt.hypervisor,
+                "hypervisor_source": virt.hypervisor_source,
+                "container": virt.container,
+                "extension": virt.hw_extension,
+                "nested": virt.nested,
+            },
+            "pci": pci_devices.iter().map(|d| json!({
+                "slot": d.slot,
+                "name": d.name(),
+                "class": format!("{:06x}", d.class),
+                "driver": d.driver,
+                "modules": d.modules,
+            })).collect::<Vec<Value>>(),
+            "usb": usb_devices.iter().map(|d| json!({
+                "port": d.port,
+                "name": d.name(),
+                "speed_mbps": d.speed_mbps,
+                "drivers": d.interfaces.iter().filter_map(|i| i.driver.clone()).collect::<Vec<String>>(),
+            })).collect::<Vec<Value>>(),
+            "network": interfaces.iter().map(|i| json!({
+                "name": i.name,
+                "kind": i.kind,
+                "mac": i.mac,
+                "state": i.operstate,
+                "speed_mbps": i.speed_mbps,
+                "driver": i.driver,
+                "firmware": i.firmware,
+                "device": i.device,
+                "addresses": i.addresses,
+            })).collect::<Vec<Value>>(),
+        })
+        .to_string()
+    } else {
+        log_data
+    };
+    if let Err(e) = log_rotation::append(&log_path, &entry, &settings.rotation()) {
+        eprintln!("⚠️ Failed to write hardware log file {}: {}", log_path.display(), e);
     }
 
     // Terminal output - concise but informative
-    println!("\n💻 CPU: {}", cpu_model);
-    println!("🧮 CPU Cores: {}, Threads per core: {}", cpu_cores, cpu_threads);
+    dmi_info::print_details(&dmi_info::read_dmi_info());
+    virtualization::print_details(&virt);
+    cpu_info::print_details(&cpu);
+    cpu_vulns::print_report(&cpu, &vulns);
+    let gpus = gpu_info::read_gpus();
+    gpu_info::print_details(&gpus, &gpu_info::read_graphics_stack(&gpus));
+    devices::print_summary(&pci_devices, &usb_devices);
     if let Some((total_kib, free_kib)) = mem_info {
         println!("🧠 RAM: Total: {}, Available: {}",
             format_mem_kib(total_kib),
@@ -194,15 +303,18 @@ pub fn run() {
         println!("  - {}", dev);
     }
 
-    println!("\n🔎 Detailed hardware info dumped to {}", log_path);
+    println!("\n🔎 Detailed hardware info dumped to {}", log_path.display());
+    if Path::new(LEGACY_LOG_PATH).exists() && log_path != Path::new(LEGACY_LOG_PATH) {
+        println!("ℹ️ ./{} from older Nephyra versions is no longer written and can be removed.", LEGACY_LOG_PATH);
+    }
+    hardware_snapshot::check_against_baseline();
 }
 
 pub fn get_summary() -> String {
     // CPU summary
-    let lscpu = run_command("lscpu", &[]).unwrap_or_default();
-    let (cpu_model, cpu_cores, _cpu_threads) = parse_basic_cpu_info(&lscpu);
+    let cpu = cpu_info::summary(&cpu_info::read_cpu_info());
     // RAM summary
-    let mem_info = parse_meminfo();
+    let mem_info = memory_info::total_and_available(&memory_info::read_meminfo());
     let ram = if let Some((total_kib, _)) = mem_info {
         format_mem_kib(total_kib)
     } else {
@@ -211,43 +323,40 @@ pub fn get_summary() -> String {
     // Get root device using findmnt
     let findmnt_json = run_command("findmnt", &["-J", "/"]).unwrap_or_default();
     let mut root_device = "Unknown".to_string();
-    if let Ok(json) = serde_json::from_str::<Value>(&findmnt_json) {
-        if let Some(filesystems) = json.get("filesystems").and_then(|v| v.as_array()) {
-            if let Some(fs) = filesystems.get(0) {
-                if let Some(source) = fs.get("source").and_then(|v| v.as_str()) {
-                    if let Some(dev) = source.strip_prefix("/dev/") {
-                        let dev_clean = dev.split(['[', '/']).next().unwrap_or(dev);
-                        root_device = dev_clean.to_string();
-                    }
-                }
-            }
-        }
+    if let Ok(json) = serde_json::from_str::<Value>(&findmnt_json)
+        && let Some(filesystems) = json.get("filesystems").and_then(|v| v.as_array())
+        && let Some(fs) = filesystems.first()
+        && let Some(source) = fs.get("source").and_then(|v| v.as_str())
+        && let Some(dev) = source.strip_prefix("/dev/")
+    {
+        let dev_clean = dev.split(['[', '/']).next().unwrap_or(dev);
+        root_device = dev_clean.to_string();
     }
     // Get all disks and their partitions from lsblk
     let lsblk_json = run_command("lsblk", &["-o", "NAME,SIZE,TYPE", "-J"]).unwrap_or_default();
     let mut root_size = "Unknown".to_string();
     let mut other_devices = Vec::new();
-    if let Ok(json) = serde_json::from_str::<Value>(&lsblk_json) {
-        if let Some(blockdevices) = json.get("blockdevices").and_then(|v| v.as_array()) {
-            for dev in blockdevices {
-                let dev_name = dev.get("name").and_then(|v| v.as_str()).unwrap_or("");
-                let dev_size = dev.get("size").and_then(|v| v.as_str()).unwrap_or("");
-                let dev_type = dev.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                if dev_type == "disk" {
-                    let mut is_root_disk = false;
-                    if let Some(children) = dev.get("children").and_then(|v| v.as_array()) {
-                        for part in children {
-                            let part_name = part.get("name").and_then(|v| v.as_str()).unwrap_or("");
-                            let part_size = part.get("size").and_then(|v| v.as_str()).unwrap_or("");
-                            if part_name == root_device {
-                                root_size = part_size.to_string();
-                                is_root_disk = true;
-                            }
+    if let Ok(json) = serde_json::from_str::<Value>(&lsblk_json)
+        && let Some(blockdevices) = json.get("blockdevices").and_then(|v| v.as_array())
+    {
+        for dev in blockdevices {
+            let dev_name = dev.get("name").and_then(|v| v.as_str()).unwrap_or("");
+            let dev_size = dev.get("size").and_then(|v| v.as_str()).unwrap_or("");
+            let dev_type = dev.get("type").and_then(|v| v.as_str()).unwrap_or("");
+            if dev_type == "disk" {
+                let mut is_root_disk = false;
+                if let Some(children) = dev.get("children").and_then(|v| v.as_array()) {
+                    for part in children {
+                        let part_name = part.get("name").and_then(|v| v.as_str()).unwrap_or("");
+                        let part_size = part.get("size").and_then(|v| v.as_str()).unwrap_or("");
+                        if part_name == root_device {
+                            root_size = part_size.to_string();
+                            is_root_disk = true;
                         }
                     }
-                    if !is_root_disk {
-                        other_devices.push(format!("{} ({})", dev_name, dev_size));
-                    }
+                }
+                if !is_root_disk {
+                    other_devices.push(format!("{} ({})", dev_name, dev_size));
                 }
             }
         }
@@ -276,5 +385,11 @@ pub fn get_summary() -> String {
     } else {
         String::new()
     };
-    format!("CPU: {} | Cores: {} | RAM: {} | Main Storage: {}\n{}", cpu_model, cpu_cores, ram, storage_str, other_str)
+    let gpu = gpu_info::summary(&gpu_info::read_gpus());
+    let system = format!(
+        "{} | {}",
+        dmi_info::summary(&dmi_info::read_dmi_info()),
+        virtualization::summary(&virtualization::detect())
+    );
+    format!("{}\nCPU: {} | RAM: {} | Main Storage: {}\n{}\n{}", system, cpu, ram, storage_str, gpu, other_str)
 }
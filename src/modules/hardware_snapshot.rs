@@ -0,0 +1,359 @@
+// hardware_snapshot.rs
+// Structured hardware snapshot kept in the state directory and `nephyra hardware diff` against it
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use super::cpu_info;
+use super::devices;
+use super::dmi_info;
+use super::firmware_health;
+use super::gpu_info;
+use super::memory_info;
+use super::network_info;
+use super::smbios;
+use super::state;
+use super::storage_health;
+use super::sysfs;
+
+const SNAPSHOT_FILE: &str = "hardware_snapshot.toml";
+const BLOCK_DIR: &str = "/sys/block";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SnapshotDisk {
+    pub name: String,
+    pub model: Option<String>,
+    pub serial: Option<String>,
+    pub size_bytes: u64,
+}
+
+impl SnapshotDisk {
+    /// Serial numbers survive renumbering (sda ↔ sdb); the kernel name is the fallback.
+    fn identity(&self) -> &str {
+        self.serial.as_deref().unwrap_or(&self.name)
+    }
+
+    fn label(&self) -> String {
+        format!(
+            "{} {} ({:.0} GB{})",
+            self.name,
+            self.model.as_deref().unwrap_or(""),
+            self.size_bytes as f64 / 1e9,
+            self.serial.as_ref().map(|s| format!(", serial {}", s)).unwrap_or_default()
+        )
+    }
+}
+
+/// A PCI, USB or GPU device: compared by IDs, with the hwdata name kept for display only, since
+/// names change whenever pci.ids / usb.ids are updated.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SnapshotDevice {
+    /// "slot vendor:device" for PCI and GPUs, "vendor:product" plus the serial number for USB.
+    pub id: String,
+    pub name: String,
+    /// Bound kernel driver, recorded for GPUs; a change is reported on its own, not as a new device.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub driver: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct HardwareSnapshot {
+    pub taken_at: String,
+    pub system: String,
+    pub cpu: String,
+    pub ram_kib: u64,
+    /// None when the SMBIOS entries were not readable (not root), so the modules are unknown.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dimms: Option<Vec<String>>,
+    pub gpus: Vec<SnapshotDevice>,
+    pub pci: Vec<SnapshotDevice>,
+    pub usb: Vec<SnapshotDevice>,
+    pub disks: Vec<SnapshotDisk>,
+    /// Component → firmware version (BIOS, CPU microcode, NICs, disks, fwupd devices).
+    pub firmware: BTreeMap<String, String>,
+}
+
+fn read_disks() -> Vec<SnapshotDisk> {
+    storage_health::list_drives()
+        .into_iter()
+        .map(|name| {
+            let dev = Path::new(BLOCK_DIR).join(&name).join("device");
+            SnapshotDisk {
+                model: sysfs::read_trimmed(dev.join("model")).filter(|m| !m.is_empty()),
+                serial: sysfs::read_trimmed(dev.join("serial"))
+                    .or_else(|| sysfs::read_trimmed(dev.join("wwid")))
+                    .filter(|s| !s.is_empty()),
+                size_bytes: sysfs::read_u64(Path::new(BLOCK_DIR).join(&name).join("size")).unwrap_or(0) * 512,
+                name,
+            }
+        })
+        .collect()
+}
+
+/// Take a snapshot of the hardware as it is now.
+pub fn capture() -> HardwareSnapshot {
+    let dmi = dmi_info::read_dmi_info();
+    let cpu = cpu_info::read_cpu_info();
+    let (pci, usb) = devices::read_devices();
+    let (_, dimms) = memory_info::read_dimms();
+    let disks = read_disks();
+
+    let mut firmware = BTreeMap::new();
+    if let Some(bios) = &dmi.bios_version {
+        firmware.insert("BIOS".to_string(), format!("{} ({})", bios, dmi.bios_date.as_deref().unwrap_or("unknown date")));
+    }
+    if let Some(microcode) = &cpu.microcode {
+        firmware.insert("CPU microcode".to_string(), microcode.clone());
+    }
+    for disk in &disks {
+        let dev = Path::new(BLOCK_DIR).join(&disk.name).join("device");
+        // NVMe exposes firmware_rev, SCSI/ATA the revision as "rev"
+        if let Some(rev) = sysfs::read_trimmed(dev.join("firmware_rev")).or_else(|| sysfs::read_trimmed(dev.join("rev"))) {
+            firmware.insert(format!("Disk {}", disk.identity()), rev);
+        }
+    }
+    for iface in network_info::read_interfaces() {
+        if let Some(fw) = iface.firmware {
+            firmware.insert(format!("NIC {}", iface.mac.unwrap_or(iface.name)), fw);
+        }
+    }
+    for device in firmware_health::read_fwupd(false).unwrap_or_default() {
+        if let Some(version) = device.version {
+            firmware.insert(format!("{} {}", device.vendor.unwrap_or_default(), device.name).trim().to_string(), version);
+        }
+    }
+
+    HardwareSnapshot {
+        taken_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        // The BIOS version is tracked in `firmware`; here it would report every BIOS update twice
+        system: format!("{} {}", dmi.sys_vendor.as_deref().unwrap_or("Unknown"), dmi.product_name.as_deref().unwrap_or("")).trim().to_string(),
+        cpu: cpu.model.clone(),
+        ram_kib: memory_info::total_and_available(&memory_info::read_meminfo()).map(|(t, _)| t).unwrap_or(0),
+        dimms: smbios::entries_readable().then(|| {
+            dimms
+                .iter()
+                .map(|d| format!("{}: {} MiB {} {}", d.locator, d.size_mib, d.manufacturer.as_deref().unwrap_or(""), d.part_number.as_deref().unwrap_or("")).trim().to_string())
+                .collect()
+        }),
+        gpus: gpu_info::read_gpus()
+            .iter()
+            .map(|g| SnapshotDevice {
+                id: format!("{} {:04x}:{:04x}", g.bus_id, g.vendor_id.unwrap_or(0), g.device_id.unwrap_or(0)),
+                name: format!("{} {}", g.bus_id, g.model()),
+                driver: g.driver.clone(),
+            })
+            .collect(),
+        pci: pci
+            .iter()
+            .map(|d| SnapshotDevice {
+                id: format!("{} {:04x}:{:04x}", d.slot, d.vendor_id, d.device_id),
+                name: format!("{} {}", d.slot, d.name()),
+                driver: None,
+            })
+            .collect(),
+        // USB ports change on replug, so devices are identified by IDs and serial number only
+        usb: usb
+            .iter()
+            .map(|d| SnapshotDevice {
+                id: format!("{:04x}:{:04x}{}", d.vendor_id, d.product_id, d.serial.as_ref().map(|s| format!(" {}", s)).unwrap_or_default()),
+                name: d.name(),
+                driver: None,
+            })
+            .collect(),
+        disks,
+        firmware,
+    }
+}
+
+/// Entries of `new` not in `old`, honouring duplicates (two identical USB sticks).
+fn added<'a, T>(old: &[T], new: &'a [T], same: impl Fn(&T, &T) -> bool) -> Vec<&'a T> {
+    let mut remaining: Vec<&T> = old.iter().collect();
+    new.iter()
+        .filter(|item| match remaining.iter().position(|o| same(o, item)) {
+            Some(i) => {
+                remaining.remove(i);
+                false
+            }
+            None => true,
+        })
+        .collect()
+}
+
+/// Human-readable differences between two snapshots, most serious first.
+pub fn diff(old: &HardwareSnapshot, new: &HardwareSnapshot) -> Vec<String> {
+    let mut changes = Vec::new();
+    for disk in &old.disks {
+        match new.disks.iter().find(|d| d.identity() == disk.identity()) {
+            None => changes.push(format!("❌ Disk disappeared: {}", disk.label())),
+            Some(now) if now.size_bytes != disk.size_bytes => {
+                changes.push(format!("⚠️ Disk size changed: {} → {}", disk.label(), now.label()))
+            }
+            _ => {}
+        }
+    }
+    for disk in new.disks.iter().filter(|d| !old.disks.iter().any(|o| o.identity() == d.identity())) {
+        changes.push(format!("➕ Disk added: {}", disk.label()));
+    }
+    if old.ram_kib != new.ram_kib && old.ram_kib > 0 && new.ram_kib > 0 {
+        // The kernel's MemTotal moves by a few MiB between kernels; only report real module changes
+        let delta = old.ram_kib.abs_diff(new.ram_kib);
+        if delta > 256 * 1024 {
+            changes.push(format!("⚠️ RAM size changed: {} → {}", sysfs::format_kib(old.ram_kib), sysfs::format_kib(new.ram_kib)));
+        }
+    }
+    if old.cpu != new.cpu {
+        changes.push(format!("🔄 CPU changed: {} → {}", old.cpu, new.cpu));
+    }
+    if old.system != new.system {
+        changes.push(format!("🔄 System identity changed: {} → {}", old.system, new.system));
+    }
+    // A snapshot taken without root has no module list; comparing it would report every DIMM
+    if let (Some(before), Some(after)) = (&old.dimms, &new.dimms) {
+        for item in added(after, before, |a, b| a == b) {
+            changes.push(format!("➖ Memory module removed: {}", item));
+        }
+        for item in added(before, after, |a, b| a == b) {
+            changes.push(format!("➕ Memory module added: {}", item));
+        }
+    }
+    for gpu in &old.gpus {
+        if let Some(now) = new.gpus.iter().find(|g| g.id == gpu.id)
+            && now.driver != gpu.driver
+        {
+            let driver = |d: &Option<String>| d.clone().unwrap_or_else(|| "no driver".to_string());
+            changes.push(format!("🔄 GPU driver changed: {}: {} → {}", gpu.name, driver(&gpu.driver), driver(&now.driver)));
+        }
+    }
+    let devices: [(&str, &Vec<SnapshotDevice>, &Vec<SnapshotDevice>); 3] =
+        [("GPU", &old.gpus, &new.gpus), ("PCI device", &old.pci, &new.pci), ("USB device", &old.usb, &new.usb)];
+    for (label, before, after) in devices {
+        for device in added(after, before, |a, b| a.id == b.id) {
+            changes.push(format!("➖ {} removed: {}", label, device.name));
+        }
+        for device in added(before, after, |a, b| a.id == b.id) {
+            changes.push(format!("➕ {} added: {}", label, device.name));
+        }
+    }
+    for (component, version) in &old.firmware {
+        match new.firmware.get(component) {
+            Some(now) if now != version => changes.push(format!("🔄 Firmware {}: {} → {}", component, version, now)),
+            _ => {}
+        }
+    }
+    changes
+}
+
+pub fn load_baseline() -> Option<HardwareSnapshot> {
+    state::load(SNAPSHOT_FILE)
+}
+
+pub fn save_baseline(snapshot: &HardwareSnapshot) {
+    match state::save(SNAPSHOT_FILE, snapshot) {
+        Ok(path) => println!("💾 Hardware snapshot saved to {}", path.display()),
+        Err(e) => eprintln!("⚠️ Failed to save hardware snapshot: {}", e),
+    }
+}
+
+/// Called after `nephyra hardware`: create the baseline on first run, otherwise point out changes.
+pub fn check_against_baseline() {
+    let current = capture();
+    match load_baseline() {
+        None => save_baseline(&current),
+        Some(baseline) => {
+            let changes = diff(&baseline, &current);
+            if !changes.is_empty() {
+                println!(
+                    "\n⚠️ {} hardware change(s) since the snapshot of {}. Run `nephyra hardware diff` for details.",
+                    changes.len(),
+                    baseline.taken_at
+                );
+            }
+        }
+    }
+}
+
+/// Entry point for `nephyra hardware diff [--accept]`.
+pub fn run_diff(args: &[String]) {
+    let accept = args.iter().any(|a| a == "--accept");
+    let current = capture();
+    let Some(baseline) = load_baseline() else {
+        println!("ℹ️ No hardware snapshot yet; saving the current hardware as the baseline.");
+        save_baseline(&current);
+        return;
+    };
+    println!("🔍 Hardware changes since {}", baseline.taken_at);
+    let changes = diff(&baseline, &current);
+    if changes.is_empty() {
+        println!("✅ No changes.");
+    }
+    for change in &changes {
+        println!("  {}", change);
+    }
+    if accept {
+        save_baseline(&current);
+    } else if !changes.is_empty() {
+        println!("\n💡 If these changes are expected, run `nephyra hardware diff --accept` to update the snapshot.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(id: &str, name: &str) -> SnapshotDevice {
+        SnapshotDevice { id: id.to_string(), name: name.to_string(), driver: None }
+    }
+
+    #[test]
+    fn devices_compare_by_id() {
+        let old = HardwareSnapshot {
+            pci: vec![device("0000:01:00.0 10de:2684", "0000:01:00.0 NVIDIA Corporation AD102 [10de:2684]")],
+            usb: vec![device("0781:5581 4C530001", "SanDisk Corp. Ultra [0781:5581]"), device("046d:c52b", "Logitech, Inc. Unifying Receiver [046d:c52b]")],
+            ..Default::default()
+        };
+        let mut renamed = old.clone();
+        renamed.pci[0].name = "0000:01:00.0 NVIDIA Corporation AD102 [GeForce RTX 4090] [10de:2684]".to_string();
+        renamed.usb[1].name = "Logitech, Inc. Unifying Receiver (hwdata 2024) [046d:c52b]".to_string();
+        assert!(diff(&old, &renamed).is_empty(), "hwdata name updates are not hardware changes");
+
+        let mut swapped = old.clone();
+        swapped.usb[0] = device("0781:5581 4C530002", "SanDisk Corp. Ultra [0781:5581]");
+        assert_eq!(
+            diff(&old, &swapped),
+            ["➖ USB device removed: SanDisk Corp. Ultra [0781:5581]", "➕ USB device added: SanDisk Corp. Ultra [0781:5581]"]
+        );
+    }
+
+    #[test]
+    fn gpu_driver_change_is_not_a_new_gpu() {
+        let gpu = |driver: Option<&str>| SnapshotDevice {
+            id: "0000:01:00.0 10de:2684".to_string(),
+            name: "0000:01:00.0 NVIDIA [10de:2684]".to_string(),
+            driver: driver.map(|d| d.to_string()),
+        };
+        let old = HardwareSnapshot { gpus: vec![gpu(Some("nouveau"))], ..Default::default() };
+        let mut new = old.clone();
+        new.gpus[0] = gpu(Some("nvidia"));
+        assert_eq!(diff(&old, &new), ["🔄 GPU driver changed: 0000:01:00.0 NVIDIA [10de:2684]: nouveau → nvidia"]);
+        new.gpus[0] = gpu(None);
+        assert_eq!(diff(&old, &new), ["🔄 GPU driver changed: 0000:01:00.0 NVIDIA [10de:2684]: nouveau → no driver"]);
+        new.gpus.clear();
+        assert_eq!(diff(&old, &new), ["➖ GPU removed: 0000:01:00.0 NVIDIA [10de:2684]"]);
+    }
+
+    #[test]
+    fn dimms_unknown_without_root() {
+        let dimms = vec!["DIMM A1: 16384 MiB Samsung M425R2GA3BB0-CQKOD".to_string(), "DIMM B1: 16384 MiB Samsung M425R2GA3BB0-CQKOD".to_string()];
+        let root = HardwareSnapshot { dimms: Some(dimms.clone()), ..Default::default() };
+        let user = HardwareSnapshot { dimms: None, ..Default::default() };
+        assert!(diff(&root, &user).is_empty(), "a baseline taken with sudo, checked as a user");
+        assert!(diff(&user, &root).is_empty(), "a baseline taken as a user, checked with sudo");
+
+        let mut pulled = root.clone();
+        pulled.dimms = Some(dimms[..1].to_vec());
+        assert_eq!(diff(&root, &pulled), ["➖ Memory module removed: DIMM B1: 16384 MiB Samsung M425R2GA3BB0-CQKOD"]);
+    }
+}
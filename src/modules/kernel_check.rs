@@ -1,13 +1,17 @@
 use std::fs;
-use std::process::{Command, Stdio};
-use std::str;
-use std::io::Write;
-use std::path::PathBuf;
-use std::env;
-use std::fs::File;
-use std::io::{Read};
+use std::path::Path;
+use std::process::Command;
 use serde::{Serialize, Deserialize};
 
+use super::config;
+use super::cpu_info;
+use super::cpu_vulns;
+use super::dmi_info;
+use super::gpu_info;
+use super::optimized_repos;
+use super::package_manager::{self, PackageManager};
+use super::virtualization;
+
 #[derive(Serialize, Deserialize, Default, Debug)]
 struct NephyraPrefs {
     preferred_kernel: Option<String>,
@@ -15,53 +19,17 @@ struct NephyraPrefs {
     use_cases: Vec<String>,
 }
 
-fn get_prefs_path() -> PathBuf {
-    let home = env::var("HOME").unwrap_or_else(|_| ".".to_string());
-    PathBuf::from(format!("{}/.config/nephyra/config.toml", home))
-}
-
 fn load_prefs() -> NephyraPrefs {
-    let path = get_prefs_path();
-    if let Ok(mut file) = File::open(&path) {
-        let mut contents = String::new();
-        if file.read_to_string(&mut contents).is_ok() {
-            toml::from_str(&contents).unwrap_or_default()
-        } else {
-            NephyraPrefs::default()
-        }
-    } else {
-        NephyraPrefs::default()
-    }
+    config::load()
 }
 
 fn save_prefs(prefs: &NephyraPrefs) {
-    let path = get_prefs_path();
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent).ok();
-    }
-    if let Ok(mut file) = File::create(&path) {
-        let toml = toml::to_string_pretty(prefs).unwrap_or_default();
-        let _ = file.write_all(toml.as_bytes());
+    // Merged into the shared config file so other modules' sections survive
+    if let Err(e) = config::save(prefs) {
+        eprintln!("⚠️ Failed to save preferences: {}", e);
     }
 }
 
-fn detect_package_manager() -> Option<&'static str> {
-    let candidates = ["pacman", "apt", "dnf", "apk", "zypper", "emerge"];
-    for pm in candidates {
-        if Command::new("which")
-            .arg(pm)
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()
-            .unwrap_or_default()
-            .success()
-        {
-            return Some(pm);
-        }
-    }
-    None
-}
-
 /// Heuristic to extract the kernel package base name from the kernel version string.
 /// E.g. "6.15.2-2-cachyos-eevdf-lto" → "linux-cachyos-eevdf-lto"
 fn kernel_package_name(kernel_version: &str) -> String {
@@ -74,72 +42,24 @@ fn kernel_package_name(kernel_version: &str) -> String {
     }
 }
 
-fn is_package_installed(pm: &str, pkg: &str) -> bool {
-    match pm {
-        "pacman" => {
-            if let Ok(output) = Command::new("pacman").args(["-Qs", pkg]).output() {
-                !output.stdout.is_empty()
-            } else {
-                false
-            }
-        }
-        "apt" => {
-            if let Ok(output) = Command::new("dpkg-query")
-                .args(["-W", "-f=${Status}", pkg])
-                .output()
-            {
-                if let Ok(stdout_str) = str::from_utf8(&output.stdout) {
-                    stdout_str.contains("installed")
-                } else {
-                    false
-                }
-            } else {
-                false
-            }
-        }
-        "dnf" => {
-            if let Ok(output) = Command::new("dnf").args(["list", "installed", pkg]).output() {
-                if let Ok(stdout_str) = str::from_utf8(&output.stdout) {
-                    stdout_str.contains(pkg)
-                } else {
-                    false
-                }
-            } else {
-                false
-            }
-        }
-        "apk" => {
-            if let Ok(output) = Command::new("apk").args(["info", pkg]).output() {
-                !output.stdout.is_empty()
-            } else {
-                false
-            }
-        }
-        "zypper" => {
-            if let Ok(output) =
-                Command::new("zypper").args(["se", "--installed-only", pkg]).output()
-            {
-                if let Ok(stdout_str) = str::from_utf8(&output.stdout) {
-                    stdout_str.contains(pkg)
-                } else {
-                    false
-                }
-            } else {
-                false
-            }
-        }
-        "emerge" => {
-            if let Ok(output) = Command::new("emerge").args(["-s", pkg]).output() {
-                if let Ok(stdout_str) = str::from_utf8(&output.stdout) {
-                    stdout_str.contains(pkg)
-                } else {
-                    false
-                }
-            } else {
-                false
-            }
-        }
-        _ => false,
+/// Package that owns the running kernel image, falling back to the name heuristic.
+fn running_kernel_package(pm: &dyn PackageManager, kernel_version: &str) -> String {
+    [format!("/usr/lib/modules/{}/vmlinuz", kernel_version), format!("/boot/vmlinuz-{}", kernel_version)]
+        .iter()
+        .find_map(|p| pm.owner_of(Path::new(p)))
+        .unwrap_or_else(|| kernel_package_name(kernel_version))
+}
+
+/// Headers package matching the running kernel for this package manager.
+fn headers_package(pm: &dyn PackageManager, kernel_version: &str) -> String {
+    // Fedora, Alpine, openSUSE and Gentoo ship headers under a fixed package name
+    match pm.name() {
+        "dnf" => "kernel-headers".to_string(),
+        "apk" => "linux-headers".to_string(),
+        "zypper" => "kernel-devel".to_string(),
+        "emerge" => "sys-kernel/linux-headers".to_string(),
+        "apt" => running_kernel_package(pm, kernel_version).replacen("linux-image-", "linux-headers-", 1),
+        _ => format!("{}-headers", running_kernel_package(pm, kernel_version)),
     }
 }
 
@@ -161,11 +81,8 @@ fn detect_kernel_variant(name: &str) -> &'static str {
 }
 
 fn detect_nvidia() -> bool {
-    // Check for NVIDIA driver
-    Command::new("lsmod")
-        .output()
-        .map(|o| String::from_utf8_lossy(&o.stdout).contains("nvidia"))
-        .unwrap_or(false)
+    // Check for a GPU bound to the proprietary NVIDIA driver
+    gpu_info::read_gpus().iter().any(|g| g.driver.as_deref() == Some("nvidia"))
 }
 
 fn detect_audio_hw() -> bool {
@@ -178,51 +95,51 @@ fn detect_audio_hw() -> bool {
 
 fn detect_init_system() -> &'static str {
     // Use ps to check the process name of PID 1
-    if let Ok(output) = Command::new("ps").args(["-p", "1", "-o", "comm="]).output() {
-        if let Ok(comm) = String::from_utf8(output.stdout) {
-            let comm = comm.trim();
-            if comm == "systemd" {
-                return "systemd";
-            } else if comm == "runit" {
-                return "runit";
-            } else if comm == "openrc-init" || comm == "openrc" {
-                return "openrc";
-            } else if comm == "s6-svscan" || comm == "s6" {
-                return "s6";
-            } else if comm == "init" {
-                // Try to resolve /sbin/init or /bin/init symlink
-                if let Ok(meta) = std::fs::read_link("/sbin/init") {
-                    if let Some(name) = meta.file_name().and_then(|n| n.to_str()) {
-                        if name.contains("openrc") {
-                            return "openrc";
-                        } else if name.contains("runit") {
-                            return "runit";
-                        } else if name.contains("systemd") {
-                            return "systemd";
-                        }
-                    }
+    if let Ok(output) = Command::new("ps").args(["-p", "1", "-o", "comm="]).output()
+        && let Ok(comm) = String::from_utf8(output.stdout)
+    {
+        let comm = comm.trim();
+        if comm == "systemd" {
+            return "systemd";
+        } else if comm == "runit" {
+            return "runit";
+        } else if comm == "openrc-init" || comm == "openrc" {
+            return "openrc";
+        } else if comm == "s6-svscan" || comm == "s6" {
+            return "s6";
+        } else if comm == "init" {
+            // Try to resolve /sbin/init or /bin/init symlink
+            if let Ok(meta) = std::fs::read_link("/sbin/init")
+                && let Some(name) = meta.file_name().and_then(|n| n.to_str())
+            {
+                if name.contains("openrc") {
+                    return "openrc";
+                } else if name.contains("runit") {
+                    return "runit";
+                } else if name.contains("systemd") {
+                    return "systemd";
                 }
-                if let Ok(meta) = std::fs::read_link("/bin/init") {
-                    if let Some(name) = meta.file_name().and_then(|n| n.to_str()) {
-                        if name.contains("openrc") {
-                            return "openrc";
-                        } else if name.contains("runit") {
-                            return "runit";
-                        } else if name.contains("systemd") {
-                            return "systemd";
-                        }
-                    }
+            }
+            if let Ok(meta) = std::fs::read_link("/bin/init")
+                && let Some(name) = meta.file_name().and_then(|n| n.to_str())
+            {
+                if name.contains("openrc") {
+                    return "openrc";
+                } else if name.contains("runit") {
+                    return "runit";
+                } else if name.contains("systemd") {
+                    return "systemd";
                 }
-                return "sysvinit";
+            }
+            return "sysvinit";
+        } else {
+            // Return a static string for unknown init systems
+            if comm == "busybox" {
+                return "busybox-init";
+            } else if comm == "linuxrc" {
+                return "linuxrc";
             } else {
-                // Return a static string for unknown init systems
-                if comm == "busybox" {
-                    return "busybox-init";
-                } else if comm == "linuxrc" {
-                    return "linuxrc";
-                } else {
-                    return "unknown";
-                }
+                return "unknown";
             }
         }
     }
@@ -277,7 +194,7 @@ struct KernelRepoInfo {
     name: String,
     version: String,
     description: String,
-    _repo: String, // was: repo
+    repo: String,
 }
 
 fn parse_pacman_kernel_list(pacman_output: &str) -> Vec<KernelRepoInfo> {
@@ -293,7 +210,7 @@ fn parse_pacman_kernel_list(pacman_output: &str) -> Vec<KernelRepoInfo> {
                 let mut desc = parts.collect::<Vec<&str>>().join(" ");
                 // Remove [installed] if present
                 desc = desc.replace("[installed]", "").trim().to_string();
-                kernels.push(KernelRepoInfo { name, version: version.to_string(), description: desc, _repo: repo });
+                kernels.push(KernelRepoInfo { name, version: version.to_string(), description: desc, repo });
             }
         }
     }
@@ -322,7 +239,7 @@ impl SystemInfo {
             .output()
             .expect("Failed to run uname");
         let current_kernel = String::from_utf8_lossy(&uname_output.stdout).trim().to_string();
-        let package_manager = detect_package_manager().map(|s| s.to_string());
+        let package_manager = package_manager::detect().map(|pm| pm.name().to_string());
         SystemInfo { current_kernel, package_manager }
     }
 }
@@ -406,21 +323,17 @@ impl DetailedKernelInfo {
 }
 
 fn enhance_kernel_info(kernel: &mut KernelInfo, package_manager: &Option<String>) {
-    if let Some(pm) = package_manager {
-        match pm.as_str() {
-            "pacman" => {
-                if let Some(details) = if kernel.installed {
-                    DetailedKernelInfo::from_installed_pacman(&kernel.name)
-                } else {
-                    DetailedKernelInfo::from_pacman(&kernel.name)
-                } {
-                    if kernel.description.is_empty() {
-                        kernel.description = details.description.clone();
-                    }
-                    analyze_kernel_details(kernel, &details);
-                }
-            },
-            _ => {}
+    if package_manager.as_deref() == Some("pacman") {
+        let details = if kernel.installed {
+            DetailedKernelInfo::from_installed_pacman(&kernel.name)
+        } else {
+            DetailedKernelInfo::from_pacman(&kernel.name)
+        };
+        if let Some(details) = details {
+            if kernel.description.is_empty() {
+                kernel.description = details.description.clone();
+            }
+            analyze_kernel_details(kernel, &details);
         }
     }
 }
@@ -470,8 +383,17 @@ fn display_detailed_kernel_info(kernel: &KernelInfo, details: Option<&DetailedKe
     }
 }
 
+/// Detected hardware facts the kernel scoring depends on
+struct HardwareContext {
+    gpu_type: Option<String>,
+    nvidia: bool,
+    audio: bool,
+    cpu_level: Option<u8>,
+    form_factor: dmi_info::FormFactor,
+}
+
 /// Score and explain kernel recommendation for a given kernel and user/system context
-fn score_and_reason_kernel(k: &KernelRepoInfo, use_cases: &[String], gpu_type: &Option<String>, nvidia: bool, audio: bool, prev_problematic: &[String]) -> (i32, String) {
+fn score_and_reason_kernel(k: &KernelRepoInfo, use_cases: &[String], hw: &HardwareContext, prev_problematic: &[String]) -> (i32, String) {
     let mut score = 0;
     let mut reasons = Vec::new();
     let name = k.name.to_lowercase();
@@ -479,7 +401,7 @@ fn score_and_reason_kernel(k: &KernelRepoInfo, use_cases: &[String], gpu_type: &
     let mut warn = None;
     let mut needs_headers = false;
     let dev_selected = use_cases.iter().any(|c| c.to_lowercase().contains("dev") || c.to_lowercase().contains("programming"));
-    let amd_intel_gpu = gpu_type.as_ref().map(|g| g.to_lowercase().contains("integrated") || g.to_lowercase().contains("amd") || g.to_lowercase().contains("intel")).unwrap_or(false);
+    let amd_intel_gpu = hw.gpu_type.as_ref().map(|g| g.to_lowercase().contains("integrated") || g.to_lowercase().contains("amd") || g.to_lowercase().contains("intel")).unwrap_or(false);
     let is_zen = name.contains("zen") || desc.contains("zen");
     let is_eevdf = name.contains("eevdf") || desc.contains("eevdf");
     let is_lts = name.contains("lts") || desc.contains("lts");
@@ -492,7 +414,7 @@ fn score_and_reason_kernel(k: &KernelRepoInfo, use_cases: &[String], gpu_type: &
         warn = Some("You previously marked this kernel as problematic.");
     }
     // Zen kernel: penalize for AMD/Intel GPU and laptops (overheating)
-    if is_zen && amd_intel_gpu {
+    if is_zen && (amd_intel_gpu || hw.form_factor.is_portable()) {
         score -= 4;
         warn = Some("Zen kernel is known to cause overheating on AMD/Intel GPUs and laptops. CachyOS EEVDF LTO is preferred for your setup.");
     }
@@ -502,7 +424,7 @@ fn score_and_reason_kernel(k: &KernelRepoInfo, use_cases: &[String], gpu_type: &
         reasons.push("CachyOS EEVDF is highly recommended for desktop/gaming/development on AMD/Intel GPUs due to its scheduler and thermal profile.");
     }
     // LTS for server, battery, stability
-    if is_lts && use_cases.iter().any(|c| c.to_lowercase().contains("server") || c.to_lowercase().contains("battery")) {
+    if is_lts && (hw.form_factor == dmi_info::FormFactor::Server || use_cases.iter().any(|c| c.to_lowercase().contains("server") || c.to_lowercase().contains("battery"))) {
         score += 4;
         reasons.push("LTS kernel is preferred for server and battery life due to stability.");
     }
@@ -522,18 +444,33 @@ fn score_and_reason_kernel(k: &KernelRepoInfo, use_cases: &[String], gpu_type: &
         score -= 2;
         warn = Some("Hardened kernel is not recommended unless you need extra security.");
     }
+    // Virtual machines: paravirtual drivers are best maintained in LTS/Standard kernels
+    if (is_lts || is_standard) && (hw.form_factor == dmi_info::FormFactor::VirtualMachine || use_cases.iter().any(|c| c.to_lowercase() == "vm")) {
+        score += 3;
+        reasons.push("LTS and Standard kernels carry the best-tested virtio/paravirtual drivers for guests.");
+    } else if (is_rt || is_zen) && use_cases.iter().any(|c| c.to_lowercase() == "vm") {
+        score -= 2;
+        warn = Some("Latency-tuned kernels bring little inside a virtual machine; the host scheduler decides.");
+    }
     // Standard for general use
     if is_standard && use_cases.iter().any(|c| c.to_lowercase().contains("desktop") || c.to_lowercase().contains("server")) {
         score += 2;
         reasons.push("Standard kernel is a safe choice for most users.");
     }
     // NVIDIA: avoid Zen/RT/Hardened
-    if nvidia && (is_zen || is_rt || is_hardened) {
+    if hw.nvidia && (is_zen || is_rt || is_hardened) {
         score -= 6;
         warn = Some("Avoid Zen/RT/Hardened kernels with NVIDIA drivers. Use LTS or Standard.");
     }
+    // Microarchitecture-optimized repos (cachyos-v3, ALHP v4, ...) must not exceed what the CPU supports
+    if let (Some(required), Some(cpu)) = (optimized_repos::level_from_name(&k.repo), hw.cpu_level)
+        && required > cpu
+    {
+        score -= 20;
+        warn = Some("This kernel is built for a newer x86-64 level than your CPU supports and will not boot (SIGILL).");
+    }
     // Audio hardware: favor RT
-    if audio && is_rt {
+    if hw.audio && is_rt {
         score += 2;
     }
     // Programming/dev: always check headers
@@ -555,20 +492,8 @@ fn score_and_reason_kernel(k: &KernelRepoInfo, use_cases: &[String], gpu_type: &
 }
 
 fn detect_gpu_type() -> Option<String> {
-    // Try to detect GPU type from lspci output
-    if let Ok(output) = Command::new("lspci").output() {
-        let lspci = String::from_utf8_lossy(&output.stdout).to_lowercase();
-        if lspci.contains("nvidia") {
-            return Some("nvidia".to_string());
-        } else if lspci.contains("amd") || lspci.contains("ati") {
-            return Some("amd".to_string());
-        } else if lspci.contains("intel") {
-            return Some("intel".to_string());
-        } else if lspci.contains("integrated") {
-            return Some("integrated".to_string());
-        }
-    }
-    None
+    // Every GPU vendor counts, so an Intel iGPU + NVIDIA dGPU laptop reports "intel+nvidia"
+    gpu_info::gpu_type(&gpu_info::read_gpus())
 }
 
 fn infer_use_cases() -> Vec<String> {
@@ -603,23 +528,49 @@ fn infer_use_cases() -> Vec<String> {
     {
         use_cases.push("security".to_string());
     }
-    // Default to desktop if nothing else
+    // Default to the environment we run in: guests and containers are not desktops
     if use_cases.is_empty() {
-        use_cases.push("desktop".to_string());
+        let virt = virtualization::detect();
+        if virt.in_container() {
+            use_cases.push("container".to_string());
+        } else if virt.in_vm() {
+            use_cases.push("vm".to_string());
+        } else {
+            use_cases.push("desktop".to_string());
+        }
+    }
+    // Unmitigated CPU vulnerabilities the user did not opt into call for a security-minded kernel
+    if !use_cases.iter().any(|c| c == "security") && cpu_vulns::unintended_exposures(&cpu_vulns::read_vulnerabilities()) > 0 {
+        use_cases.push("security".to_string());
     }
     use_cases
 }
 
+/// Use cases from the user's preferences, falling back to the automatically inferred ones.
+pub fn effective_use_cases() -> Vec<String> {
+    let prefs = load_prefs();
+    if prefs.use_cases.is_empty() {
+        infer_use_cases()
+    } else {
+        prefs.use_cases
+    }
+}
+
 pub fn run() {
     println!("🤖 Nephyra AI Kernel Assistant: Automated System Context Analysis\n");
 
     let sysinfo = SystemInfo::gather();
+    let pm = sysinfo.package_manager.as_deref().and_then(package_manager::by_name);
     let mut prefs = load_prefs();
     // Automated detection
     let detected_gpu = detect_gpu_type();
     let detected_use_cases = infer_use_cases();
     let nvidia = detect_nvidia();
     let audio = detect_audio_hw();
+    let cpu = cpu_info::read_cpu_info();
+    let cpu_level = cpu.x86_64_level();
+    let form_factor = dmi_info::form_factor();
+    let virt = virtualization::detect();
     let current_kernel = sysinfo.current_kernel.clone();
     // Use detected values unless user has set preferences
     if prefs.gpu_type.is_none() {
@@ -636,23 +587,47 @@ pub fn run() {
     println!("  Use cases: {}", prefs.use_cases.join(", "));
     println!("  NVIDIA driver: {}", if nvidia { "yes" } else { "no" });
     println!("  Audio hardware: {}", if audio { "yes" } else { "no" });
+    println!("  Form factor: {}", form_factor.label());
+    println!("  Environment: {}", virt.label());
+    // A container shares the host's kernel; there is nothing to install or boot here
+    if virt.in_container() {
+        println!("\nℹ️ Running inside a {} container: the kernel belongs to the host.", virt.container.as_deref().unwrap_or("unknown"));
+        println!("   Run `nephyra kernel` on the host for kernel recommendations.");
+        return;
+    }
+    if let Some(level) = cpu_level {
+        println!("  CPU level: x86-64-v{}", level);
+    }
+    let vulns = cpu_vulns::read_vulnerabilities();
+    if !vulns.is_empty() {
+        println!("  {}", cpu_vulns::summary(&vulns));
+        let opted_out = vulns.iter().any(|v| v.opt_out.is_some());
+        if opted_out && prefs.use_cases.iter().any(|c| c.to_lowercase().contains("security")) {
+            println!("⚠️ Security use case selected, but CPU mitigations are disabled on the kernel command line.");
+        }
+        if vulns.iter().any(|v| v.needs_microcode()) {
+            println!("⚠️ Some CPU mitigations are waiting for a microcode update, not a different kernel.");
+            println!("💡 Update {} and regenerate the initramfs.", cpu_vulns::microcode_package(&cpu.vendor));
+        }
+    }
+    optimized_repos::report();
     // List installed kernels
     let mut installed_kernels: Vec<KernelInfo> = vec![];
     if let Ok(entries) = fs::read_dir("/lib/modules") {
         for entry in entries.flatten() {
-            if let Ok(file_type) = entry.file_type() {
-                if file_type.is_dir() {
-                    let name = entry.file_name().to_string_lossy().to_string();
-                    let variant = detect_kernel_variant(&name).to_string();
-                    let installed = name == current_kernel;
-                    installed_kernels.push(KernelInfo {
-                        name: name.clone(),
-                        version: String::new(),
-                        description: String::new(),
-                        variant,
-                        installed,
-                    });
-                }
+            if let Ok(file_type) = entry.file_type()
+                && file_type.is_dir()
+            {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let variant = detect_kernel_variant(&name).to_string();
+                let installed = name == current_kernel;
+                installed_kernels.push(KernelInfo {
+                    name: name.clone(),
+                    version: String::new(),
+                    description: String::new(),
+                    variant,
+                    installed,
+                });
             }
         }
     }
@@ -692,51 +667,44 @@ galaxy/linux-zen 6.15.2.zen1-1 The Linux ZEN kernel and modules
         }
     }
     let prev_problematic: Vec<String> = vec![];
+    let hw = HardwareContext { gpu_type: prefs.gpu_type.clone(), nvidia, audio, cpu_level, form_factor };
     let scored_kernels: Vec<_> = all_kernels.iter().map(|k| {
+        let repo = available_kernels.iter().find(|a| a.name == k.name).map(|a| a.repo.clone()).unwrap_or_default();
         let (score, reason) = score_and_reason_kernel(&KernelRepoInfo {
             name: k.name.clone(),
             version: k.version.clone(),
             description: k.description.clone(),
-            _repo: String::new(),
-        }, &prefs.use_cases, &prefs.gpu_type, nvidia, audio, &prev_problematic);
+            repo,
+        }, &prefs.use_cases, &hw, &prev_problematic);
         (k, score, reason)
     }).collect();
     let mut top_kernels = scored_kernels;
-    top_kernels.sort_by(|a, b| b.1.cmp(&a.1));
+    top_kernels.sort_by_key(|k| std::cmp::Reverse(k.1));
     let needs_headers_pkg = prefs.use_cases.iter().any(|c| c.to_lowercase().contains("dev") || c.to_lowercase().contains("server"));
     println!("\n🤖 Top Kernel Recommendations (AI-Inferred):");
     for (i, (kernel, score, reason)) in top_kernels.iter().take(3).enumerate() {
         println!("{}. {} (Score: {})", i + 1, kernel.name, score);
         println!("   Variant: {}", kernel.variant);
         println!("   Reason: {}", reason);
-        if let Some(pm) = &sysinfo.package_manager {
-            if !kernel.installed {
-                let pkg_base = if kernel.name.starts_with("linux-") { kernel.name.clone() } else { kernel_package_name(&kernel.name) };
-                if needs_headers_pkg {
-                    let headers_pkg = format!("{}-headers", pkg_base);
-                    println!("   Install: sudo {} -S {} {}", pm, pkg_base, headers_pkg);
-                } else {
-                    println!("   Install: sudo {} -S {}", pm, pkg_base);
-                }
+        if let Some(pm) = &pm
+            && !kernel.installed
+        {
+            let pkg_base = if kernel.name.starts_with("linux-") { kernel.name.clone() } else { kernel_package_name(&kernel.name) };
+            let mut packages = vec![pkg_base.clone()];
+            if needs_headers_pkg {
+                packages.push(format!("{}-headers", pkg_base));
             }
+            println!("   Install: {}", pm.install_hint(&packages));
         }
     }
-    let headers_pkg = format!("{}-headers", kernel_package_name(&current_kernel));
-    if let Some(pm) = &sysinfo.package_manager {
-        if is_package_installed(pm, &headers_pkg) {
+    if let Some(pm) = &pm {
+        let headers_pkg = headers_package(pm.as_ref(), &current_kernel);
+        if pm.is_installed(&headers_pkg) {
             println!("🧵 Kernel headers package '{}' is installed.", headers_pkg);
         } else {
             println!("⚠️ Kernel headers package '{}' is NOT installed.", headers_pkg);
             println!("💡 Try installing it with:");
-            match pm.as_str() {
-                "pacman" => println!("    sudo pacman -S {}", headers_pkg),
-                "apt" => println!("    sudo apt install {}", headers_pkg),
-                "dnf" => println!("    sudo dnf install kernel-headers"),
-                "apk" => println!("    sudo apk add linux-headers"),
-                "zypper" => println!("    sudo zypper install kernel-devel"),
-                "emerge" => println!("    sudo emerge --ask sys-kernel/linux-headers"),
-                _ => println!("    [No install instructions available for {}]", pm),
-            }
+            println!("    {}", pm.install_hint(std::slice::from_ref(&headers_pkg)));
         }
     }
     let init = detect_init_system();
@@ -772,14 +740,18 @@ pub fn get_summary() -> String {
     let mut installed_kernels = vec![];
     if let Ok(entries) = std::fs::read_dir(modules_dir) {
         for entry in entries.flatten() {
-            if let Ok(file_type) = entry.file_type() {
-                if file_type.is_dir() {
-                    installed_kernels.push(entry.file_name().to_string_lossy().to_string());
-                }
+            if let Ok(file_type) = entry.file_type()
+                && file_type.is_dir()
+            {
+                installed_kernels.push(entry.file_name().to_string_lossy().to_string());
             }
         }
     }
     installed_kernels.sort();
+    let virt = virtualization::detect();
+    if virt.in_container() {
+        return format!("Kernel: {} (host kernel, {} container)", current_kernel, virt.container.as_deref().unwrap_or("unknown"));
+    }
     let mut summary = format!("Kernel: {}\nInstalled Kernels:", current_kernel);
     for kernel in &installed_kernels {
         if kernel == &current_kernel {
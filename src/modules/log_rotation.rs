@@ -0,0 +1,146 @@
+// log_rotation.rs
+// Append-only log files with size-based rotation and optional gzip compression of rotated files
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::Compression;
+use flate2::write::GzEncoder;
+
+#[derive(Debug, Clone)]
+pub struct Rotation {
+    /// Rotate once the live file reaches this size.
+    pub max_bytes: u64,
+    /// Rotated files to keep (path.1 … path.N); 0 truncates instead of rotating.
+    pub keep: usize,
+    /// Gzip rotated files (path.1.gz …).
+    pub compress: bool,
+}
+
+fn rotated_path(path: &Path, index: usize, compressed: bool) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{}", index));
+    if compressed {
+        name.push(".gz");
+    }
+    PathBuf::from(name)
+}
+
+/// Existing rotated file for `index`, compressed or not.
+fn existing_rotated(path: &Path, index: usize) -> Option<PathBuf> {
+    [true, false].into_iter().map(|c| rotated_path(path, index, c)).find(|p| p.exists())
+}
+
+fn gzip_file(source: &Path, target: &Path) -> io::Result<()> {
+    let mut input = File::open(source)?;
+    let mut encoder = GzEncoder::new(File::create(target)?, Compression::default());
+    io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?;
+    fs::remove_file(source)
+}
+
+/// Index of a rotated file of `path` (path.3 or path.3.gz), None for anything else.
+fn rotated_index(path: &Path, candidate: &Path) -> Option<usize> {
+    let base = path.file_name()?.to_str()?;
+    let name = candidate.file_name()?.to_str()?;
+    let suffix = name.strip_prefix(base)?.strip_prefix('.')?;
+    suffix.strip_suffix(".gz").unwrap_or(suffix).parse().ok().filter(|&i| i > 0)
+}
+
+/// Delete rotated files with an index of `keep` or more, including those left over from a
+/// larger `keep` setting.
+fn prune(path: &Path, keep: usize) -> io::Result<()> {
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    for entry in fs::read_dir(dir)?.flatten() {
+        let candidate = entry.path();
+        if rotated_index(path, &candidate).is_some_and(|i| i >= keep) {
+            fs::remove_file(candidate)?;
+        }
+    }
+    Ok(())
+}
+
+/// Shift path.N-1 → path.N … path → path.1, dropping what falls off the end.
+fn rotate(path: &Path, rotation: &Rotation) -> io::Result<()> {
+    prune(path, rotation.keep.max(1))?;
+    if rotation.keep == 0 {
+        return fs::remove_file(path);
+    }
+    for index in (1..rotation.keep).rev() {
+        if let Some(current) = existing_rotated(path, index) {
+            let compressed = current.extension().is_some_and(|e| e == "gz");
+            fs::rename(&current, rotated_path(path, index + 1, compressed))?;
+        }
+    }
+    if rotation.compress {
+        gzip_file(path, &rotated_path(path, 1, true))
+    } else {
+        fs::rename(path, rotated_path(path, 1, false))
+    }
+}
+
+/// Append `data` (plus a newline) to `path`, rotating first if the file has grown too large.
+pub fn append(path: &Path, data: &str, rotation: &Rotation) -> io::Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::metadata(path).map(|m| m.len() >= rotation.max_bytes).unwrap_or(false) {
+        rotate(path, rotation)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::sysfs;
+
+    #[test]
+    fn rotated_names() {
+        let log = Path::new("/var/log/hardware.log");
+        assert_eq!(rotated_path(log, 2, false), Path::new("/var/log/hardware.log.2"));
+        assert_eq!(rotated_path(log, 1, true), Path::new("/var/log/hardware.log.1.gz"));
+        assert_eq!(rotated_index(log, Path::new("/var/log/hardware.log.12.gz")), Some(12));
+        assert_eq!(rotated_index(log, Path::new("/var/log/hardware.log.3")), Some(3));
+        assert_eq!(rotated_index(log, Path::new("/var/log/hardware.log")), None);
+        assert_eq!(rotated_index(log, Path::new("/var/log/hardware.log.old")), None);
+        assert_eq!(rotated_index(log, Path::new("/var/log/other.log.1")), None);
+    }
+
+    #[test]
+    fn rotate_shifts_and_prunes() {
+        let dir = sysfs::scratch_dir("rotate");
+        let log = dir.join("hardware.log");
+        // Leftovers from an earlier keep = 5
+        for index in 1..=5 {
+            fs::write(rotated_path(&log, index, false), format!("{}\n", index)).unwrap();
+        }
+        fs::write(&log, "live\n").unwrap();
+        rotate(&log, &Rotation { max_bytes: 0, keep: 3, compress: false }).unwrap();
+
+        assert!(!log.exists());
+        assert_eq!(fs::read_to_string(rotated_path(&log, 1, false)).unwrap(), "live\n");
+        assert_eq!(fs::read_to_string(rotated_path(&log, 2, false)).unwrap(), "1\n");
+        assert_eq!(fs::read_to_string(rotated_path(&log, 3, false)).unwrap(), "2\n");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 3, "files beyond keep are removed");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn append_rotates_with_compression() {
+        let dir = sysfs::scratch_dir("append");
+        let log = dir.join("hardware.log");
+        let rotation = Rotation { max_bytes: 4, keep: 2, compress: true };
+        append(&log, "first", &rotation).unwrap();
+        append(&log, "second", &rotation).unwrap();
+        assert_eq!(fs::read_to_string(&log).unwrap(), "second\n");
+        assert!(rotated_path(&log, 1, true).exists());
+
+        append(&log, "third", &Rotation { keep: 0, ..rotation }).unwrap();
+        assert_eq!(fs::read_to_string(&log).unwrap(), "third\n");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1, "keep = 0 leaves only the live file");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,423 @@
+// memory_info.rs
+// Memory, swap, zram/zswap, THP and pressure report with DIMM details and sizing advice
+
+use std::collections::HashMap;
+use std::fs;
+
+use super::kernel_check;
+use super::smbios;
+use super::sysfs;
+
+const MEMINFO_PATH: &str = "/proc/meminfo";
+const SWAPS_PATH: &str = "/proc/swaps";
+const PRESSURE_PATH: &str = "/proc/pressure/memory";
+const ZSWAP_PARAMS_DIR: &str = "/sys/module/zswap/parameters";
+const THP_DIR: &str = "/sys/kernel/mm/transparent_hugepage";
+const SWAPPINESS_PATH: &str = "/proc/sys/vm/swappiness";
+const SMBIOS_MEMORY_ARRAY: u8 = 16;
+const SMBIOS_MEMORY_DEVICE: u8 = 17;
+const GIB_KIB: u64 = 1024 * 1024;
+
+/// /proc/meminfo values in KiB, keyed by field name ("MemTotal", "SwapFree", ...).
+pub fn parse_meminfo(content: &str) -> HashMap<String, u64> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (key, rest) = line.split_once(':')?;
+            let value = rest.split_whitespace().next()?.parse().ok()?;
+            Some((key.trim().to_string(), value))
+        })
+        .collect()
+}
+
+pub fn read_meminfo() -> HashMap<String, u64> {
+    parse_meminfo(&fs::read_to_string(MEMINFO_PATH).unwrap_or_default())
+}
+
+/// Total and available memory in KiB. Available legitimately reads 0 under extreme pressure;
+/// kernels older than 3.14 lack MemAvailable, in which case MemFree is used.
+pub fn total_and_available(meminfo: &HashMap<String, u64>) -> Option<(u64, u64)> {
+    let total = *meminfo.get("MemTotal").filter(|t| **t > 0)?;
+    let available = meminfo.get("MemAvailable").or_else(|| meminfo.get("MemFree")).copied().unwrap_or(0);
+    Some((total, available))
+}
+
+#[derive(Debug, Clone)]
+pub struct SwapArea {
+    pub name: String,
+    pub kind: String,
+    pub size_kib: u64,
+    pub used_kib: u64,
+    pub priority: i64,
+}
+
+pub fn read_swaps() -> Vec<SwapArea> {
+    fs::read_to_string(SWAPS_PATH)
+        .unwrap_or_default()
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            Some(SwapArea {
+                // Paths with spaces are escaped as \040
+                name: fields.first()?.replace("\\040", " "),
+                kind: fields.get(1)?.to_string(),
+                size_kib: fields.get(2)?.parse().ok()?,
+                used_kib: fields.get(3)?.parse().ok()?,
+                priority: fields.get(4)?.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct ZramDevice {
+    pub name: String,
+    pub disksize: u64,
+    pub algorithm: Option<String>,
+    /// Uncompressed data stored and memory actually used, in bytes (from mm_stat).
+    pub original_bytes: u64,
+    pub used_bytes: u64,
+}
+
+pub fn read_zram() -> Vec<ZramDevice> {
+    sysfs::list_dir_prefixed("/sys/block", "zram")
+        .into_iter()
+        .filter_map(|dir| {
+            let disksize = sysfs::read_u64(dir.join("disksize")).filter(|s| *s > 0)?;
+            // "lzo lzo-rle [zstd]" marks the active algorithm in brackets
+            let algorithm = sysfs::read_trimmed(dir.join("comp_algorithm")).and_then(|s| sysfs::bracketed_choice(&s));
+            let stats: Vec<u64> = sysfs::read_trimmed(dir.join("mm_stat"))
+                .unwrap_or_default()
+                .split_whitespace()
+                .filter_map(|v| v.parse().ok())
+                .collect();
+            Some(ZramDevice {
+                name: dir.file_name()?.to_string_lossy().to_string(),
+                disksize,
+                algorithm,
+                original_bytes: stats.first().copied().unwrap_or(0),
+                used_bytes: stats.get(2).copied().unwrap_or(0),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Zswap {
+    pub enabled: bool,
+    pub compressor: Option<String>,
+    pub zpool: Option<String>,
+    pub max_pool_percent: Option<u64>,
+}
+
+pub fn read_zswap() -> Option<Zswap> {
+    let enabled = sysfs::read_trimmed(format!("{}/enabled", ZSWAP_PARAMS_DIR))?;
+    Some(Zswap {
+        enabled: enabled == "Y" || enabled == "1",
+        compressor: sysfs::read_trimmed(format!("{}/compressor", ZSWAP_PARAMS_DIR)),
+        zpool: sysfs::read_trimmed(format!("{}/zpool", ZSWAP_PARAMS_DIR)),
+        max_pool_percent: sysfs::read_u64(format!("{}/max_pool_percent", ZSWAP_PARAMS_DIR)),
+    })
+}
+
+/// The selected transparent hugepage mode, e.g. "madvise" for `enabled` or `defrag`.
+fn thp_mode(attr: &str) -> Option<String> {
+    sysfs::bracketed_choice(&sysfs::read_trimmed(format!("{}/{}", THP_DIR, attr))?)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Pressure {
+    pub some_avg10: f64,
+    pub some_avg60: f64,
+    pub full_avg60: f64,
+}
+
+pub fn read_pressure() -> Option<Pressure> {
+    let content = fs::read_to_string(PRESSURE_PATH).ok()?;
+    let field = |kind: &str, name: &str| -> f64 {
+        content
+            .lines()
+            .find(|l| l.starts_with(kind))
+            .and_then(|l| l.split_whitespace().find_map(|f| f.strip_prefix(name)?.strip_prefix('=')?.parse().ok()))
+            .unwrap_or(0.0)
+    };
+    Some(Pressure {
+        some_avg10: field("some", "avg10"),
+        some_avg60: field("some", "avg60"),
+        full_avg60: field("full", "avg60"),
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct Dimm {
+    pub locator: String,
+    pub size_mib: u64,
+    pub kind: Option<&'static str>,
+    pub speed_mts: Option<u16>,
+    pub configured_speed_mts: Option<u16>,
+    pub manufacturer: Option<String>,
+    pub part_number: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MemoryArray {
+    pub slots: Option<u16>,
+    pub error_correction: Option<&'static str>,
+}
+
+fn memory_type_name(code: u8) -> Option<&'static str> {
+    Some(match code {
+        0x12 => "DDR",
+        0x13 => "DDR2",
+        0x18 => "DDR3",
+        0x1A => "DDR4",
+        0x1B => "LPDDR",
+        0x1C => "LPDDR2",
+        0x1D => "LPDDR3",
+        0x1E => "LPDDR4",
+        0x22 => "DDR5",
+        0x23 => "LPDDR5",
+        _ => return None,
+    })
+}
+
+fn error_correction_name(code: u8) -> Option<&'static str> {
+    Some(match code {
+        0x03 => "None",
+        0x04 => "Parity",
+        0x05 => "Single-bit ECC",
+        0x06 => "Multi-bit ECC",
+        0x07 => "CRC",
+        _ => return None,
+    })
+}
+
+/// Decode an SMBIOS type 17 (memory device) structure; empty slots have a size of 0.
+fn dimm_from(s: &smbios::Structure) -> Option<Dimm> {
+    let size = s.word(0x0C)?;
+    let size_mib = match size {
+        0 | 0xFFFF => 0,
+        // Size in extended field at 0x1C
+        0x7FFF => s.dword(0x1C).unwrap_or(0) as u64 & 0x7FFF_FFFF,
+        // Bit 15 set means the value is in KiB
+        s if s & 0x8000 != 0 => (s & 0x7FFF) as u64 / 1024,
+        s => s as u64,
+    };
+    let speed = |offset| s.word(offset).filter(|v| *v != 0 && *v != 0xFFFF);
+    Some(Dimm {
+        locator: s.string(0x10).unwrap_or("unknown").to_string(),
+        size_mib,
+        kind: s.byte(0x12).and_then(memory_type_name),
+        speed_mts: speed(0x15),
+        configured_speed_mts: speed(0x20),
+        manufacturer: s.string(0x17).map(|v| v.to_string()),
+        part_number: s.string(0x1A).map(|v| v.to_string()),
+    })
+}
+
+/// SMBIOS type 16 (Physical Memory Array) and type 17 (Memory Device) entries; empty without root.
+pub fn read_dimms() -> (Vec<MemoryArray>, Vec<Dimm>) {
+    let arrays = smbios::read_entries(SMBIOS_MEMORY_ARRAY)
+        .iter()
+        .map(|s| MemoryArray { slots: s.word(0x0D), error_correction: s.byte(0x06).and_then(error_correction_name) })
+        .collect();
+    let dimms = smbios::read_entries(SMBIOS_MEMORY_DEVICE).iter().filter_map(dimm_from).collect();
+    (arrays, dimms)
+}
+
+/// Swap and zram sizing advice for the given use cases.
+pub fn advice(total_kib: u64, swaps: &[SwapArea], zram: &[ZramDevice], zswap: Option<&Zswap>, use_cases: &[String]) -> Vec<String> {
+    let has = |name: &str| use_cases.iter().any(|c| c.to_lowercase().contains(name));
+    let ram_gib = (total_kib as f64 / GIB_KIB as f64).round().max(1.0) as u64;
+    let disk_swap_kib: u64 = swaps.iter().filter(|s| !s.name.contains("zram")).map(|s| s.size_kib).sum();
+    let zswap_on = zswap.is_some_and(|z| z.enabled);
+    let mut tips = Vec::new();
+
+    if zram.is_empty() && disk_swap_kib == 0 {
+        tips.push(format!(
+            "No swap configured. A zram device of {} GiB (half of RAM, zstd) avoids OOM kills without disk I/O.",
+            (ram_gib / 2).clamp(1, 16)
+        ));
+    }
+    if !zram.is_empty() && zswap_on {
+        tips.push("zswap is enabled in front of zram; compressed pages get compressed twice. Disable zswap (zswap.enabled=0).".to_string());
+    }
+    if has("gaming") && zram.is_empty() {
+        tips.push(format!("Gaming: zram sized up to RAM ({} GiB) with vm.swappiness=180 keeps stutter from disk swap away.", ram_gib.min(16)));
+    }
+    if has("dev") && ram_gib <= 16 {
+        tips.push("Development: parallel builds can exceed RAM; keep zram plus a disk swap file of at least 8 GiB.".to_string());
+    }
+    if has("server") {
+        if disk_swap_kib == 0 {
+            tips.push("Server: a small disk swap (2-8 GiB) lets the kernel evict cold anonymous pages instead of page cache.".to_string());
+        }
+        if thp_mode("enabled").as_deref() == Some("always") {
+            tips.push("Server: databases (PostgreSQL, Redis, MongoDB) recommend transparent hugepages = madvise, not always.".to_string());
+        }
+    }
+    if has("audio") && sysfs::read_u64(SWAPPINESS_PATH).is_some_and(|s| s > 10) && zram.is_empty() {
+        tips.push("Audio: swapping during recording causes xruns; consider vm.swappiness=10.".to_string());
+    }
+    if has("battery") && disk_swap_kib < total_kib {
+        tips.push(format!(
+            "Hibernation needs a disk swap at least as large as RAM ({}); current disk swap is {}.",
+            sysfs::format_kib(total_kib),
+            sysfs::format_kib(disk_swap_kib)
+        ));
+    }
+    tips
+}
+
+/// Entry point for `nephyra memory`.
+pub fn run() {
+    println!("🧠 Memory");
+    let meminfo = read_meminfo();
+    let Some((total, available)) = total_and_available(&meminfo) else {
+        eprintln!("❌ Could not read {}", MEMINFO_PATH);
+        return;
+    };
+    let get = |key: &str| meminfo.get(key).copied().unwrap_or(0);
+    println!("  Total: {}, Available: {} ({:.0}%)", sysfs::format_kib(total), sysfs::format_kib(available), available as f64 * 100.0 / total as f64);
+    println!(
+        "  Cached: {}, Buffers: {}, Shared: {}, Dirty: {}",
+        sysfs::format_kib(get("Cached")),
+        sysfs::format_kib(get("Buffers")),
+        sysfs::format_kib(get("Shmem")),
+        sysfs::format_kib(get("Dirty"))
+    );
+    if get("HugePages_Total") > 0 {
+        println!("  Huge pages: {} total, {} free", get("HugePages_Total"), get("HugePages_Free"));
+    }
+
+    let swaps = read_swaps();
+    println!("\n💱 Swap: {} used of {}", sysfs::format_kib(get("SwapTotal").saturating_sub(get("SwapFree"))), sysfs::format_kib(get("SwapTotal")));
+    for s in &swaps {
+        println!("  {} ({}): {} used of {}, priority {}", s.name, s.kind, sysfs::format_kib(s.used_kib), sysfs::format_kib(s.size_kib), s.priority);
+    }
+    let zram = read_zram();
+    for z in &zram {
+        let ratio = if z.used_bytes > 0 { z.original_bytes as f64 / z.used_bytes as f64 } else { 0.0 };
+        println!(
+            "  {}: {} disk size, {}, {} stored in {} (ratio {:.1})",
+            z.name,
+            sysfs::format_kib(z.disksize / 1024),
+            z.algorithm.as_deref().unwrap_or("unknown algorithm"),
+            sysfs::format_kib(z.original_bytes / 1024),
+            sysfs::format_kib(z.used_bytes / 1024),
+            ratio
+        );
+    }
+    let zswap = read_zswap();
+    if let Some(z) = &zswap {
+        println!(
+            "  zswap: {}{}",
+            if z.enabled { "enabled" } else { "disabled" },
+            if z.enabled {
+                format!(
+                    " ({} / {}, max pool {}%)",
+                    z.compressor.as_deref().unwrap_or("?"),
+                    z.zpool.as_deref().unwrap_or("?"),
+                    z.max_pool_percent.unwrap_or(0)
+                )
+            } else {
+                String::new()
+            }
+        );
+    }
+    if let Some(s) = sysfs::read_u64(SWAPPINESS_PATH) {
+        println!("  vm.swappiness: {}", s);
+    }
+    if let Some(thp) = thp_mode("enabled") {
+        let defrag = thp_mode("defrag").unwrap_or_else(|| "?".to_string());
+        println!("  Transparent hugepages: {} (defrag: {})", thp, defrag);
+    }
+
+    if let Some(p) = read_pressure() {
+        println!("\n📉 Pressure: some {:.2}% (10s) {:.2}% (60s), full {:.2}% (60s)", p.some_avg10, p.some_avg60, p.full_avg60);
+        if p.some_avg60 > 10.0 {
+            println!("  ⚠️ Tasks are frequently stalled waiting for memory.");
+        }
+    }
+
+    let (arrays, dimms) = read_dimms();
+    if dimms.is_empty() {
+        println!("\n🧩 DIMMs: SMBIOS tables not readable (run as root for slot details)");
+    } else {
+        let slots: u16 = arrays.iter().filter_map(|a| a.slots).sum();
+        let populated = dimms.iter().filter(|d| d.size_mib > 0).count();
+        let ecc = arrays.iter().find_map(|a| a.error_correction).unwrap_or("unknown");
+        println!("\n🧩 DIMMs: {} of {} slots populated, error correction: {}", populated, slots.max(dimms.len() as u16), ecc);
+        for d in dimms.iter().filter(|d| d.size_mib > 0) {
+            let speed = match (d.speed_mts, d.configured_speed_mts) {
+                (Some(max), Some(cur)) if cur != max => format!("{} MT/s (rated {})", cur, max),
+                (Some(s), _) | (None, Some(s)) => format!("{} MT/s", s),
+                _ => "unknown speed".to_string(),
+            };
+            println!(
+                "  {}: {} {} {}, {} {}",
+                d.locator,
+                sysfs::format_kib(d.size_mib * 1024),
+                d.kind.unwrap_or(""),
+                speed,
+                d.manufacturer.as_deref().unwrap_or(""),
+                d.part_number.as_deref().unwrap_or("")
+            );
+        }
+    }
+
+    let use_cases = kernel_check::effective_use_cases();
+    let tips = advice(total, &swaps, &zram, zswap.as_ref(), &use_cases);
+    if !tips.is_empty() {
+        println!("\n💡 Advice for use cases: {}", use_cases.join(", "));
+        for tip in tips {
+            println!("  - {}", tip);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An SMBIOS 3.x type 17 structure (0x22 bytes) with locator, manufacturer and part number strings.
+    fn memory_device(size: u16, extended_mib: u32, kind: u8, speed: u16, configured: u16) -> Vec<u8> {
+        let mut raw = vec![0u8; 0x22];
+        raw[0] = 17;
+        raw[1] = 0x22;
+        raw[0x0C..0x0E].copy_from_slice(&size.to_le_bytes());
+        raw[0x10] = 1;
+        raw[0x12] = kind;
+        raw[0x15..0x17].copy_from_slice(&speed.to_le_bytes());
+        raw[0x17] = 2;
+        raw[0x1A] = 3;
+        raw[0x1C..0x20].copy_from_slice(&extended_mib.to_le_bytes());
+        raw[0x20..0x22].copy_from_slice(&configured.to_le_bytes());
+        raw.extend_from_slice(b"DIMM A1\0Samsung\0M425R2GA3BB0-CQKOD\0\0");
+        raw
+    }
+
+    fn dimm(raw: &[u8]) -> Dimm {
+        dimm_from(&smbios::Structure::parse(raw).unwrap().0).unwrap()
+    }
+
+    #[test]
+    fn memory_device_sizes() {
+        let d = dimm(&memory_device(16384, 0, 0x22, 5600, 5200));
+        assert_eq!(d.locator, "DIMM A1");
+        assert_eq!(d.size_mib, 16384);
+        assert_eq!(d.kind, Some("DDR5"));
+        assert_eq!(d.speed_mts, Some(5600));
+        assert_eq!(d.configured_speed_mts, Some(5200));
+        assert_eq!(d.manufacturer.as_deref(), Some("Samsung"));
+        assert_eq!(d.part_number.as_deref(), Some("M425R2GA3BB0-CQKOD"));
+
+        assert_eq!(dimm(&memory_device(0x7FFF, 65536, 0x22, 0, 0)).size_mib, 65536, "extended size field");
+        assert_eq!(dimm(&memory_device(0x8000 | 512, 0, 0x1A, 0, 0)).size_mib, 0, "512 KiB rounds down");
+        let empty = dimm(&memory_device(0, 0, 0x02, 0xFFFF, 0));
+        assert_eq!(empty.size_mib, 0);
+        assert_eq!(empty.kind, None);
+        assert_eq!(empty.speed_mts, None);
+    }
+}
@@ -0,0 +1,350 @@
+// netlink.rs
+// Minimal rtnetlink/generic netlink client: interface addresses, routes and the nl80211 regulatory domain
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+const NLMSG_HDR_LEN: usize = 16;
+const NLMSG_ERROR: u16 = 2;
+const NLMSG_DONE: u16 = 3;
+const NLM_F_REQUEST: u16 = 0x1;
+const NLM_F_DUMP: u16 = 0x300;
+const RECV_TIMEOUT_SECS: libc::time_t = 2;
+
+const RTM_GETADDR: u16 = 22;
+const RTM_GETROUTE: u16 = 26;
+const IFA_ADDRESS: u16 = 1;
+const IFA_LOCAL: u16 = 2;
+const RTA_DST: u16 = 1;
+const RTA_OIF: u16 = 4;
+const RTA_GATEWAY: u16 = 5;
+const RTA_PRIORITY: u16 = 6;
+const RTA_TABLE: u16 = 15;
+const RT_TABLE_MAIN: u32 = 254;
+const RTN_UNICAST: u8 = 1;
+
+const GENL_ID_CTRL: u16 = 0x10;
+const CTRL_CMD_GETFAMILY: u8 = 3;
+const CTRL_ATTR_FAMILY_ID: u16 = 1;
+const CTRL_ATTR_FAMILY_NAME: u16 = 2;
+const NL80211_CMD_GET_REG: u8 = 31;
+const NL80211_ATTR_REG_ALPHA2: u16 = 33;
+
+/// One netlink message: its type and the payload after the 16-byte header.
+pub struct Message {
+    pub kind: u16,
+    pub payload: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Address {
+    pub index: u32,
+    pub address: IpAddr,
+    pub prefix_len: u8,
+    /// RT_SCOPE_* value: 0 = global, 253 = link, 254 = host.
+    pub scope: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct Route {
+    /// None for the default route.
+    pub destination: Option<(IpAddr, u8)>,
+    pub gateway: Option<IpAddr>,
+    pub out_index: Option<u32>,
+    pub metric: Option<u32>,
+    /// RTPROT_* value: 2 = kernel, 3 = boot, 4 = static, 16 = dhcp, ...
+    pub protocol: u8,
+}
+
+fn align4(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_ne_bytes(data.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_ne_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+/// Split a run of netlink/rtnetlink attributes into (type, value) pairs.
+pub fn parse_attributes(mut data: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut attrs = Vec::new();
+    while let (Some(len), Some(kind)) = (u16_at(data, 0), u16_at(data, 2)) {
+        let len = len as usize;
+        if len < 4 || len > data.len() {
+            break;
+        }
+        // The top two bits are the nested/byte-order flags
+        attrs.push((kind & 0x3FFF, &data[4..len]));
+        data = &data[align4(len).min(data.len())..];
+    }
+    attrs
+}
+
+fn push_attribute(buf: &mut Vec<u8>, kind: u16, value: &[u8]) {
+    buf.extend_from_slice(&((4 + value.len()) as u16).to_ne_bytes());
+    buf.extend_from_slice(&kind.to_ne_bytes());
+    buf.extend_from_slice(value);
+    buf.resize(align4(buf.len()), 0);
+}
+
+/// Split a received datagram into messages.
+pub fn parse_messages(mut data: &[u8]) -> Vec<Message> {
+    let mut messages = Vec::new();
+    while let (Some(len), Some(kind)) = (u32_at(data, 0), u16_at(data, 4)) {
+        let len = len as usize;
+        if len < NLMSG_HDR_LEN || len > data.len() {
+            break;
+        }
+        messages.push(Message { kind, payload: data[NLMSG_HDR_LEN..len].to_vec() });
+        data = &data[align4(len).min(data.len())..];
+    }
+    messages
+}
+
+fn ip_from(bytes: &[u8]) -> Option<IpAddr> {
+    match bytes.len() {
+        4 => Some(IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(bytes).ok()?))),
+        16 => Some(IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(bytes).ok()?))),
+        _ => None,
+    }
+}
+
+/// Send one request and collect the replies; dumps are read until NLMSG_DONE.
+fn request(protocol: libc::c_int, kind: u16, flags: u16, body: &[u8]) -> io::Result<Vec<Message>> {
+    // SAFETY: plain socket(2) call; the returned descriptor is owned by OwnedFd below.
+    let fd = unsafe { libc::socket(libc::AF_NETLINK, libc::SOCK_RAW | libc::SOCK_CLOEXEC, protocol) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: fd is a freshly created, valid descriptor not owned elsewhere.
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+    // A request the kernel never answers must not hang the whole report
+    let timeout = libc::timeval { tv_sec: RECV_TIMEOUT_SECS, tv_usec: 0 };
+    // SAFETY: timeout is a valid timeval for the length passed.
+    let set = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_RCVTIMEO,
+            &timeout as *const libc::timeval as *const libc::c_void,
+            std::mem::size_of::<libc::timeval>() as libc::socklen_t,
+        )
+    };
+    if set < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut packet = Vec::with_capacity(NLMSG_HDR_LEN + body.len());
+    packet.extend_from_slice(&((NLMSG_HDR_LEN + body.len()) as u32).to_ne_bytes());
+    packet.extend_from_slice(&kind.to_ne_bytes());
+    packet.extend_from_slice(&(NLM_F_REQUEST | flags).to_ne_bytes());
+    packet.extend_from_slice(&1u32.to_ne_bytes());
+    packet.extend_from_slice(&0u32.to_ne_bytes());
+    packet.extend_from_slice(body);
+    // SAFETY: sockaddr_nl is plain data; all-zero addresses the kernel.
+    let mut kernel: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+    kernel.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+    // SAFETY: packet and kernel are valid for the lengths passed.
+    let sent = unsafe {
+        libc::sendto(
+            socket.as_raw_fd(),
+            packet.as_ptr() as *const libc::c_void,
+            packet.len(),
+            0,
+            &kernel as *const libc::sockaddr_nl as *const libc::sockaddr,
+            std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+        )
+    };
+    if sent < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut replies = Vec::new();
+    let mut buf = vec![0u8; 32 * 1024];
+    loop {
+        // SAFETY: buf is a live buffer of buf.len() bytes.
+        let received = unsafe { libc::recv(socket.as_raw_fd(), buf.as_mut_ptr() as *mut libc::c_void, buf.len(), 0) };
+        if received < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        for message in parse_messages(&buf[..received as usize]) {
+            match message.kind {
+                NLMSG_DONE => return Ok(replies),
+                NLMSG_ERROR => {
+                    // The payload starts with a negative errno; 0 is a plain acknowledgement
+                    let errno = u32_at(&message.payload, 0).unwrap_or(0) as i32;
+                    if errno != 0 {
+                        return Err(io::Error::from_raw_os_error(-errno));
+                    }
+                    return Ok(replies);
+                }
+                _ => replies.push(message),
+            }
+        }
+        if flags & NLM_F_DUMP == 0 && !replies.is_empty() {
+            return Ok(replies);
+        }
+    }
+}
+
+/// One RTM_NEWADDR reply: struct ifaddrmsg followed by IFA_* attributes.
+fn parse_address(m: &Message) -> Option<Address> {
+    let (header, attrs) = (m.payload.get(..8)?, parse_attributes(m.payload.get(8..)?));
+    // Point-to-point links carry the local address in IFA_LOCAL and the peer in IFA_ADDRESS
+    let value = attrs
+        .iter()
+        .find(|(k, _)| *k == IFA_LOCAL)
+        .or_else(|| attrs.iter().find(|(k, _)| *k == IFA_ADDRESS))?
+        .1;
+    Some(Address { index: u32_at(header, 4)?, address: ip_from(value)?, prefix_len: header[1], scope: header[3] })
+}
+
+/// One RTM_NEWROUTE reply: struct rtmsg followed by RTA_* attributes; None unless it is a
+/// unicast route of the main table.
+fn parse_route(m: &Message) -> Option<Route> {
+    let header = m.payload.get(..12)?;
+    let attrs = parse_attributes(m.payload.get(12..)?);
+    let find = |kind: u16| attrs.iter().find(|(k, _)| *k == kind).map(|(_, v)| *v);
+    let table = find(RTA_TABLE).and_then(|v| u32_at(v, 0)).unwrap_or(header[4] as u32);
+    if table != RT_TABLE_MAIN || header[7] != RTN_UNICAST {
+        return None;
+    }
+    Some(Route {
+        destination: find(RTA_DST).and_then(ip_from).map(|ip| (ip, header[1])),
+        gateway: find(RTA_GATEWAY).and_then(ip_from),
+        out_index: find(RTA_OIF).and_then(|v| u32_at(v, 0)),
+        metric: find(RTA_PRIORITY).and_then(|v| u32_at(v, 0)),
+        protocol: header[5],
+    })
+}
+
+/// All interface addresses (IPv4 and IPv6).
+pub fn addresses() -> io::Result<Vec<Address>> {
+    // struct ifaddrmsg with AF_UNSPEC asks for every family
+    let replies = request(libc::NETLINK_ROUTE, RTM_GETADDR, NLM_F_DUMP, &[0u8; 8])?;
+    Ok(replies.iter().filter_map(parse_address).collect())
+}
+
+/// Unicast routes of the main table (what `ip route` and `ip -6 route` show).
+pub fn routes() -> io::Result<Vec<Route>> {
+    // struct rtmsg with AF_UNSPEC asks for every family
+    let replies = request(libc::NETLINK_ROUTE, RTM_GETROUTE, NLM_F_DUMP, &[0u8; 12])?;
+    Ok(replies.iter().filter_map(parse_route).collect())
+}
+
+/// Resolve a generic netlink family name ("nl80211") to its dynamic message type.
+fn genl_family_id(name: &str) -> io::Result<Option<u16>> {
+    let mut body = vec![CTRL_CMD_GETFAMILY, 1, 0, 0];
+    let mut value = name.as_bytes().to_vec();
+    value.push(0);
+    push_attribute(&mut body, CTRL_ATTR_FAMILY_NAME, &value);
+    let replies = request(libc::NETLINK_GENERIC, GENL_ID_CTRL, 0, &body)?;
+    Ok(replies.iter().find_map(|m| {
+        parse_attributes(m.payload.get(4..)?)
+            .into_iter()
+            .find(|(k, _)| *k == CTRL_ATTR_FAMILY_ID)
+            .and_then(|(_, v)| u16_at(v, 0))
+    }))
+}
+
+/// The global wireless regulatory domain (ISO 3166 alpha2, "00" = world) from nl80211.
+pub fn regulatory_domain() -> io::Result<Option<String>> {
+    let Some(family) = genl_family_id("nl80211")? else {
+        return Ok(None);
+    };
+    let replies = request(libc::NETLINK_GENERIC, family, 0, &[NL80211_CMD_GET_REG, 0, 0, 0])?;
+    Ok(replies.iter().find_map(|m| {
+        parse_attributes(m.payload.get(4..)?)
+            .into_iter()
+            .find(|(k, _)| *k == NL80211_ATTR_REG_ALPHA2)
+            .map(|(_, v)| String::from_utf8_lossy(v).trim_end_matches('\0').to_string())
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A netlink message with the given type around `payload`, padded like the kernel does.
+    fn message(kind: u16, payload: &[u8]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&((NLMSG_HDR_LEN + payload.len()) as u32).to_ne_bytes());
+        buf.extend_from_slice(&kind.to_ne_bytes());
+        buf.extend_from_slice(&[0u8; 10]);
+        buf.extend_from_slice(payload);
+        buf.resize(align4(buf.len()), 0);
+        buf
+    }
+
+    #[test]
+    fn attributes_are_split_and_aligned() {
+        let mut buf = Vec::new();
+        push_attribute(&mut buf, CTRL_ATTR_FAMILY_NAME, b"nl80211\0");
+        push_attribute(&mut buf, CTRL_ATTR_FAMILY_ID | 0x8000, &[0x1c, 0x00, 0x00]);
+        push_attribute(&mut buf, RTA_OIF, &3u32.to_ne_bytes());
+        let attrs = parse_attributes(&buf);
+        assert_eq!(attrs.len(), 3);
+        assert_eq!(attrs[0], (CTRL_ATTR_FAMILY_NAME, &b"nl80211\0"[..]));
+        assert_eq!(attrs[1], (CTRL_ATTR_FAMILY_ID, &[0x1c, 0x00, 0x00][..]), "flag bits are masked and padding skipped");
+        assert_eq!(attrs[2].1, &3u32.to_ne_bytes()[..]);
+    }
+
+    #[test]
+    fn truncated_attributes_stop_parsing() {
+        let mut buf = Vec::new();
+        push_attribute(&mut buf, RTA_OIF, &3u32.to_ne_bytes());
+        buf.extend_from_slice(&(40u16).to_ne_bytes());
+        buf.extend_from_slice(&RTA_GATEWAY.to_ne_bytes());
+        assert_eq!(parse_attributes(&buf).len(), 1);
+        assert!(parse_attributes(&[2, 0]).is_empty());
+    }
+
+    #[test]
+    fn messages_from_one_datagram() {
+        let mut data = message(20, &[1, 2, 3]);
+        data.extend(message(NLMSG_DONE, &[0u8; 4]));
+        let messages = parse_messages(&data);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].kind, 20);
+        assert_eq!(messages[0].payload, vec![1, 2, 3]);
+        assert_eq!(messages[1].kind, NLMSG_DONE);
+        assert!(parse_messages(&data[..10]).is_empty(), "a short header is ignored");
+    }
+
+    #[test]
+    fn address_prefers_local_over_peer() {
+        // ifaddrmsg: AF_INET, prefix 24, flags, scope global, index 2
+        let mut payload = vec![libc::AF_INET as u8, 24, 0, 0];
+        payload.extend_from_slice(&2u32.to_ne_bytes());
+        push_attribute(&mut payload, IFA_ADDRESS, &[10, 0, 0, 1]);
+        push_attribute(&mut payload, IFA_LOCAL, &[10, 0, 0, 2]);
+        let address = parse_address(&Message { kind: 20, payload }).unwrap();
+        assert_eq!(address.index, 2);
+        assert_eq!(address.address, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)));
+        assert_eq!(address.prefix_len, 24);
+        assert_eq!(address.scope, 0);
+    }
+
+    #[test]
+    fn routes_of_the_main_table_only() {
+        // rtmsg: AF_INET, dst_len 0, src_len, tos, table main, protocol dhcp, scope, type unicast, flags
+        let mut payload = vec![libc::AF_INET as u8, 0, 0, 0, RT_TABLE_MAIN as u8, 16, 0, RTN_UNICAST, 0, 0, 0, 0];
+        push_attribute(&mut payload, RTA_GATEWAY, &[192, 168, 1, 1]);
+        push_attribute(&mut payload, RTA_OIF, &3u32.to_ne_bytes());
+        push_attribute(&mut payload, RTA_PRIORITY, &600u32.to_ne_bytes());
+        let route = parse_route(&Message { kind: 24, payload: payload.clone() }).unwrap();
+        assert_eq!(route.destination, None);
+        assert_eq!(route.gateway, Some(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1))));
+        assert_eq!(route.out_index, Some(3));
+        assert_eq!(route.metric, Some(600));
+        assert_eq!(route.protocol, 16);
+
+        // The local table (255) is skipped, RTA_TABLE overriding the header byte
+        push_attribute(&mut payload, RTA_TABLE, &255u32.to_ne_bytes());
+        assert!(parse_route(&Message { kind: 24, payload }).is_none());
+    }
+}
@@ -0,0 +1,308 @@
+// network_info.rs
+// Network interface inventory from /sys/class/net, the ethtool ioctl and netlink
+
+use std::fs;
+use std::net::IpAddr;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::path::Path;
+
+use super::netlink;
+use super::sysfs;
+
+const NET_CLASS_DIR: &str = "/sys/class/net";
+/// Fallback when nl80211 is unavailable: the regdom the cfg80211 module was loaded with.
+const CFG80211_REGDOM: &str = "/sys/module/cfg80211/parameters/ieee80211_regdom";
+
+const SIOCETHTOOL: libc::c_ulong = 0x8946;
+const ETHTOOL_GDRVINFO: u32 = 0x3;
+/// ARPHRD_LOOPBACK from include/uapi/linux/if_arp.h
+const ARPHRD_LOOPBACK: u64 = 772;
+
+#[derive(Debug, Clone, Default)]
+pub struct Interface {
+    pub name: String,
+    pub index: u32,
+    pub kind: &'static str,
+    pub mac: Option<String>,
+    pub operstate: String,
+    pub carrier: Option<bool>,
+    pub speed_mbps: Option<u32>,
+    pub duplex: Option<String>,
+    pub mtu: Option<u32>,
+    pub driver: Option<String>,
+    pub driver_version: Option<String>,
+    pub firmware: Option<String>,
+    /// The PCI or USB device the interface is bound to, e.g. "0000:03:00.0 Intel Wi-Fi 6 AX200".
+    pub device: Option<String>,
+    pub addresses: Vec<String>,
+}
+
+/// struct ethtool_drvinfo from include/uapi/linux/ethtool.h
+#[repr(C)]
+struct EthtoolDrvinfo {
+    cmd: u32,
+    driver: [u8; 32],
+    version: [u8; 32],
+    fw_version: [u8; 32],
+    bus_info: [u8; 32],
+    erom_version: [u8; 32],
+    reserved2: [u8; 12],
+    n_priv_flags: u32,
+    n_stats: u32,
+    testinfo_len: u32,
+    eedump_len: u32,
+    regdump_len: u32,
+}
+
+/// struct ifreq with the ifr_data member of the union; padded to the kernel's 40 bytes.
+#[repr(C)]
+struct IfreqData {
+    name: [u8; 16],
+    data: *mut libc::c_void,
+    _pad: [u8; 16],
+}
+
+fn c_string(bytes: &[u8]) -> Option<String> {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    let s = String::from_utf8_lossy(&bytes[..end]).trim().to_string();
+    (!s.is_empty() && s != "N/A").then_some(s)
+}
+
+/// Driver name, driver version and firmware version through ETHTOOL_GDRVINFO (no root needed).
+fn ethtool_drvinfo(ifname: &str) -> Option<(Option<String>, Option<String>, Option<String>)> {
+    if ifname.len() >= 16 {
+        return None;
+    }
+    // SAFETY: plain socket(2) call; the returned descriptor is owned by OwnedFd below.
+    let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0) };
+    if fd < 0 {
+        return None;
+    }
+    // SAFETY: fd is a freshly created, valid descriptor not owned elsewhere.
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+    // SAFETY: EthtoolDrvinfo is plain data and valid when zeroed.
+    let mut info: EthtoolDrvinfo = unsafe { std::mem::zeroed() };
+    info.cmd = ETHTOOL_GDRVINFO;
+    let mut req = IfreqData { name: [0; 16], data: &mut info as *mut EthtoolDrvinfo as *mut libc::c_void, _pad: [0; 16] };
+    req.name[..ifname.len()].copy_from_slice(ifname.as_bytes());
+    // SAFETY: req is a properly laid out ifreq whose data points to a live ethtool_drvinfo.
+    let ret = unsafe { libc::ioctl(socket.as_raw_fd(), SIOCETHTOOL as _, &mut req as *mut IfreqData) };
+    if ret != 0 {
+        return None;
+    }
+    Some((c_string(&info.driver), c_string(&info.version), c_string(&info.fw_version)))
+}
+
+fn interface_kind(dir: &Path) -> &'static str {
+    if sysfs::read_u64(dir.join("type")) == Some(ARPHRD_LOOPBACK) {
+        "loopback"
+    } else if dir.join("wireless").exists() || dir.join("phy80211").exists() {
+        "wireless"
+    } else if dir.join("bridge").exists() {
+        "bridge"
+    } else if dir.join("bonding").exists() {
+        "bond"
+    } else if dir.join("tun_flags").exists() {
+        "tun/tap"
+    } else if !dir.join("device").exists() {
+        "virtual"
+    } else {
+        "ethernet"
+    }
+}
+
+/// Slot and IDs of the device behind an interface, read straight from its sysfs node rather
+/// than through a full bus scan.
+fn bound_device(dir: &Path) -> Option<String> {
+    let device = dir.join("device");
+    let target = sysfs::link_name(&device)?;
+    let ids = |base: &Path, vendor: &str, product: &str| -> Option<String> {
+        let read = |name: &str| sysfs::read_trimmed(base.join(name)).map(|v| v.trim_start_matches("0x").to_string());
+        Some(format!("[{}:{}]", read(vendor)?, read(product)?))
+    };
+    let (name, bus, ids) = match sysfs::link_name(device.join("subsystem")).as_deref() {
+        Some("pci") => (target.clone(), "PCI", ids(&device, "vendor", "device")),
+        // USB network interfaces bind to an interface such as "1-2:1.0"; the IDs live on the device "1-2"
+        Some("usb") => {
+            let port = target.split(':').next().unwrap_or(&target).to_string();
+            (port, "USB", fs::canonicalize(&device).ok().and_then(|d| ids(d.parent()?, "idVendor", "idProduct")))
+        }
+        _ => return Some(target),
+    };
+    Some(match ids {
+        Some(ids) => format!("{} {} ({})", name, ids, bus),
+        None => format!("{} ({})", name, bus),
+    })
+}
+
+fn format_address(address: &IpAddr, prefix_len: u8) -> String {
+    format!("{}/{}", address, prefix_len)
+}
+
+pub fn read_interfaces() -> Vec<Interface> {
+    let mut addresses = netlink::addresses().unwrap_or_default();
+    // Global addresses first, then link-local and host scope
+    addresses.sort_by_key(|a| a.scope);
+    sysfs::list_dir_prefixed(NET_CLASS_DIR, "")
+        .into_iter()
+        .filter_map(|dir| {
+            let name = dir.file_name()?.to_string_lossy().to_string();
+            let index = sysfs::read_u64(dir.join("ifindex")).unwrap_or(0) as u32;
+            let (driver, driver_version, firmware) = ethtool_drvinfo(&name).unwrap_or_default();
+            Some(Interface {
+                kind: interface_kind(&dir),
+                mac: sysfs::read_trimmed(dir.join("address")).filter(|m| m != "00:00:00:00:00:00"),
+                operstate: sysfs::read_trimmed(dir.join("operstate")).unwrap_or_else(|| "unknown".to_string()),
+                // carrier and speed fail with EINVAL while the interface is down
+                carrier: sysfs::read_u64(dir.join("carrier")).map(|c| c == 1),
+                speed_mbps: sysfs::read_i64(dir.join("speed")).filter(|s| *s > 0).map(|s| s as u32),
+                duplex: sysfs::read_trimmed(dir.join("duplex")).filter(|d| d != "unknown"),
+                mtu: sysfs::read_u64(dir.join("mtu")).map(|m| m as u32),
+                driver: driver.or_else(|| sysfs::link_name(dir.join("device/driver"))),
+                driver_version,
+                firmware,
+                device: bound_device(&dir),
+                addresses: addresses
+                    .iter()
+                    .filter(|a| a.index == index)
+                    .map(|a| format_address(&a.address, a.prefix_len))
+                    .collect(),
+                name,
+                index,
+            })
+        })
+        .collect()
+}
+
+/// Current regulatory domain: nl80211 first, then the cfg80211 module parameter.
+pub fn regulatory_domain() -> Option<String> {
+    netlink::regulatory_domain()
+        .ok()
+        .flatten()
+        .or_else(|| sysfs::read_trimmed(CFG80211_REGDOM))
+        .filter(|r| !r.is_empty())
+}
+
+fn route_line(route: &netlink::Route, interfaces: &[Interface]) -> String {
+    let mut line = match route.destination {
+        Some((dst, len)) => format_address(&dst, len),
+        None => "default".to_string(),
+    };
+    if let Some(gw) = route.gateway {
+        line.push_str(&format!(" via {}", gw));
+    }
+    if let Some(iface) = route.out_index.and_then(|i| interfaces.iter().find(|f| f.index == i)) {
+        line.push_str(&format!(" dev {}", iface.name));
+    }
+    let proto = match route.protocol {
+        2 => Some("kernel"),
+        3 => Some("boot"),
+        4 => Some("static"),
+        16 => Some("dhcp"),
+        _ => None,
+    };
+    if let Some(proto) = proto {
+        line.push_str(&format!(" proto {}", proto));
+    }
+    if let Some(metric) = route.metric {
+        line.push_str(&format!(" metric {}", metric));
+    }
+    line
+}
+
+fn link_state(iface: &Interface) -> String {
+    let mut state = iface.operstate.clone();
+    if iface.carrier == Some(false) {
+        state.push_str(", no carrier");
+    }
+    if let Some(speed) = iface.speed_mbps {
+        state.push_str(&format!(", {} Mb/s", speed));
+    }
+    if let Some(duplex) = &iface.duplex {
+        state.push_str(&format!(" {} duplex", duplex));
+    }
+    state
+}
+
+/// Full per-interface listing for the hardware log.
+pub fn log_text(interfaces: &[Interface]) -> String {
+    let mut out = String::from("[network interfaces]\n");
+    for iface in interfaces {
+        out.push_str(&format!(
+            "{} ({}) {} mac={} mtu={} driver={} {} firmware={} device={} addresses={}\n",
+            iface.name,
+            iface.kind,
+            link_state(iface),
+            iface.mac.as_deref().unwrap_or("-"),
+            iface.mtu.map(|m| m.to_string()).unwrap_or_else(|| "-".to_string()),
+            iface.driver.as_deref().unwrap_or("-"),
+            iface.driver_version.as_deref().unwrap_or(""),
+            iface.firmware.as_deref().unwrap_or("-"),
+            iface.device.as_deref().unwrap_or("-"),
+            iface.addresses.join(",")
+        ));
+    }
+    out
+}
+
+/// Entry point for `nephyra network`.
+pub fn run() {
+    println!("🌐 Network interfaces");
+    let interfaces = read_interfaces();
+    if interfaces.is_empty() {
+        println!("No network interfaces found.");
+        return;
+    }
+    for iface in &interfaces {
+        println!("\n🔌 {} ({}) — {}", iface.name, iface.kind, link_state(iface));
+        if let Some(mac) = &iface.mac {
+            println!("   MAC: {}", mac);
+        }
+        if let Some(mtu) = iface.mtu {
+            println!("   MTU: {}", mtu);
+        }
+        if let Some(driver) = &iface.driver {
+            println!("   Driver: {} {}", driver, iface.driver_version.as_deref().unwrap_or(""));
+        }
+        if let Some(firmware) = &iface.firmware {
+            println!("   Firmware: {}", firmware);
+        }
+        if let Some(device) = &iface.device {
+            println!("   Device: {}", device);
+        }
+        if !iface.addresses.is_empty() {
+            println!("   Addresses: {}", iface.addresses.join(", "));
+        }
+    }
+    if interfaces.iter().any(|i| i.kind == "wireless") {
+        match regulatory_domain() {
+            Some(regdom) if regdom == "00" => {
+                println!("\n📡 Regulatory domain: 00 (world) — set your country to unlock all channels and TX power.")
+            }
+            Some(regdom) => println!("\n📡 Regulatory domain: {}", regdom),
+            None => println!("\n📡 Regulatory domain: unknown"),
+        }
+    }
+    match netlink::routes() {
+        Ok(routes) if !routes.is_empty() => {
+            println!("\n🧭 Routes:");
+            for route in &routes {
+                println!("   {}", route_line(route, &interfaces));
+            }
+        }
+        Ok(_) => println!("\n🧭 Routes: none"),
+        Err(e) => eprintln!("⚠️ Failed to read routes over netlink: {}", e),
+    }
+}
+
+pub fn get_summary() -> String {
+    let parts: Vec<String> = read_interfaces()
+        .iter()
+        .filter(|i| i.kind != "loopback" && i.operstate == "up")
+        .map(|i| match i.addresses.first() {
+            Some(addr) => format!("{} ({}) {}", i.name, i.kind, addr),
+            None => format!("{} ({})", i.name, i.kind),
+        })
+        .collect();
+    format!("Network: {}", if parts.is_empty() { "no active interfaces".to_string() } else { parts.join(", ") })
+}
@@ -0,0 +1,194 @@
+// optimized_repos.rs
+// Match repositories built for x86-64 microarchitecture levels (CachyOS, ALHP, openSUSE/OBS v3) against the CPU
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::cpu_info;
+use super::sysfs;
+
+const PACMAN_CONF: &str = "/etc/pacman.conf";
+const RPM_REPO_DIRS: [&str; 2] = ["/etc/yum.repos.d", "/etc/zypp/repos.d"];
+
+/// A configured repository whose packages require a minimum x86-64 psABI level.
+#[derive(Debug, Clone)]
+pub struct OptimizedRepo {
+    pub name: String,
+    /// File the repository was found in.
+    pub source: PathBuf,
+    pub required_level: u8,
+    /// Whether the repository is managed by pacman (installed packages can then be listed).
+    pub pacman: bool,
+}
+
+/// Minimum psABI level implied by a repository name, mirror URL or architecture string, e.g.
+/// "cachyos-v3", "core-x86-64-v3", "$arch_v3", "x86_64_v4" or "cachyos-core-znver4".
+pub fn level_from_name(name: &str) -> Option<u8> {
+    let name = name.to_lowercase().replace('_', "-");
+    // CachyOS znver4 repos are built with AVX-512 enabled.
+    if name.contains("znver4") || name.contains("znver5") {
+        return Some(4);
+    }
+    (2..=4u8).rev().find(|level| {
+        let suffix = format!("-v{}", level);
+        name.contains(&format!("x86-64-v{}", level))
+            || name.contains(&format!("arch-v{}", level))
+            || name.ends_with(&suffix)
+            || name.contains(&format!("{}-", suffix))
+            || name.contains(&format!("{}/", suffix))
+    })
+}
+
+/// Level required by the first Server line of a pacman Include file (CachyOS uses "$arch_v3").
+fn include_level(path: &str) -> Option<u8> {
+    let content = fs::read_to_string(path).ok()?;
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.starts_with('#'))
+        .filter_map(|l| l.split_once('=').filter(|(k, _)| k.trim() == "Server").map(|(_, v)| v.trim()))
+        .find_map(level_from_name)
+}
+
+fn pacman_repos() -> (Vec<OptimizedRepo>, Option<u8>) {
+    let Ok(content) = fs::read_to_string(PACMAN_CONF) else {
+        return (Vec::new(), None);
+    };
+    let mut repos: Vec<OptimizedRepo> = Vec::new();
+    let mut arch_level = None;
+    let mut section = String::new();
+    for line in content.lines().map(str::trim).filter(|l| !l.starts_with('#')) {
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = name.to_string();
+            if section != "options"
+                && let Some(level) = level_from_name(&section)
+            {
+                repos.push(OptimizedRepo { name: section.clone(), source: PACMAN_CONF.into(), required_level: level, pacman: true });
+            }
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=').map(|(k, v)| (k.trim(), v.trim())) else {
+            continue;
+        };
+        match key {
+            // "Architecture = x86_64 x86_64_v3" lets pacman accept packages built for that level.
+            "Architecture" if section == "options" => {
+                arch_level = value.split_whitespace().filter_map(level_from_name).max();
+            }
+            "Include" if section != "options" && !repos.iter().any(|r| r.name == section) => {
+                if let Some(level) = level_from_name(value).or_else(|| include_level(value)) {
+                    repos.push(OptimizedRepo { name: section.clone(), source: value.into(), required_level: level, pacman: true });
+                }
+            }
+            _ => {}
+        }
+    }
+    (repos, arch_level)
+}
+
+/// Enabled .repo sections (dnf and zypper share the ini format) whose id, name or URLs carry a level.
+fn rpm_repos() -> Vec<OptimizedRepo> {
+    let mut repos = Vec::new();
+    for dir in RPM_REPO_DIRS {
+        for file in sysfs::list_dir_prefixed(dir, "").into_iter().filter(|p| p.extension().is_some_and(|e| e == "repo")) {
+            let content = fs::read_to_string(&file).unwrap_or_default();
+            let mut sections: Vec<(String, Vec<&str>)> = Vec::new();
+            for line in content.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+                if let Some(id) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                    sections.push((id.to_string(), Vec::new()));
+                } else if let Some((_, lines)) = sections.last_mut() {
+                    lines.push(line);
+                }
+            }
+            for (id, lines) in sections {
+                let value = |key: &str| {
+                    lines.iter().find_map(|l| l.split_once('=').filter(|(k, _)| k.trim() == key).map(|(_, v)| v.trim()))
+                };
+                if value("enabled") == Some("0") {
+                    continue;
+                }
+                let level = [Some(id.as_str()), value("name"), value("baseurl"), value("mirrorlist"), value("metalink")]
+                    .into_iter()
+                    .flatten()
+                    .filter_map(level_from_name)
+                    .max();
+                if let Some(level) = level {
+                    repos.push(OptimizedRepo { name: id, source: file.clone(), required_level: level, pacman: false });
+                }
+            }
+        }
+    }
+    repos
+}
+
+/// All configured repositories that require a specific x86-64 level, plus the level pacman's
+/// Architecture option allows, if any.
+pub fn configured_repos() -> (Vec<OptimizedRepo>, Option<u8>) {
+    let (mut repos, arch_level) = pacman_repos();
+    repos.extend(rpm_repos());
+    (repos, arch_level)
+}
+
+/// Packages installed from a pacman repository, via `pacman -Sl <repo>`.
+fn installed_from(repo: &str) -> Vec<String> {
+    let Ok(output) = Command::new("pacman").args(["-Sl", repo]).output() else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|l| l.contains("[installed"))
+        .filter_map(|l| l.split_whitespace().nth(1).map(|s| s.to_string()))
+        .collect()
+}
+
+/// Human-readable verdict for packages that need `required` on a CPU supporting `cpu_level`.
+pub fn mismatch_warning(required: u8, cpu_level: u8) -> Option<String> {
+    if required <= cpu_level {
+        return None;
+    }
+    let extra = if required == 4 { " (AVX-512)" } else { "" };
+    Some(format!(
+        "needs x86-64-v{}{} but this CPU only supports x86-64-v{}; its binaries will crash with SIGILL (illegal instruction)",
+        required, extra, cpu_level
+    ))
+}
+
+/// Print whether the configured optimized repositories match this CPU. Used by the package and kernel checks.
+pub fn report() {
+    let Some(cpu_level) = cpu_info::read_cpu_info().x86_64_level() else {
+        return;
+    };
+    let (repos, arch_level) = configured_repos();
+    println!("\n🧬 CPU microarchitecture level: x86-64-v{}", cpu_level);
+    if repos.is_empty() && arch_level.is_none() {
+        if cpu_level >= 3 && Path::new(PACMAN_CONF).exists() {
+            println!("ℹ️ No x86-64-v3/v4 optimized repositories configured; CachyOS or ALHP repos would match this CPU.");
+        } else {
+            println!("No microarchitecture-optimized repositories configured.");
+        }
+        return;
+    }
+    if let Some(level) = arch_level {
+        match mismatch_warning(level, cpu_level) {
+            Some(warning) => println!("❌ pacman Architecture option {}", warning),
+            None => println!("✅ pacman Architecture option allows x86-64-v{} packages", level),
+        }
+    }
+    for repo in &repos {
+        match mismatch_warning(repo.required_level, cpu_level) {
+            None => println!("✅ [{}] x86-64-v{} matches this CPU ({})", repo.name, repo.required_level, repo.source.display()),
+            Some(warning) => {
+                println!("❌ [{}] {} ({})", repo.name, warning, repo.source.display());
+                let installed = if repo.pacman { installed_from(&repo.name) } else { Vec::new() };
+                if !installed.is_empty() {
+                    println!("   ⚠️ {} installed package(s) come from this repository: {}", installed.len(), installed.join(" "));
+                }
+            }
+        }
+    }
+    let best = repos.iter().map(|r| r.required_level).max().unwrap_or(1);
+    if best < cpu_level && cpu_level >= 3 {
+        println!("ℹ️ This CPU supports x86-64-v{}; repositories built for that level may be faster.", cpu_level);
+    }
+}
@@ -0,0 +1,49 @@
+// os_release.rs
+// Distribution identity from os-release(5)
+
+use std::fs;
+
+const OS_RELEASE_PATHS: [&str; 2] = ["/etc/os-release", "/usr/lib/os-release"];
+
+#[derive(Debug, Clone, Default)]
+pub struct OsRelease {
+    /// Lower-case distribution id, e.g. "arch", "debian", "fedora".
+    pub id: String,
+    /// Distributions this one derives from, e.g. ["ubuntu", "debian"] on Linux Mint.
+    pub id_like: Vec<String>,
+    /// Release codename, e.g. "bookworm"; empty on rolling releases.
+    pub version_codename: String,
+}
+
+impl OsRelease {
+    /// Whether the distribution is `name` or derived from it.
+    pub fn is_like(&self, name: &str) -> bool {
+        self.id == name || self.id_like.iter().any(|l| l == name)
+    }
+}
+
+/// Parse os-release KEY=value lines, unquoting values.
+pub fn parse(content: &str) -> OsRelease {
+    let mut release = OsRelease::default();
+    for line in content.lines() {
+        let Some((key, value)) = line.trim().split_once('=') else {
+            continue;
+        };
+        let value = value.trim().trim_matches('"').trim_matches('\'').to_string();
+        match key {
+            "ID" => release.id = value.to_lowercase(),
+            "ID_LIKE" => release.id_like = value.split_whitespace().map(|v| v.to_lowercase()).collect(),
+            "VERSION_CODENAME" => release.version_codename = value,
+            _ => {}
+        }
+    }
+    release
+}
+
+pub fn read() -> OsRelease {
+    OS_RELEASE_PATHS
+        .iter()
+        .find_map(|p| fs::read_to_string(p).ok())
+        .map(|c| parse(&c))
+        .unwrap_or_default()
+}
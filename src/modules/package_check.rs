@@ -1,194 +1,171 @@
 // package_check.rs
 // Scan for mismatched/orphaned packages and suggest updates (cross-distro)
 
-use std::process::Command;
+use std::io::{self, Write};
 
-fn detect_package_manager() -> Option<&'static str> {
-    let candidates = ["pacman", "apt", "dnf", "apk", "zypper", "emerge"];
-    for pm in candidates {
-        if Command::new("which")
-            .arg(pm)
-            .output()
-            .map(|o| o.status.success())
-            .unwrap_or(false)
-        {
-            return Some(pm);
-        }
+use super::config_merge;
+use super::optimized_repos;
+use super::package_manager::{self, InstallReason, Package, PackageManager};
+use super::security_advisories;
+use super::universal_packages;
+
+const LARGEST_SHOWN: usize = 5;
+/// Nix can report thousands of dead store paths; list only the first few.
+const ORPHANS_SHOWN: usize = 20;
+
+fn format_size(bytes: u64) -> String {
+    if bytes >= 1 << 30 {
+        format!("{:.1} GiB", bytes as f64 / (1u64 << 30) as f64)
+    } else {
+        format!("{:.1} MiB", bytes as f64 / (1u64 << 20) as f64)
     }
-    None
 }
 
-pub fn run() {
-    println!("\n📦 Nephyra: Package Check Module");
-    match detect_package_manager() {
-        Some("pacman") => run_pacman(),
-        Some("apt") => run_apt(),
-        Some("dnf") => run_dnf(),
-        Some("apk") => run_apk(),
-        Some("zypper") => run_zypper(),
-        Some("emerge") => run_emerge(),
-        _ => println!("Could not detect supported package manager."),
+fn describe(package: &Package) -> String {
+    let mut text = format!("{} {}", package.name, package.version).trim_end().to_string();
+    if let Some(repo) = &package.repo {
+        text.push_str(&format!(" [{}]", repo));
     }
+    if let Some(size) = package.size {
+        text.push_str(&format!(" ({})", format_size(size)));
+    }
+    text
 }
 
-fn run_pacman() {
-    // Orphans
-    let orphans = Command::new("pacman").args(["-Qdtq"]).output().ok();
-    let mut orphan_list = Vec::new();
-    if let Some(out) = orphans {
-        let s = String::from_utf8_lossy(&out.stdout);
-        if s.trim().is_empty() {
-            println!("No orphaned packages detected.");
-        } else {
-            println!("Orphaned packages:\n{}", s.trim());
-            orphan_list = s.lines().map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect();
-        }
+fn print_installed(pm: &dyn PackageManager) {
+    let mut installed = pm.installed();
+    if installed.is_empty() {
+        return;
     }
-    // Prompt for removal if orphans found
-    if !orphan_list.is_empty() {
-        use std::io::{self, Write};
-        print!("\nWould you like me to remove these to preserve storage? [y/N]: ");
-        io::stdout().flush().ok();
-        let mut input = String::new();
-        if io::stdin().read_line(&mut input).is_ok() {
-            if input.trim().eq_ignore_ascii_case("y") {
-                let status = Command::new("sudo")
-                    .arg("pacman")
-                    .arg("-Rns")
-                    .args(&orphan_list)
-                    .status();
-                match status {
-                    Ok(s) if s.success() => println!("Successfully removed orphaned packages."),
-                    Ok(_) | Err(_) => println!("Failed to remove some or all orphaned packages."),
-                }
-            } else {
-                println!("No packages were removed.");
-            }
-        }
+    let explicit = installed.iter().filter(|p| p.reason == Some(InstallReason::Explicit)).count();
+    let total: u64 = installed.iter().filter_map(|p| p.size).sum();
+    print!("Installed packages: {}", installed.len());
+    if installed.iter().any(|p| p.reason.is_some()) {
+        print!(" ({} explicit, {} dependencies)", explicit, installed.len() - explicit);
     }
-    // Updates
-    let updates = Command::new("checkupdates").output().ok();
-    if let Some(out) = updates {
-        let s = String::from_utf8_lossy(&out.stdout);
-        if s.trim().is_empty() {
-            println!("All packages up to date.");
-        } else {
-            println!("Available updates:\n{}", s.trim());
+    if total > 0 {
+        print!(", {} on disk", format_size(total));
+    }
+    println!();
+
+    installed.retain(|p| p.size.is_some());
+    installed.sort_by_key(|p| std::cmp::Reverse(p.size));
+    if !installed.is_empty() {
+        println!("Largest packages:");
+        for package in installed.iter().take(LARGEST_SHOWN) {
+            println!("  {}", describe(package));
         }
     }
 }
 
-fn run_apt() {
-    // Orphans (auto-removable)
-    let orphans = Command::new("apt").args(["autoremove", "--dry-run"]).output().ok();
-    if let Some(out) = orphans {
-        let s = String::from_utf8_lossy(&out.stdout);
-        if s.contains("The following packages will be REMOVED:") {
-            println!("Orphaned packages detected (auto-removable):\n{}", s.trim());
-        } else {
-            println!("No orphaned packages detected.");
-        }
+fn print_orphan_list(orphans: &[Package]) {
+    println!("Orphaned packages:");
+    for package in orphans.iter().take(ORPHANS_SHOWN) {
+        println!("  {}", describe(package));
     }
-    // Updates
-    let updates = Command::new("apt").args(["list", "--upgradable"]).output().ok();
-    if let Some(out) = updates {
-        let s = String::from_utf8_lossy(&out.stdout);
-        if s.lines().count() <= 1 {
-            println!("All packages up to date.");
-        } else {
-            println!("Available updates:\n{}", s.trim());
-        }
+    if orphans.len() > ORPHANS_SHOWN {
+        println!("  … and {} more", orphans.len() - ORPHANS_SHOWN);
     }
 }
 
-fn run_dnf() {
-    // Orphans
-    let orphans = Command::new("dnf").args(["repoquery", "--extras"]).output().ok();
-    if let Some(out) = orphans {
-        let s = String::from_utf8_lossy(&out.stdout);
-        if s.trim().is_empty() {
-            println!("No orphaned packages detected.");
-        } else {
-            println!("Orphaned packages:\n{}", s.trim());
-        }
+fn handle_orphans(pm: &dyn PackageManager) {
+    let orphans = pm.orphans();
+    if orphans.is_empty() {
+        println!("No orphaned packages detected.");
+        return;
     }
-    // Updates
-    let updates = Command::new("dnf").args(["check-update"]).output().ok();
-    if let Some(out) = updates {
-        let s = String::from_utf8_lossy(&out.stdout);
-        if s.contains("Obsoleting Packages") || s.contains("Last metadata expiration check") {
-            println!("Available updates:\n{}", s.trim());
+    print_orphan_list(&orphans);
+    let reclaimable: u64 = orphans.iter().filter_map(|p| p.size).sum();
+    if reclaimable > 0 {
+        println!("Removing them frees about {}.", format_size(reclaimable));
+    }
+
+    print!("\nWould you like me to remove these to preserve storage? [y/N]: ");
+    io::stdout().flush().ok();
+    let mut input = String::new();
+    if io::stdin().read_line(&mut input).is_ok() {
+        if input.trim().eq_ignore_ascii_case("y") {
+            let names: Vec<String> = orphans.into_iter().map(|p| p.name).collect();
+            match pm.remove(&names) {
+                Ok(s) if s.success() => println!("Successfully removed orphaned packages."),
+                Ok(_) | Err(_) => println!("Failed to remove some or all orphaned packages."),
+            }
         } else {
-            println!("All packages up to date.");
+            println!("No packages were removed.");
         }
     }
 }
 
-fn run_apk() {
-    // Orphans (no direct, but can show unneeded)
-    let orphans = Command::new("apk").args(["info", "-d"]).output().ok();
-    if let Some(out) = orphans {
-        let s = String::from_utf8_lossy(&out.stdout);
-        if s.trim().is_empty() {
-            println!("No orphaned packages detected.");
-        } else {
-            println!("Potentially unneeded packages:\n{}", s.trim());
+fn print_upgrades(pm: &dyn PackageManager) {
+    let upgrades = pm.upgradable();
+    if upgrades.is_empty() {
+        println!("All packages up to date.");
+        return;
+    }
+    println!("Available updates ({}):", upgrades.len());
+    for upgrade in &upgrades {
+        let current = upgrade.current.as_deref().unwrap_or("?");
+        match &upgrade.repo {
+            Some(repo) => println!("  {} {} → {} [{}]", upgrade.name, current, upgrade.available, repo),
+            None => println!("  {} {} → {}", upgrade.name, current, upgrade.available),
         }
     }
-    // Updates
-    let updates = Command::new("apk").args(["version", "-l", "'<'"]).output().ok();
-    if let Some(out) = updates {
-        let s = String::from_utf8_lossy(&out.stdout);
-        if s.trim().is_empty() {
-            println!("All packages up to date.");
-        } else {
-            println!("Available updates:\n{}", s.trim());
+}
+
+/// Report-only check for Flatpak, Snap, Nix and friends; cleanup commands are printed, not run.
+fn check_secondary(pm: &dyn PackageManager) {
+    println!("\n📦 {} ({} installed)", pm.name(), pm.installed().len());
+    let orphans = pm.orphans();
+    if orphans.is_empty() {
+        println!("No orphaned packages detected.");
+    } else {
+        print_orphan_list(&orphans);
+        println!("💡 Clean up with:");
+        for command in pm.orphan_cleanup(&orphans) {
+            println!("    {}", command);
         }
     }
+    print_upgrades(pm);
 }
 
-fn run_zypper() {
-    // Orphans
-    let orphans = Command::new("zypper").args(["packages", "--orphaned"]).output().ok();
-    if let Some(out) = orphans {
-        let s = String::from_utf8_lossy(&out.stdout);
-        if s.trim().is_empty() {
-            println!("No orphaned packages detected.");
-        } else {
-            println!("Orphaned packages:\n{}", s.trim());
+/// AppImages and Distrobox containers: listed so they are not forgotten, nothing to check.
+fn print_unmanaged() {
+    let appimages = universal_packages::appimages();
+    if !appimages.is_empty() {
+        println!("\n📦 AppImage ({} found)", appimages.len());
+        for package in &appimages {
+            println!("  {}", describe(package));
         }
+        println!("ℹ️ AppImages update themselves or through AppImageUpdate; nephyra cannot check them.");
     }
-    // Updates
-    let updates = Command::new("zypper").args(["lu"]).output().ok();
-    if let Some(out) = updates {
-        let s = String::from_utf8_lossy(&out.stdout);
-        if s.contains("No updates found.") {
-            println!("All packages up to date.");
-        } else {
-            println!("Available updates:\n{}", s.trim());
+    let containers = universal_packages::distrobox_containers();
+    if !containers.is_empty() {
+        println!("\n📦 Distrobox ({} containers)", containers.len());
+        for container in &containers {
+            println!("  {} [{}] — {}", container.name, container.image, container.status);
         }
+        println!("💡 Packages inside a container are updated from it: distrobox enter <name>, then its package manager.");
     }
 }
 
-fn run_emerge() {
-    // Orphans
-    let orphans = Command::new("emerge").args(["--depclean", "--pretend"]).output().ok();
-    if let Some(out) = orphans {
-        let s = String::from_utf8_lossy(&out.stdout);
-        if s.contains("Nothing to clean") {
-            println!("No orphaned packages detected.");
-        } else {
-            println!("Orphaned packages (pretend):\n{}", s.trim());
+/// Entry point for `nephyra packages [--offline]`.
+pub fn run(args: &[String]) {
+    let offline = args.iter().any(|a| a == "--offline");
+    println!("\n📦 Nephyra: Package Check Module");
+    match package_manager::detect() {
+        Some(pm) => {
+            println!("Package manager: {}", pm.name());
+            print_installed(pm.as_ref());
+            handle_orphans(pm.as_ref());
+            print_upgrades(pm.as_ref());
+            security_advisories::report(pm.as_ref(), offline);
         }
+        None => println!("Could not detect supported package manager."),
     }
-    // Updates
-    let updates = Command::new("emerge").args(["-uDNav", "@world"]).output().ok();
-    if let Some(out) = updates {
-        let s = String::from_utf8_lossy(&out.stdout);
-        if s.contains("Total: 0 packages") {
-            println!("All packages up to date.");
-        } else {
-            println!("Available updates:\n{}", s.trim());
-        }
+    for pm in universal_packages::detect() {
+        check_secondary(pm.as_ref());
     }
+    print_unmanaged();
+    config_merge::run();
+    optimized_repos::report();
 }
@@ -0,0 +1,1051 @@
+// package_manager.rs
+// Typed access to the system package manager: pacman, apt, dnf, apk, zypper and portage
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::{Command, ExitStatus, Stdio};
+
+use super::os_release::{self, OsRelease};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InstallReason {
+    Explicit,
+    Dependency,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Package {
+    pub name: String,
+    pub version: String,
+    /// Repository the package comes from, when the backend records it.
+    pub repo: Option<String>,
+    pub reason: Option<InstallReason>,
+    /// Installed size in bytes.
+    pub size: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Upgrade {
+    pub name: String,
+    pub current: Option<String>,
+    pub available: String,
+    pub repo: Option<String>,
+}
+
+pub trait PackageManager {
+    /// Command name, e.g. "pacman".
+    fn name(&self) -> &'static str;
+    fn installed(&self) -> Vec<Package>;
+    /// Details of one installed package, None if it is not installed.
+    fn info(&self, name: &str) -> Option<Package>;
+    /// The installed package owning a file.
+    fn owner_of(&self, path: &Path) -> Option<String>;
+    /// Dependencies nothing requires any more.
+    fn orphans(&self) -> Vec<Package>;
+    /// Pending upgrades according to the locally synced metadata.
+    fn upgradable(&self) -> Vec<Upgrade>;
+    /// Full command line that installs `names`.
+    fn install_args(&self, names: &[String]) -> Vec<String>;
+    /// Full command line that removes `names` (and dependencies only they needed).
+    fn remove_args(&self, names: &[String]) -> Vec<String>;
+
+    /// System package managers need root; user-level ones (brew, cargo, pipx) refuse it.
+    fn needs_root(&self) -> bool {
+        true
+    }
+
+    fn is_installed(&self, name: &str) -> bool {
+        self.info(name).is_some()
+    }
+
+    /// The install command as a copy-pasteable hint.
+    fn install_hint(&self, names: &[String]) -> String {
+        self.command_hint(self.install_args(names))
+    }
+
+    /// Commands that clean up `orphans`; one removal by default.
+    fn orphan_cleanup(&self, orphans: &[Package]) -> Vec<String> {
+        let names: Vec<String> = orphans.iter().map(|p| p.name.clone()).collect();
+        vec![self.command_hint(self.remove_args(&names))]
+    }
+
+    fn command_hint(&self, args: Vec<String>) -> String {
+        let command = args.join(" ");
+        if self.needs_root() { format!("sudo {}", command) } else { command }
+    }
+
+    fn remove(&self, names: &[String]) -> io::Result<ExitStatus> {
+        execute(self.remove_args(names), self.needs_root())
+    }
+}
+
+fn execute(args: Vec<String>, root: bool) -> io::Result<ExitStatus> {
+    let (program, rest) = if root { ("sudo", &args[..]) } else { (args[0].as_str(), &args[1..]) };
+    Command::new(program).args(rest).status()
+}
+
+/// Run a query and return stdout regardless of the exit status (several tools signal "updates
+/// available" or "nothing found" through it); None only if the tool could not be started.
+/// The C locale keeps field names, units and table headers in the English the parsers expect.
+pub fn query(cmd: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(cmd).args(args).env("LC_ALL", "C").stderr(Stdio::null()).output().ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+pub fn args_with(base: &[&str], names: &[String]) -> Vec<String> {
+    base.iter().map(|s| s.to_string()).chain(names.iter().cloned()).collect()
+}
+
+pub fn command_exists(cmd: &str) -> bool {
+    Command::new("which")
+        .arg(cmd)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+/// Parse sizes such as "12.50 MiB" or "300 KiB" into bytes.
+pub fn parse_size(text: &str) -> Option<u64> {
+    let mut parts = text.split_whitespace();
+    let value: f64 = parts.next()?.replace(',', ".").parse().ok()?;
+    let factor = match parts.next().unwrap_or("B") {
+        "B" => 1.0,
+        "KiB" | "K" | "k" => 1024.0,
+        "MiB" | "M" => 1024.0 * 1024.0,
+        "GiB" | "G" => 1024.0 * 1024.0 * 1024.0,
+        // GLib-style decimal units (flatpak, snap)
+        "kB" | "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        _ => return None,
+    };
+    Some((value * factor) as u64)
+}
+
+pub fn lines_set(output: &str) -> HashSet<String> {
+    output.lines().map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect()
+}
+
+// ---------------------------------------------------------------------------------------------
+// pacman
+
+pub struct Pacman;
+
+/// `pacman -Q` / `pacman -Qdt`: "name version" per line.
+pub fn parse_pacman_list(output: &str) -> Vec<Package> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let (name, version) = l.trim().split_once(' ')?;
+            Some(Package { name: name.to_string(), version: version.trim().to_string(), ..Default::default() })
+        })
+        .collect()
+}
+
+/// `pacman -Qi` or `pacman -Si` for a single package.
+pub fn parse_pacman_info(output: &str) -> Option<Package> {
+    let mut package = Package::default();
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "Name" => package.name = value.to_string(),
+            "Version" => package.version = value.to_string(),
+            "Repository" => package.repo = Some(value.to_string()),
+            "Installed Size" => package.size = parse_size(value),
+            "Install Reason" => {
+                package.reason = Some(if value.starts_with("Explicitly") { InstallReason::Explicit } else { InstallReason::Dependency })
+            }
+            _ => {}
+        }
+    }
+    (!package.name.is_empty()).then_some(package)
+}
+
+/// `checkupdates` / `pacman -Qu`: "name old -> new" per line.
+pub fn parse_pacman_upgrades(output: &str) -> Vec<Upgrade> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let fields: Vec<&str> = l.split_whitespace().collect();
+            match fields.as_slice() {
+                [name, current, "->", available, ..] => Some(Upgrade {
+                    name: name.to_string(),
+                    current: Some(current.to_string()),
+                    available: available.to_string(),
+                    repo: None,
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+impl PackageManager for Pacman {
+    fn name(&self) -> &'static str {
+        "pacman"
+    }
+
+    fn installed(&self) -> Vec<Package> {
+        let explicit = lines_set(&query("pacman", &["-Qqe"]).unwrap_or_default());
+        let mut packages = parse_pacman_list(&query("pacman", &["-Q"]).unwrap_or_default());
+        for p in &mut packages {
+            p.reason = Some(if explicit.contains(&p.name) { InstallReason::Explicit } else { InstallReason::Dependency });
+        }
+        packages
+    }
+
+    fn info(&self, name: &str) -> Option<Package> {
+        parse_pacman_info(&query("pacman", &["-Qi", name])?)
+    }
+
+    fn owner_of(&self, path: &Path) -> Option<String> {
+        query("pacman", &["-Qoq", &path.to_string_lossy()])?.lines().next().map(|l| l.trim().to_string()).filter(|l| !l.is_empty())
+    }
+
+    fn orphans(&self) -> Vec<Package> {
+        let mut orphans = parse_pacman_list(&query("pacman", &["-Qdt"]).unwrap_or_default());
+        for p in &mut orphans {
+            p.reason = Some(InstallReason::Dependency);
+        }
+        orphans
+    }
+
+    fn upgradable(&self) -> Vec<Upgrade> {
+        // checkupdates uses a temporary database copy, so it is safe to run without root
+        let output = Command::new("checkupdates").env("LC_ALL", "C").stderr(Stdio::null()).output();
+        match output {
+            // Exit status 2 means "no updates"; anything else is a failed sync (offline, no fakeroot)
+            Ok(o) if o.status.success() || o.status.code() == Some(2) => parse_pacman_upgrades(&String::from_utf8_lossy(&o.stdout)),
+            // Fall back to the system sync database, as of the last `pacman -Sy`
+            _ => parse_pacman_upgrades(&query("pacman", &["-Qu"]).unwrap_or_default()),
+        }
+    }
+
+    fn install_args(&self, names: &[String]) -> Vec<String> {
+        args_with(&["pacman", "-S"], names)
+    }
+
+    fn remove_args(&self, names: &[String]) -> Vec<String> {
+        args_with(&["pacman", "-Rns"], names)
+    }
+}
+
+// ---------------------------------------------------------------------------------------------
+// apt / dpkg
+
+pub struct Apt;
+
+const DPKG_FORMAT: &str = "${Package}\t${Version}\t${Installed-Size}\t${db:Status-Abbrev}\n";
+
+/// `dpkg-query -W -f=DPKG_FORMAT`: installed size is in KiB; only "ii" packages are installed.
+pub fn parse_dpkg_query(output: &str) -> Vec<Package> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let fields: Vec<&str> = l.split('\t').collect();
+            if fields.len() < 4 || !fields[3].starts_with("ii") {
+                return None;
+            }
+            Some(Package {
+                name: fields[0].to_string(),
+                version: fields[1].to_string(),
+                size: fields[2].trim().parse::<u64>().ok().map(|kib| kib * 1024),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// `apt-get -s autoremove`: "Remv name [version]" per package.
+pub fn parse_apt_autoremove(output: &str) -> Vec<Package> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let rest = l.strip_prefix("Remv ")?;
+            let (name, version) = rest.split_once(' ').unwrap_or((rest, ""));
+            Some(Package {
+                name: name.to_string(),
+                version: version.trim_matches(|c| c == '[' || c == ']' || c == ' ').to_string(),
+                reason: Some(InstallReason::Dependency),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// `apt list --upgradable`: "name/suite new arch [upgradable from: old]".
+pub fn parse_apt_upgradable(output: &str) -> Vec<Upgrade> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let (name_suite, rest) = l.split_once(' ')?;
+            let (name, suite) = name_suite.split_once('/')?;
+            let available = rest.split_whitespace().next()?;
+            let current = rest.split_once("upgradable from: ").map(|(_, v)| v.trim_end_matches(']').trim().to_string());
+            Some(Upgrade { name: name.to_string(), current, available: available.to_string(), repo: Some(suite.to_string()) })
+        })
+        .collect()
+}
+
+/// `dpkg -S /path`: "pkg[:arch][, pkg2]: /path".
+pub fn parse_dpkg_search(output: &str) -> Option<String> {
+    let line = output.lines().find(|l| !l.starts_with("diversion"))?;
+    let (owners, _) = line.split_once(": ")?;
+    let first = owners.split(", ").next()?;
+    Some(first.split(':').next()?.to_string())
+}
+
+impl PackageManager for Apt {
+    fn name(&self) -> &'static str {
+        "apt"
+    }
+
+    fn installed(&self) -> Vec<Package> {
+        let auto = lines_set(&query("apt-mark", &["showauto"]).unwrap_or_default());
+        let mut packages = parse_dpkg_query(&query("dpkg-query", &["-W", &format!("-f={}", DPKG_FORMAT)]).unwrap_or_default());
+        for p in &mut packages {
+            p.reason = Some(if auto.contains(&p.name) { InstallReason::Dependency } else { InstallReason::Explicit });
+        }
+        packages
+    }
+
+    fn info(&self, name: &str) -> Option<Package> {
+        let mut package = parse_dpkg_query(&query("dpkg-query", &["-W", &format!("-f={}", DPKG_FORMAT), name])?).into_iter().next()?;
+        let auto = lines_set(&query("apt-mark", &["showauto", name]).unwrap_or_default());
+        package.reason = Some(if auto.contains(name) { InstallReason::Dependency } else { InstallReason::Explicit });
+        Some(package)
+    }
+
+    fn owner_of(&self, path: &Path) -> Option<String> {
+        parse_dpkg_search(&query("dpkg", &["-S", &path.to_string_lossy()])?)
+    }
+
+    fn orphans(&self) -> Vec<Package> {
+        parse_apt_autoremove(&query("apt-get", &["-s", "autoremove"]).unwrap_or_default())
+    }
+
+    fn upgradable(&self) -> Vec<Upgrade> {
+        parse_apt_upgradable(&query("apt", &["list", "--upgradable"]).unwrap_or_default())
+    }
+
+    fn install_args(&self, names: &[String]) -> Vec<String> {
+        args_with(&["apt", "install"], names)
+    }
+
+    fn remove_args(&self, names: &[String]) -> Vec<String> {
+        args_with(&["apt", "autoremove", "--purge"], names)
+    }
+}
+
+// ---------------------------------------------------------------------------------------------
+// rpm (shared by dnf and zypper)
+
+const RPM_FORMAT: &str = "%{NAME}\t%{EPOCHNUM}:%{VERSION}-%{RELEASE}\t%{SIZE}\n";
+
+/// `rpm -qa --qf RPM_FORMAT`; a zero epoch is dropped from the version.
+pub fn parse_rpm_query(output: &str) -> Vec<Package> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let fields: Vec<&str> = l.split('\t').collect();
+            if fields.len() < 3 {
+                return None;
+            }
+            Some(Package {
+                name: fields[0].to_string(),
+                version: fields[1].strip_prefix("0:").unwrap_or(fields[1]).to_string(),
+                size: fields[2].trim().parse().ok(),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+fn rpm_installed() -> Vec<Package> {
+    parse_rpm_query(&query("rpm", &["-qa", "--qf", RPM_FORMAT]).unwrap_or_default())
+}
+
+fn rpm_info(name: &str) -> Option<Package> {
+    parse_rpm_query(&query("rpm", &["-q", "--qf", RPM_FORMAT, name])?).into_iter().next()
+}
+
+fn rpm_owner(path: &Path) -> Option<String> {
+    let output = query("rpm", &["-qf", "--qf", "%{NAME}\n", &path.to_string_lossy()])?;
+    output.lines().next().filter(|l| !l.contains(' ')).map(|l| l.to_string())
+}
+
+// ---------------------------------------------------------------------------------------------
+// dnf
+
+pub struct Dnf;
+
+/// `dnf repoquery --qf '%{name} %{evr}\n'` (dnf4 adds blank lines, which are skipped).
+pub fn parse_dnf_repoquery(output: &str) -> Vec<Package> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let (name, version) = l.trim().split_once(' ')?;
+            Some(Package { name: name.to_string(), version: version.trim().to_string(), ..Default::default() })
+        })
+        .collect()
+}
+
+/// `dnf check-update`: "name.arch  version  repo" rows until "Obsoleting Packages".
+pub fn parse_dnf_check_update(output: &str) -> Vec<Upgrade> {
+    output
+        .lines()
+        .take_while(|l| !l.starts_with("Obsoleting"))
+        .filter_map(|l| {
+            let fields: Vec<&str> = l.split_whitespace().collect();
+            let [name_arch, version, repo] = fields.as_slice() else {
+                return None;
+            };
+            let (name, _arch) = name_arch.rsplit_once('.')?;
+            // Version strings always contain a digit; this skips headers such as "Last metadata ..."
+            if !version.chars().any(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            Some(Upgrade { name: name.to_string(), current: None, available: version.to_string(), repo: Some(repo.to_string()) })
+        })
+        .collect()
+}
+
+impl PackageManager for Dnf {
+    fn name(&self) -> &'static str {
+        "dnf"
+    }
+
+    fn installed(&self) -> Vec<Package> {
+        let user = lines_set(&query("dnf", &["repoquery", "--userinstalled", "--qf", "%{name}\n"]).unwrap_or_default());
+        let mut packages = rpm_installed();
+        if !user.is_empty() {
+            for p in &mut packages {
+                p.reason = Some(if user.contains(&p.name) { InstallReason::Explicit } else { InstallReason::Dependency });
+            }
+        }
+        packages
+    }
+
+    fn info(&self, name: &str) -> Option<Package> {
+        rpm_info(name)
+    }
+
+    fn owner_of(&self, path: &Path) -> Option<String> {
+        rpm_owner(path)
+    }
+
+    fn orphans(&self) -> Vec<Package> {
+        let mut orphans = parse_dnf_repoquery(&query("dnf", &["repoquery", "--unneeded", "--qf", "%{name} %{evr}\n"]).unwrap_or_default());
+        for p in &mut orphans {
+            p.reason = Some(InstallReason::Dependency);
+        }
+        orphans
+    }
+
+    fn upgradable(&self) -> Vec<Upgrade> {
+        // Exit status 100 means updates are available; -C stays on cached metadata
+        parse_dnf_check_update(&query("dnf", &["-C", "-q", "check-update"]).unwrap_or_default())
+    }
+
+    fn install_args(&self, names: &[String]) -> Vec<String> {
+        args_with(&["dnf", "install"], names)
+    }
+
+    fn remove_args(&self, names: &[String]) -> Vec<String> {
+        args_with(&["dnf", "remove"], names)
+    }
+}
+
+// ---------------------------------------------------------------------------------------------
+// apk
+
+pub struct Apk;
+
+const APK_WORLD: &str = "/etc/apk/world";
+
+/// Split an apk "name-1.2.3-r0" into name and version (the last two dash-separated fields).
+pub fn split_apk_name(full: &str) -> Option<(String, String)> {
+    let (rest, release) = full.rsplit_once('-')?;
+    let (name, version) = rest.rsplit_once('-')?;
+    Some((name.to_string(), format!("{}-{}", version, release)))
+}
+
+/// `apk list --installed`: "name-1.2.3-r0 x86_64 {origin} (license) [installed]".
+pub fn parse_apk_list(output: &str) -> Vec<Package> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let (name, version) = split_apk_name(l.split_whitespace().next()?)?;
+            Some(Package { name, version, ..Default::default() })
+        })
+        .collect()
+}
+
+/// `apk version -l '<'`: "name-1.2.3-r0  <  1.2.4-r0" rows after the header.
+pub fn parse_apk_version(output: &str) -> Vec<Upgrade> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let fields: Vec<&str> = l.split_whitespace().collect();
+            let [full, "<", available] = fields.as_slice() else {
+                return None;
+            };
+            let (name, current) = split_apk_name(full)?;
+            Some(Upgrade { name, current: Some(current), available: available.to_string(), repo: None })
+        })
+        .collect()
+}
+
+/// The package names in /etc/apk/world, stripped of version constraints and repository tags.
+fn apk_world() -> HashSet<String> {
+    fs::read_to_string(APK_WORLD)
+        .unwrap_or_default()
+        .lines()
+        .map(|l| l.split(['=', '<', '>', '~', '@']).next().unwrap_or("").trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect()
+}
+
+impl PackageManager for Apk {
+    fn name(&self) -> &'static str {
+        "apk"
+    }
+
+    fn installed(&self) -> Vec<Package> {
+        let world = apk_world();
+        let mut packages = parse_apk_list(&query("apk", &["list", "--installed"]).unwrap_or_default());
+        for p in &mut packages {
+            p.reason = Some(if world.contains(&p.name) { InstallReason::Explicit } else { InstallReason::Dependency });
+        }
+        packages
+    }
+
+    fn info(&self, name: &str) -> Option<Package> {
+        self.installed().into_iter().find(|p| p.name == name)
+    }
+
+    fn owner_of(&self, path: &Path) -> Option<String> {
+        // "/usr/bin/foo is owned by foo-1.0-r0"
+        let output = query("apk", &["info", "--who-owns", &path.to_string_lossy()])?;
+        let full = output.split("is owned by ").nth(1)?.split_whitespace().next()?;
+        split_apk_name(full).map(|(name, _)| name)
+    }
+
+    fn orphans(&self) -> Vec<Package> {
+        // apk removes dependencies together with the packages in world; nothing is left behind
+        Vec::new()
+    }
+
+    fn upgradable(&self) -> Vec<Upgrade> {
+        parse_apk_version(&query("apk", &["version", "-l", "<"]).unwrap_or_default())
+    }
+
+    fn install_args(&self, names: &[String]) -> Vec<String> {
+        args_with(&["apk", "add"], names)
+    }
+
+    fn remove_args(&self, names: &[String]) -> Vec<String> {
+        args_with(&["apk", "del"], names)
+    }
+}
+
+// ---------------------------------------------------------------------------------------------
+// zypper
+
+pub struct Zypper;
+
+/// Rows of a zypper table ("S | Repository | Name | ...") as trimmed columns, header excluded.
+fn zypper_rows(output: &str) -> Vec<Vec<String>> {
+    output
+        .lines()
+        .filter(|l| l.contains('|') && !l.starts_with("S ") && !l.starts_with("--"))
+        .map(|l| l.split('|').map(|c| c.trim().to_string()).collect())
+        .collect()
+}
+
+/// `zypper packages --unneeded`: "i | Repository | Name | Version | Arch".
+pub fn parse_zypper_packages(output: &str) -> Vec<Package> {
+    zypper_rows(output)
+        .into_iter()
+        .filter(|cols| cols.len() >= 5 && cols[0].starts_with('i'))
+        .map(|cols| Package {
+            name: cols[2].clone(),
+            version: cols[3].clone(),
+            repo: Some(cols[1].clone()),
+            reason: Some(InstallReason::Dependency),
+            size: None,
+        })
+        .collect()
+}
+
+/// `zypper list-updates`: "v | Repository | Name | Current Version | Available Version | Arch".
+pub fn parse_zypper_updates(output: &str) -> Vec<Upgrade> {
+    zypper_rows(output)
+        .into_iter()
+        .filter(|cols| cols.len() >= 6 && cols[0] == "v")
+        .map(|cols| Upgrade { name: cols[2].clone(), current: Some(cols[3].clone()), available: cols[4].clone(), repo: Some(cols[1].clone()) })
+        .collect()
+}
+
+impl PackageManager for Zypper {
+    fn name(&self) -> &'static str {
+        "zypper"
+    }
+
+    fn installed(&self) -> Vec<Package> {
+        rpm_installed()
+    }
+
+    fn info(&self, name: &str) -> Option<Package> {
+        rpm_info(name)
+    }
+
+    fn owner_of(&self, path: &Path) -> Option<String> {
+        rpm_owner(path)
+    }
+
+    fn orphans(&self) -> Vec<Package> {
+        parse_zypper_packages(&query("zypper", &["--quiet", "--no-refresh", "packages", "--unneeded"]).unwrap_or_default())
+    }
+
+    fn upgradable(&self) -> Vec<Upgrade> {
+        parse_zypper_updates(&query("zypper", &["--quiet", "--no-refresh", "list-updates"]).unwrap_or_default())
+    }
+
+    fn install_args(&self, names: &[String]) -> Vec<String> {
+        args_with(&["zypper", "install"], names)
+    }
+
+    fn remove_args(&self, names: &[String]) -> Vec<String> {
+        args_with(&["zypper", "remove", "--clean-deps"], names)
+    }
+}
+
+// ---------------------------------------------------------------------------------------------
+// portage (emerge)
+
+pub struct Portage;
+
+const PORTAGE_DB: &str = "/var/db/pkg";
+const PORTAGE_WORLD: &str = "/var/lib/portage/world";
+
+/// Split "sys-apps/foo-1.2.3-r1" into "sys-apps/foo" and "1.2.3-r1": the version starts at the
+/// first dash followed by a digit.
+pub fn split_portage_atom(atom: &str) -> Option<(String, String)> {
+    let atom = atom.trim_start_matches('=');
+    let bytes = atom.as_bytes();
+    let pos = (1..bytes.len()).find(|&i| bytes[i - 1] == b'-' && bytes[i].is_ascii_digit())?;
+    Some((atom[..pos - 1].to_string(), atom[pos..].to_string()))
+}
+
+/// `emerge --pretend --depclean --quiet`: "All selected packages: =cat/pkg-1.0 =cat/other-2.0".
+pub fn parse_emerge_depclean(output: &str) -> Vec<Package> {
+    output
+        .lines()
+        .filter_map(|l| l.trim().strip_prefix("All selected packages:"))
+        .flat_map(|atoms| atoms.split_whitespace())
+        .filter_map(|atom| {
+            let (name, version) = split_portage_atom(atom)?;
+            Some(Package { name, version, reason: Some(InstallReason::Dependency), ..Default::default() })
+        })
+        .collect()
+}
+
+/// `emerge -puDNq @world`: "[ebuild     U  ] cat/pkg-2.0 [1.0]".
+pub fn parse_emerge_updates(output: &str) -> Vec<Upgrade> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let rest = l.trim().strip_prefix("[ebuild")?;
+            let (flags, rest) = rest.split_once(']')?;
+            if !flags.contains('U') {
+                return None;
+            }
+            let mut fields = rest.split_whitespace();
+            let (name, available) = split_portage_atom(fields.next()?)?;
+            let current = fields.next().filter(|f| f.starts_with('[')).map(|f| f.trim_matches(|c| c == '[' || c == ']').to_string());
+            Some(Upgrade { name, current, available, repo: None })
+        })
+        .collect()
+}
+
+fn portage_package(dir: &Path, world: &HashSet<String>) -> Option<Package> {
+    let category = dir.parent()?.file_name()?.to_string_lossy().to_string();
+    let pf = dir.file_name()?.to_string_lossy().to_string();
+    let (name, version) = split_portage_atom(&format!("{}/{}", category, pf))?;
+    Some(Package {
+        reason: Some(if world.contains(&name) { InstallReason::Explicit } else { InstallReason::Dependency }),
+        repo: fs::read_to_string(dir.join("repository")).ok().map(|r| r.trim().to_string()),
+        size: fs::read_to_string(dir.join("SIZE")).ok().and_then(|s| s.trim().parse().ok()),
+        name,
+        version,
+    })
+}
+
+impl PackageManager for Portage {
+    fn name(&self) -> &'static str {
+        "emerge"
+    }
+
+    fn installed(&self) -> Vec<Package> {
+        let world = lines_set(&fs::read_to_string(PORTAGE_WORLD).unwrap_or_default());
+        let mut packages = Vec::new();
+        for category in fs::read_dir(PORTAGE_DB).into_iter().flatten().flatten() {
+            for dir in fs::read_dir(category.path()).into_iter().flatten().flatten() {
+                packages.extend(portage_package(&dir.path(), &world));
+            }
+        }
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+        packages
+    }
+
+    fn info(&self, name: &str) -> Option<Package> {
+        // Accept both "cat/pkg" and a bare "pkg"
+        self.installed().into_iter().find(|p| p.name == name || p.name.rsplit('/').next() == Some(name))
+    }
+
+    fn owner_of(&self, path: &Path) -> Option<String> {
+        // qfile ships with portage-utils
+        query("qfile", &["-qC", &path.to_string_lossy()])?.lines().next().map(|l| l.trim().to_string()).filter(|l| !l.is_empty())
+    }
+
+    fn orphans(&self) -> Vec<Package> {
+        parse_emerge_depclean(&query("emerge", &["--pretend", "--depclean", "--quiet"]).unwrap_or_default())
+    }
+
+    fn upgradable(&self) -> Vec<Upgrade> {
+        parse_emerge_updates(&query("emerge", &["-puDNq", "@world"]).unwrap_or_default())
+    }
+
+    fn install_args(&self, names: &[String]) -> Vec<String> {
+        args_with(&["emerge", "--ask"], names)
+    }
+
+    fn remove_args(&self, names: &[String]) -> Vec<String> {
+        args_with(&["emerge", "--ask", "--depclean"], names)
+    }
+}
+
+/// The backend for a package manager command name.
+pub fn by_name(name: &str) -> Option<Box<dyn PackageManager>> {
+    match name {
+        "pacman" => Some(Box::new(Pacman)),
+        "apt" => Some(Box::new(Apt)),
+        "dnf" => Some(Box::new(Dnf)),
+        "apk" => Some(Box::new(Apk)),
+        "zypper" => Some(Box::new(Zypper)),
+        "emerge" => Some(Box::new(Portage)),
+        _ => None,
+    }
+}
+
+/// Distribution id (or ID_LIKE entry) → native package manager.
+const DISTRO_MANAGERS: [(&str, &str); 13] = [
+    ("arch", "pacman"),
+    ("debian", "apt"),
+    ("ubuntu", "apt"),
+    ("fedora", "dnf"),
+    ("rhel", "dnf"),
+    ("centos", "dnf"),
+    ("almalinux", "dnf"),
+    ("rocky", "dnf"),
+    ("alpine", "apk"),
+    ("suse", "zypper"),
+    ("opensuse", "zypper"),
+    ("gentoo", "emerge"),
+    ("funtoo", "emerge"),
+];
+
+/// The native package manager for a distribution: its own ID first, then ID_LIKE in order.
+pub fn primary_for(release: &OsRelease) -> Option<&'static str> {
+    std::iter::once(&release.id)
+        .chain(release.id_like.iter())
+        .find_map(|id| {
+            // "opensuse-tumbleweed", "opensuse-leap"
+            let base = id.split('-').next().unwrap_or(id);
+            DISTRO_MANAGERS.iter().find(|(distro, _)| *distro == id || *distro == base).map(|(_, pm)| *pm)
+        })
+}
+
+/// The system package manager: the one the distribution ships (an Arch box with apt installed
+/// for packaging work is still a pacman system), else the first supported one found on PATH.
+pub fn detect() -> Option<Box<dyn PackageManager>> {
+    if let Some(pm) = primary_for(&os_release::read()).filter(|pm| command_exists(pm)) {
+        return by_name(pm);
+    }
+    ["pacman", "apt", "dnf", "apk", "zypper", "emerge"].into_iter().find(|pm| command_exists(pm)).and_then(by_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PACMAN_QI: &str = "\
+Name            : linux
+Version         : 6.9.7.arch1-1
+Description     : The Linux kernel and modules
+Architecture    : x86_64
+URL             : https://github.com/archlinux/linux
+Optional Deps   : wireless-regdb: to set the correct wireless channels of your country [installed]
+                  linux-firmware: firmware images needed for some devices [installed]
+Installed Size  : 136.63 MiB
+Packager        : Jan Alexander Steffens (heftig) <heftig@archlinux.org>
+Build Date      : Fri 28 Jun 2024 12:00:00 AM CEST
+Install Reason  : Explicitly installed
+Install Script  : No
+Validated By    : Signature
+";
+
+    #[test]
+    fn pacman_list() {
+        let packages = parse_pacman_list("linux 6.9.7.arch1-1\nbase 3-2\n");
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].name, "linux");
+        assert_eq!(packages[0].version, "6.9.7.arch1-1");
+        assert_eq!(packages[1].name, "base");
+    }
+
+    #[test]
+    fn pacman_info() {
+        let package = parse_pacman_info(PACMAN_QI).unwrap();
+        assert_eq!(package.name, "linux");
+        assert_eq!(package.version, "6.9.7.arch1-1");
+        assert_eq!(package.size, Some(143_266_938));
+        assert_eq!(package.reason, Some(InstallReason::Explicit));
+        assert_eq!(package.repo, None);
+
+        let sync = parse_pacman_info("Repository      : core\nName            : zlib\nVersion         : 1:1.3.1-1\n").unwrap();
+        assert_eq!(sync.repo.as_deref(), Some("core"));
+        assert_eq!(sync.version, "1:1.3.1-1");
+
+        let dep = parse_pacman_info("Name : zlib\nInstall Reason : Installed as a dependency for another package\n").unwrap();
+        assert_eq!(dep.reason, Some(InstallReason::Dependency));
+        assert!(parse_pacman_info("error: package 'nope' was not found\n").is_none());
+    }
+
+    #[test]
+    fn pacman_upgrades() {
+        let upgrades = parse_pacman_upgrades("linux 6.9.7.arch1-1 -> 6.9.8.arch1-1\nzlib 1:1.3-1 -> 1:1.3.1-1 [ignored]\n\n");
+        assert_eq!(upgrades.len(), 2);
+        assert_eq!(upgrades[0].name, "linux");
+        assert_eq!(upgrades[0].current.as_deref(), Some("6.9.7.arch1-1"));
+        assert_eq!(upgrades[0].available, "6.9.8.arch1-1");
+        assert_eq!(upgrades[1].available, "1:1.3.1-1");
+    }
+
+    #[test]
+    fn dpkg_query() {
+        let packages = parse_dpkg_query("bash\t5.2.15-2+b8\t7164\tii \nlibfoo1\t1.0-1\t12\trc \nzlib1g\t1:1.2.13.dfsg-1\t\tii \n");
+        assert_eq!(packages.len(), 2, "removed-but-configured packages are skipped");
+        assert_eq!(packages[0].name, "bash");
+        assert_eq!(packages[0].size, Some(7164 * 1024));
+        assert_eq!(packages[1].version, "1:1.2.13.dfsg-1");
+        assert_eq!(packages[1].size, None);
+    }
+
+    #[test]
+    fn apt_autoremove() {
+        let output = "\
+NOTE: This is only a simulation!
+      apt-get needs root privileges for real execution.
+Reading package lists...
+Building dependency tree...
+The following packages will be REMOVED:
+  libllvm14 linux-image-6.1.0-17-amd64
+0 upgraded, 0 newly installed, 2 to remove and 97 not upgraded.
+Remv libllvm14 [1:14.0.6-12]
+Remv linux-image-6.1.0-17-amd64 [6.1.69-1]
+";
+        let orphans = parse_apt_autoremove(output);
+        assert_eq!(orphans.len(), 2);
+        assert_eq!(orphans[0].name, "libllvm14");
+        assert_eq!(orphans[0].version, "1:14.0.6-12");
+        assert_eq!(orphans[1].name, "linux-image-6.1.0-17-amd64");
+        assert_eq!(orphans[1].reason, Some(InstallReason::Dependency));
+    }
+
+    #[test]
+    fn apt_upgradable() {
+        let output = "\
+Listing...
+bash/oldstable 5.2.15-2+b13 amd64 [upgradable from: 5.2.15-2+b8]
+google-cloud-cli/cloud-sdk-bookworm 569.0.0-0 all [upgradable from: 528.0.0-0]
+";
+        let upgrades = parse_apt_upgradable(output);
+        assert_eq!(upgrades.len(), 2);
+        assert_eq!(upgrades[0].name, "bash");
+        assert_eq!(upgrades[0].repo.as_deref(), Some("oldstable"));
+        assert_eq!(upgrades[0].available, "5.2.15-2+b13");
+        assert_eq!(upgrades[0].current.as_deref(), Some("5.2.15-2+b8"));
+        assert_eq!(upgrades[1].name, "google-cloud-cli");
+    }
+
+    #[test]
+    fn dpkg_search() {
+        assert_eq!(parse_dpkg_search("bash: /bin/bash\n").as_deref(), Some("bash"));
+        assert_eq!(parse_dpkg_search("libc6:amd64: /lib/x86_64-linux-gnu/libc.so.6\n").as_deref(), Some("libc6"));
+        assert_eq!(
+            parse_dpkg_search("diversion by dash from: /bin/sh\ndiversion by dash to: /bin/sh.distrib\ndash: /bin/sh\n").as_deref(),
+            Some("dash")
+        );
+        assert_eq!(parse_dpkg_search("libfoo, libbar: /usr/share/doc/foo\n").as_deref(), Some("libfoo"));
+        assert_eq!(parse_dpkg_search(""), None);
+    }
+
+    #[test]
+    fn rpm_query() {
+        let packages = parse_rpm_query("bash\t0:5.2.26-3.fc40\t8114059\nopenssl-libs\t1:3.2.1-2.fc40\t7813210\ngpg-pubkey\t0:a15b79cc-63d04c2c\t0\n");
+        assert_eq!(packages.len(), 3);
+        assert_eq!(packages[0].version, "5.2.26-3.fc40");
+        assert_eq!(packages[0].size, Some(8_114_059));
+        assert_eq!(packages[1].version, "1:3.2.1-2.fc40");
+    }
+
+    #[test]
+    fn dnf_repoquery() {
+        // dnf4 adds an empty line after each entry when the format ends in \n
+        let packages = parse_dnf_repoquery("libfoo 1.2-3.fc40\n\nlibbar 2:2.0-1.fc40\n\n");
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].name, "libfoo");
+        assert_eq!(packages[1].version, "2:2.0-1.fc40");
+    }
+
+    #[test]
+    fn dnf_check_update() {
+        let output = "\
+Last metadata expiration check: 0:01:23 ago on Sat 29 Jun 2024 10:00:00 AM CEST.
+
+bash.x86_64                          5.2.26-3.fc40                       updates
+kernel-core.x86_64                   6.9.8-200.fc40                      updates
+python3-libs.x86_64                  3.12.4-1.fc40                       updates-testing
+Obsoleting Packages
+grub2-tools.x86_64                   1:2.06-121.fc40                     updates
+";
+        let upgrades = parse_dnf_check_update(output);
+        assert_eq!(upgrades.len(), 3);
+        assert_eq!(upgrades[0].name, "bash");
+        assert_eq!(upgrades[1].name, "kernel-core");
+        assert_eq!(upgrades[1].available, "6.9.8-200.fc40");
+        assert_eq!(upgrades[2].repo.as_deref(), Some("updates-testing"));
+    }
+
+    #[test]
+    fn apk_names() {
+        assert_eq!(split_apk_name("busybox-1.36.1-r29"), Some(("busybox".to_string(), "1.36.1-r29".to_string())));
+        assert_eq!(split_apk_name("py3-foo-bar-2.0-r1"), Some(("py3-foo-bar".to_string(), "2.0-r1".to_string())));
+        assert_eq!(split_apk_name("nodash"), None);
+    }
+
+    #[test]
+    fn apk_list() {
+        let output = "\
+busybox-1.36.1-r29 x86_64 {busybox} (GPL-2.0-only) [installed]
+ca-certificates-bundle-20240226-r0 x86_64 {ca-certificates} (MPL-2.0 AND MIT) [installed]
+";
+        let packages = parse_apk_list(output);
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[1].name, "ca-certificates-bundle");
+        assert_eq!(packages[1].version, "20240226-r0");
+    }
+
+    #[test]
+    fn apk_version() {
+        let output = "\
+Installed:                                Available:
+busybox-1.36.1-r28                      < 1.36.1-r29
+musl-1.2.5-r0                           < 1.2.5-r1
+";
+        let upgrades = parse_apk_version(output);
+        assert_eq!(upgrades.len(), 2);
+        assert_eq!(upgrades[0].name, "busybox");
+        assert_eq!(upgrades[0].current.as_deref(), Some("1.36.1-r28"));
+        assert_eq!(upgrades[0].available, "1.36.1-r29");
+    }
+
+    #[test]
+    fn zypper_packages() {
+        let output = "\
+S  | Repository | Name    | Version  | Arch
+---+------------+---------+----------+-------
+i  | repo-oss   | libfoo1 | 1.2-1.1  | x86_64
+i+ | repo-oss   | libbar2 | 2.0-3.2  | noarch
+v  | repo-oss   | libbaz  | 3.0-1.1  | x86_64
+";
+        let packages = parse_zypper_packages(output);
+        assert_eq!(packages.len(), 2, "only installed rows count");
+        assert_eq!(packages[0].name, "libfoo1");
+        assert_eq!(packages[0].version, "1.2-1.1");
+        assert_eq!(packages[0].repo.as_deref(), Some("repo-oss"));
+    }
+
+    #[test]
+    fn zypper_updates() {
+        let output = "\
+S | Repository  | Name | Current Version | Available Version | Arch
+--+-------------+------+-----------------+-------------------+-------
+v | repo-update | bash | 5.2.15-1.1      | 5.2.26-2.1        | x86_64
+";
+        let upgrades = parse_zypper_updates(output);
+        assert_eq!(upgrades.len(), 1);
+        assert_eq!(upgrades[0].name, "bash");
+        assert_eq!(upgrades[0].current.as_deref(), Some("5.2.15-1.1"));
+        assert_eq!(upgrades[0].available, "5.2.26-2.1");
+        assert_eq!(upgrades[0].repo.as_deref(), Some("repo-update"));
+    }
+
+    #[test]
+    fn portage_atoms() {
+        assert_eq!(split_portage_atom("sys-apps/portage-3.0.65-r1"), Some(("sys-apps/portage".to_string(), "3.0.65-r1".to_string())));
+        assert_eq!(split_portage_atom("=dev-libs/openssl-3.0.13"), Some(("dev-libs/openssl".to_string(), "3.0.13".to_string())));
+        assert_eq!(split_portage_atom("media-libs/libsdl2-2.30.3"), Some(("media-libs/libsdl2".to_string(), "2.30.3".to_string())));
+        assert_eq!(split_portage_atom("x11-libs/gtk+-3.24.41"), Some(("x11-libs/gtk+".to_string(), "3.24.41".to_string())));
+        assert_eq!(split_portage_atom("sys-apps/portage"), None);
+    }
+
+    #[test]
+    fn emerge_depclean() {
+        let output = "
+ dev-libs/libfoo
+    selected: 1.2.3
+   protected: none
+     omitted: none
+
+All selected packages: =dev-libs/libfoo-1.2.3 =sys-apps/bar-2.0-r1
+";
+        let orphans = parse_emerge_depclean(output);
+        assert_eq!(orphans.len(), 2);
+        assert_eq!(orphans[0].name, "dev-libs/libfoo");
+        assert_eq!(orphans[1].version, "2.0-r1");
+    }
+
+    #[test]
+    fn emerge_updates() {
+        let output = "\
+[ebuild     U  ] sys-apps/portage-3.0.66 [3.0.65-r1]
+[ebuild   R    ] dev-libs/foo-1.0
+[ebuild  N     ] dev-libs/newdep-2.0
+[ebuild     U ~] app-editors/vim-9.1.0 [9.0.2167]
+";
+        let upgrades = parse_emerge_updates(output);
+        assert_eq!(upgrades.len(), 2);
+        assert_eq!(upgrades[0].name, "sys-apps/portage");
+        assert_eq!(upgrades[0].available, "3.0.66");
+        assert_eq!(upgrades[0].current.as_deref(), Some("3.0.65-r1"));
+        assert_eq!(upgrades[1].name, "app-editors/vim");
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(parse_size("300 KiB"), Some(300 * 1024));
+        assert_eq!(parse_size("1,50 MiB"), Some(1_572_864));
+        assert_eq!(parse_size("2.0 GB"), Some(2_000_000_000));
+        assert_eq!(parse_size("12 furlongs"), None);
+    }
+}
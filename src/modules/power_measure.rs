@@ -0,0 +1,269 @@
+// power_measure.rs
+// Sample RAPL energy counters and battery discharge over time to report average and peak watts
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use super::power_status;
+use super::sysfs;
+
+const POWERCAP_DIR: &str = "/sys/class/powercap";
+const HWMON_DIR: &str = "/sys/class/hwmon";
+
+/// A monotonically increasing energy counter in microjoules.
+#[derive(Debug, Clone)]
+struct EnergyCounter {
+    name: String,
+    path: PathBuf,
+    /// Highest value the counter reaches before wrapping to zero (max_energy_range_uj), if known.
+    max_uj: Option<u64>,
+    /// Nesting depth for display (subzones such as core/uncore/dram sit under their package).
+    depth: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+struct DomainStats {
+    total_uj: u64,
+    peak_w: f64,
+}
+
+/// Powercap zones (intel-rapl:0, intel-rapl:0:1, intel-rapl-mmio:0, ...). AMD Zen CPUs expose their
+/// RAPL MSRs through the same intel-rapl powercap driver.
+fn powercap_counters() -> Vec<EnergyCounter> {
+    sysfs::list_dir_prefixed(POWERCAP_DIR, "intel-rapl")
+        .into_iter()
+        .filter(|zone| zone.join("energy_uj").exists())
+        .map(|zone| {
+            let id = zone.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let name = sysfs::read_trimmed(zone.join("name")).unwrap_or_else(|| id.clone());
+            let prefix = if id.starts_with("intel-rapl-mmio") { "mmio " } else { "" };
+            EnergyCounter {
+                name: format!("{}{}", prefix, name),
+                path: zone.join("energy_uj"),
+                max_uj: sysfs::read_u64(zone.join("max_energy_range_uj")),
+                depth: id.matches(':').count().saturating_sub(1),
+            }
+        })
+        .collect()
+}
+
+/// The amd_energy hwmon driver reports per-core and per-socket counters as energyN_input (µJ).
+fn amd_energy_counters() -> Vec<EnergyCounter> {
+    let mut counters = Vec::new();
+    for dir in sysfs::list_dir_prefixed(HWMON_DIR, "hwmon") {
+        if sysfs::read_trimmed(dir.join("name")).as_deref() != Some("amd_energy") {
+            continue;
+        }
+        for input in sysfs::list_dir_prefixed(&dir, "energy") {
+            let file = input.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let Some(base) = file.strip_suffix("_input") else {
+                continue;
+            };
+            let label = sysfs::read_trimmed(dir.join(format!("{}_label", base))).unwrap_or_else(|| base.to_string());
+            // Only socket totals; per-core counters would flood the table.
+            if label.starts_with("Esocket") {
+                counters.push(EnergyCounter { name: format!("amd_energy {}", label), path: input, max_uj: None, depth: 0 });
+            }
+        }
+    }
+    counters
+}
+
+fn energy_delta(counter: &EnergyCounter, before: u64, after: u64) -> u64 {
+    if after >= before {
+        after - before
+    } else {
+        // The counter wrapped around its range 0..=max during the interval.
+        counter.max_uj.map(|max| max.saturating_sub(before) + after + 1).unwrap_or(0)
+    }
+}
+
+fn read_counters(counters: &[EnergyCounter]) -> Vec<Option<u64>> {
+    counters.iter().map(|c| sysfs::read_u64(&c.path)).collect()
+}
+
+/// Current battery discharge in watts for every system battery; None unless it is discharging,
+/// since power_now is the charge rate (or 0) otherwise.
+fn battery_watts() -> Vec<(String, Option<f64>)> {
+    power_status::scan_power_supplies()
+        .into_iter()
+        .filter_map(|s| {
+            let bat = s.battery?;
+            if bat.peripheral {
+                return None;
+            }
+            let watts = bat.power_w.filter(|_| bat.status == "Discharging");
+            Some((s.name, watts))
+        })
+        .collect()
+}
+
+/// Parse "30s", "2m", "1h" or a bare number of seconds.
+fn parse_duration(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    let (number, multiplier) = if let Some(n) = s.strip_suffix("ms") {
+        return n.parse().ok().map(Duration::from_millis);
+    } else if let Some(n) = s.strip_suffix('s') {
+        (n, 1)
+    } else if let Some(n) = s.strip_suffix('m') {
+        (n, 60)
+    } else if let Some(n) = s.strip_suffix('h') {
+        (n, 3600)
+    } else {
+        (s, 1)
+    };
+    number.parse::<u64>().ok()?.checked_mul(multiplier).map(Duration::from_secs)
+}
+
+struct Measurement {
+    elapsed: Duration,
+    counters: Vec<EnergyCounter>,
+    stats: Vec<Option<DomainStats>>,
+    /// Average and peak discharge; None if the battery never discharged while sampling.
+    batteries: Vec<(String, Option<(f64, f64)>)>,
+}
+
+async fn sample(duration: Duration, interval: Duration) -> Measurement {
+    let mut counters = powercap_counters();
+    counters.extend(amd_energy_counters());
+    let mut previous = read_counters(&counters);
+    let mut stats: Vec<Option<DomainStats>> = previous.iter().map(|v| v.map(|_| DomainStats::default())).collect();
+    let mut battery_samples: Vec<(String, Vec<f64>)> = Vec::new();
+
+    let start = Instant::now();
+    let mut last = start;
+    let mut ticker = tokio::time::interval(interval);
+    ticker.tick().await; // the first tick completes immediately
+    while start.elapsed() < duration {
+        ticker.tick().await;
+        let now = Instant::now();
+        let dt = now.duration_since(last).as_secs_f64();
+        last = now;
+
+        let current = read_counters(&counters);
+        for (i, counter) in counters.iter().enumerate() {
+            if let (Some(before), Some(after), Some(stat)) = (previous[i], current[i], stats[i].as_mut()) {
+                let delta = energy_delta(counter, before, after);
+                stat.total_uj += delta;
+                stat.peak_w = stat.peak_w.max(delta as f64 / 1_000_000.0 / dt);
+            }
+        }
+        previous = current;
+
+        for (name, watts) in battery_watts() {
+            let index = match battery_samples.iter().position(|(n, _)| *n == name) {
+                Some(index) => index,
+                None => {
+                    battery_samples.push((name, Vec::new()));
+                    battery_samples.len() - 1
+                }
+            };
+            battery_samples[index].1.extend(watts);
+        }
+    }
+
+    let batteries = battery_samples
+        .into_iter()
+        .map(|(name, samples)| {
+            if samples.is_empty() {
+                return (name, None);
+            }
+            let avg = samples.iter().sum::<f64>() / samples.len() as f64;
+            let peak = samples.iter().cloned().fold(0.0, f64::max);
+            (name, Some((avg, peak)))
+        })
+        .collect();
+    Measurement { elapsed: start.elapsed(), counters, stats, batteries }
+}
+
+/// Value of a duration flag, `default` if the flag is absent, Err if its value is missing or invalid.
+fn duration_option(args: &[String], flag: &str, default: Duration) -> Result<Duration, String> {
+    let Some(i) = args.iter().position(|a| a == flag) else {
+        return Ok(default);
+    };
+    let value = args.get(i + 1).ok_or_else(|| format!("{} needs a value such as 30s, 500ms or 2m", flag))?;
+    parse_duration(value).ok_or_else(|| format!("Invalid {} value: {} (use e.g. 30s, 500ms or 2m)", flag, value))
+}
+
+/// Entry point for `nephyra power measure [--duration 30s] [--interval 1s]`.
+pub fn run(args: &[String]) {
+    let parsed = duration_option(args, "--duration", Duration::from_secs(30))
+        .and_then(|d| Ok((d, duration_option(args, "--interval", Duration::from_secs(1))?)));
+    let (duration, interval) = match parsed {
+        Ok((duration, interval)) => (duration, interval.max(Duration::from_millis(100))),
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return;
+        }
+    };
+
+    println!("📈 Measuring power for {:?} (sampling every {} ms)...", duration, interval.as_millis());
+    let runtime = match tokio::runtime::Builder::new_current_thread().enable_time().build() {
+        Ok(rt) => rt,
+        Err(e) => {
+            eprintln!("❌ Failed to start sampling runtime: {}", e);
+            return;
+        }
+    };
+    let m = runtime.block_on(sample(duration, interval));
+    let secs = m.elapsed.as_secs_f64().max(f64::EPSILON);
+
+    println!("\n{:<28} {:>9} {:>9}", "Domain", "Avg W", "Peak W");
+    let mut unreadable = false;
+    for (counter, stat) in m.counters.iter().zip(&m.stats) {
+        let name = format!("{}{}", "  ".repeat(counter.depth), counter.name);
+        match stat {
+            Some(stat) => {
+                let avg = stat.total_uj as f64 / 1_000_000.0 / secs;
+                println!("{:<28} {:>9.2} {:>9.2}", name, avg, stat.peak_w);
+            }
+            None => {
+                unreadable = true;
+                println!("{:<28} {:>9} {:>9}", name, "n/a", "n/a");
+            }
+        }
+    }
+    for (name, draw) in &m.batteries {
+        let name = format!("battery {}", name);
+        match draw {
+            Some((avg, peak)) => println!("{:<28} {:>9.2} {:>9.2}", name, avg, peak),
+            None => println!("{:<28} {:>9} {:>9}", name, "n/a", "n/a"),
+        }
+    }
+
+    if m.counters.is_empty() && m.batteries.is_empty() {
+        println!("No RAPL energy counters or batteries found.");
+    }
+    if unreadable {
+        println!("\n⚠️ Some energy counters are root-only (since Linux 5.10). Re-run with sudo for full results.");
+    }
+    if m.batteries.iter().any(|(_, draw)| draw.is_none()) {
+        println!("ℹ️ Battery draw is only measured while discharging; unplug from AC to measure it.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_duration("2m"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_duration("1h"), Some(Duration::from_secs(3600)));
+        assert_eq!(parse_duration("45"), Some(Duration::from_secs(45)));
+        assert_eq!(parse_duration("30x"), None);
+        assert_eq!(parse_duration("99999999999999999h"), None, "overflow is rejected");
+    }
+
+    #[test]
+    fn energy_counter_wraparound() {
+        let counter = EnergyCounter { name: "package-0".to_string(), path: "energy_uj".into(), max_uj: Some(999), depth: 0 };
+        assert_eq!(energy_delta(&counter, 100, 350), 250);
+        assert_eq!(energy_delta(&counter, 990, 10), 20, "999 → 0 counts as one step");
+        assert_eq!(energy_delta(&counter, 1200, 10), 11, "a reading above max does not underflow");
+        let unbounded = EnergyCounter { max_uj: None, ..counter };
+        assert_eq!(energy_delta(&unbounded, 990, 10), 0);
+    }
+}
@@ -0,0 +1,408 @@
+// power_profile.rs
+// Apply coherent power profiles via power-profiles-daemon, tuned or sysfs, with a revert record
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use super::cpu_freq::{self, CpuFreqInfo};
+use super::state;
+use super::sysfs;
+
+const PLATFORM_PROFILE_PATH: &str = "/sys/firmware/acpi/platform_profile";
+const PLATFORM_PROFILE_CHOICES_PATH: &str = "/sys/firmware/acpi/platform_profile_choices";
+const STATE_FILE: &str = "power_profile.toml";
+
+// (bus name, object path); the UPower name replaced net.hadess in power-profiles-daemon 0.20.
+const PPD_BUS_NAMES: [(&str, &str); 2] = [
+    ("org.freedesktop.UPower.PowerProfiles", "/org/freedesktop/UPower/PowerProfiles"),
+    ("net.hadess.PowerProfiles", "/net/hadess/PowerProfiles"),
+];
+const TUNED_BUS_NAME: &str = "com.redhat.tuned";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Profile {
+    Performance,
+    Balanced,
+    Powersave,
+}
+
+impl Profile {
+    fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "performance" => Some(Profile::Performance),
+            "balanced" => Some(Profile::Balanced),
+            "powersave" | "power-saver" => Some(Profile::Powersave),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Profile::Performance => "performance",
+            Profile::Balanced => "balanced",
+            Profile::Powersave => "powersave",
+        }
+    }
+
+    fn ppd_name(self) -> &'static str {
+        match self {
+            Profile::Performance => "performance",
+            Profile::Balanced => "balanced",
+            Profile::Powersave => "power-saver",
+        }
+    }
+
+    fn tuned_name(self) -> &'static str {
+        match self {
+            Profile::Performance => "throughput-performance",
+            Profile::Balanced => "balanced",
+            Profile::Powersave => "powersave",
+        }
+    }
+
+    /// platform_profile choices in order of preference.
+    fn platform_choices(self) -> &'static [&'static str] {
+        match self {
+            Profile::Performance => &["performance", "balanced-performance"],
+            Profile::Balanced => &["balanced"],
+            Profile::Powersave => &["low-power", "quiet", "cool"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Backend {
+    PowerProfilesDaemon { bus_name: &'static str, object: &'static str },
+    Tuned,
+    Sysfs,
+}
+
+impl Backend {
+    fn label(&self) -> &'static str {
+        match self {
+            Backend::PowerProfilesDaemon { .. } => "power-profiles-daemon",
+            Backend::Tuned => "tuned",
+            Backend::Sysfs => "sysfs",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct SavedAttribute {
+    path: String,
+    value: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct SavedPowerState {
+    saved_at: String,
+    backend: String,
+    previous_profile: Option<String>,
+    attributes: Vec<SavedAttribute>,
+}
+
+fn busctl(args: &[&str]) -> Option<String> {
+    let output = Command::new("busctl").arg("--system").args(args).output().ok()?;
+    if output.status.success() {
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        None
+    }
+}
+
+/// Extract the string from busctl's `s "value"` reply format.
+fn parse_busctl_string(reply: &str) -> Option<String> {
+    let value = reply.strip_prefix("s ")?.trim();
+    Some(value.trim_matches('"').to_string())
+}
+
+/// Whether a service currently owns `name` on the system bus.
+pub fn bus_has_owner(name: &str) -> bool {
+    busctl(&["call", "org.freedesktop.DBus", "/org/freedesktop/DBus", "org.freedesktop.DBus", "NameHasOwner", "s", name])
+        .map(|reply| reply == "b true")
+        .unwrap_or(false)
+}
+
+fn detect_backend() -> Backend {
+    for (bus_name, object) in PPD_BUS_NAMES {
+        if bus_has_owner(bus_name) {
+            return Backend::PowerProfilesDaemon { bus_name, object };
+        }
+    }
+    if bus_has_owner(TUNED_BUS_NAME) {
+        return Backend::Tuned;
+    }
+    Backend::Sysfs
+}
+
+fn current_daemon_profile(backend: &Backend) -> Option<String> {
+    match backend {
+        Backend::PowerProfilesDaemon { bus_name, object } => {
+            busctl(&["get-property", bus_name, object, bus_name, "ActiveProfile"]).and_then(|r| parse_busctl_string(&r))
+        }
+        Backend::Tuned => {
+            busctl(&["call", TUNED_BUS_NAME, "/Tuned", "com.redhat.tuned.control", "active_profile"])
+                .and_then(|r| parse_busctl_string(&r))
+        }
+        Backend::Sysfs => None,
+    }
+}
+
+fn set_daemon_profile(backend: &Backend, name: &str) -> Result<(), String> {
+    let ok = match backend {
+        Backend::PowerProfilesDaemon { bus_name, object } => {
+            busctl(&["set-property", bus_name, object, bus_name, "ActiveProfile", "s", name]).is_some()
+        }
+        Backend::Tuned => {
+            // switch_profile replies with "(bs) true \"OK\"" on success.
+            busctl(&["call", TUNED_BUS_NAME, "/Tuned", "com.redhat.tuned.control", "switch_profile", "s", name])
+                .map(|r| r.contains("true"))
+                .unwrap_or(false)
+        }
+        Backend::Sysfs => false,
+    };
+    if ok {
+        Ok(())
+    } else {
+        Err(format!("{} rejected profile '{}'", backend.label(), name))
+    }
+}
+
+/// The profile currently active via a daemon or the ACPI platform profile, with its source.
+pub fn active_profile() -> Option<(String, &'static str)> {
+    let backend = detect_backend();
+    if let Some(profile) = current_daemon_profile(&backend) {
+        return Some((profile, backend.label()));
+    }
+    sysfs::read_trimmed(PLATFORM_PROFILE_PATH).map(|p| (p, "platform_profile"))
+}
+
+/// Build the ordered list of sysfs writes for a profile. Governors are written before EPP because
+/// intel_pstate rejects EPP changes while the performance governor is active.
+fn plan_sysfs(profile: Profile, info: &CpuFreqInfo) -> Vec<(PathBuf, String)> {
+    let mut writes = Vec::new();
+    for policy in &info.policies {
+        let dir = Path::new(cpu_freq::CPUFREQ_DIR).join(&policy.name);
+        let epp_driven = !policy.available_epp.is_empty();
+        let governor = match profile {
+            Profile::Performance => "performance",
+            _ if epp_driven => "powersave",
+            _ => "schedutil",
+        };
+        if policy.available_governors.iter().any(|g| g == governor) && policy.governor != governor {
+            writes.push((dir.join("scaling_governor"), governor.to_string()));
+        }
+        if epp_driven && governor != "performance" {
+            let epp = match profile {
+                Profile::Performance => "performance",
+                Profile::Balanced => "balance_performance",
+                Profile::Powersave => "power",
+            };
+            if policy.available_epp.iter().any(|e| e == epp) && policy.epp.as_deref() != Some(epp) {
+                writes.push((dir.join("energy_performance_preference"), epp.to_string()));
+            }
+        }
+    }
+
+    let boost = profile != Profile::Powersave;
+    if let Some(current) = info.boost
+        && current != boost
+    {
+        if Path::new(cpu_freq::BOOST_PATH).exists() {
+            writes.push((PathBuf::from(cpu_freq::BOOST_PATH), if boost { "1" } else { "0" }.to_string()));
+        } else if Path::new(cpu_freq::INTEL_NO_TURBO_PATH).exists() {
+            writes.push((PathBuf::from(cpu_freq::INTEL_NO_TURBO_PATH), if boost { "0" } else { "1" }.to_string()));
+        }
+    }
+
+    let choices: Vec<String> = sysfs::read_trimmed(PLATFORM_PROFILE_CHOICES_PATH)
+        .map(|s| s.split_whitespace().map(|c| c.to_string()).collect())
+        .unwrap_or_default();
+    if let Some(choice) = profile.platform_choices().iter().find(|c| choices.iter().any(|a| a == *c))
+        && sysfs::read_trimmed(PLATFORM_PROFILE_PATH).as_deref() != Some(choice)
+    {
+        writes.push((PathBuf::from(PLATFORM_PROFILE_PATH), choice.to_string()));
+    }
+    writes
+}
+
+fn apply_writes(writes: &[(PathBuf, String)]) -> usize {
+    let mut failures = 0;
+    for (path, value) in writes {
+        match sysfs::write_attr(path, value) {
+            Ok(()) => println!("  ✅ {} = {}", path.display(), value),
+            Err(e) => {
+                failures += 1;
+                eprintln!("  ❌ {} = {}: {}", path.display(), value, e);
+            }
+        }
+    }
+    failures
+}
+
+fn save_state(saved: SavedPowerState) {
+    let merged = match state::load::<SavedPowerState>(STATE_FILE) {
+        // --restore drives one backend; a record for another one is left alone.
+        Some(existing) if existing.backend != saved.backend => {
+            println!("ℹ️ Keeping existing {} revert record from an earlier profile change.", existing.backend);
+            return;
+        }
+        Some(mut existing) => {
+            // Keep the first recorded values so --restore returns to the state before Nephyra touched anything.
+            existing.previous_profile = existing.previous_profile.or(saved.previous_profile);
+            for attr in saved.attributes {
+                if !existing.attributes.iter().any(|a| a.path == attr.path) {
+                    existing.attributes.push(attr);
+                }
+            }
+            existing.saved_at = saved.saved_at;
+            existing
+        }
+        None => saved,
+    };
+    match state::save(STATE_FILE, &merged) {
+        Ok(path) => println!("💾 Previous state recorded in {}", path.display()),
+        Err(e) => eprintln!("⚠️ Failed to record previous state: {}", e),
+    }
+}
+
+fn apply_profile(profile: Profile, dry_run: bool) {
+    let backend = detect_backend();
+    println!("⚡ Applying '{}' profile via {}", profile.label(), backend.label());
+
+    if backend != Backend::Sysfs {
+        let name = if backend == Backend::Tuned { profile.tuned_name() } else { profile.ppd_name() };
+        let previous = current_daemon_profile(&backend);
+        if dry_run {
+            println!("  Would switch {} from {} to {}", backend.label(), previous.as_deref().unwrap_or("unknown"), name);
+            return;
+        }
+        save_state(SavedPowerState {
+            saved_at: Local::now().to_rfc3339(),
+            backend: backend.label().to_string(),
+            previous_profile: previous,
+            attributes: Vec::new(),
+        });
+        match set_daemon_profile(&backend, name) {
+            Ok(()) => println!("✅ {} profile set to {}", backend.label(), name),
+            Err(e) => eprintln!("❌ {}", e),
+        }
+        return;
+    }
+
+    let writes = plan_sysfs(profile, &cpu_freq::read_cpufreq());
+    if writes.is_empty() {
+        println!("✅ System already matches the '{}' profile (or exposes no tunable knobs).", profile.label());
+        return;
+    }
+    if dry_run {
+        for (path, value) in &writes {
+            println!("  Would write {} to {}", value, path.display());
+        }
+        return;
+    }
+    if !sysfs::is_root() {
+        eprintln!("❌ Writing sysfs power settings requires root. Re-run with sudo.");
+        return;
+    }
+    let attributes = writes
+        .iter()
+        .filter_map(|(path, _)| {
+            sysfs::read_trimmed(path).map(|value| SavedAttribute { path: path.display().to_string(), value })
+        })
+        .collect();
+    save_state(SavedPowerState {
+        saved_at: Local::now().to_rfc3339(),
+        backend: backend.label().to_string(),
+        previous_profile: None,
+        attributes,
+    });
+    let failures = apply_writes(&writes);
+    if failures > 0 {
+        eprintln!("⚠️ {} setting(s) could not be applied.", failures);
+    }
+}
+
+fn restore(dry_run: bool) {
+    let Some(saved) = state::load::<SavedPowerState>(STATE_FILE) else {
+        println!("ℹ️ No recorded power state to restore.");
+        return;
+    };
+    println!("⏪ Restoring power state recorded at {} ({})", saved.saved_at, saved.backend);
+
+    let ok = if saved.backend == "sysfs" {
+        if dry_run {
+            for attr in &saved.attributes {
+                println!("  Would write {} to {}", attr.value, attr.path);
+            }
+            return;
+        }
+        if !sysfs::is_root() {
+            eprintln!("❌ Restoring sysfs power settings requires root. Re-run with sudo.");
+            return;
+        }
+        let writes: Vec<(PathBuf, String)> =
+            saved.attributes.iter().map(|a| (PathBuf::from(&a.path), a.value.clone())).collect();
+        apply_writes(&writes) == 0
+    } else {
+        let backend = detect_backend();
+        let Some(previous) = saved.previous_profile.as_deref() else {
+            eprintln!("❌ The revert record does not contain a previous profile.");
+            return;
+        };
+        if backend.label() != saved.backend {
+            eprintln!("❌ {} is no longer running; cannot restore profile '{}'.", saved.backend, previous);
+            return;
+        }
+        if dry_run {
+            println!("  Would switch {} back to {}", backend.label(), previous);
+            return;
+        }
+        match set_daemon_profile(&backend, previous) {
+            Ok(()) => {
+                println!("✅ {} profile restored to {}", backend.label(), previous);
+                true
+            }
+            Err(e) => {
+                eprintln!("❌ {}", e);
+                false
+            }
+        }
+    };
+
+    if ok {
+        if let Err(e) = state::remove(STATE_FILE) {
+            eprintln!("⚠️ Failed to remove revert record: {}", e);
+        }
+    } else {
+        eprintln!("⚠️ Revert record kept; fix the errors above and retry --restore.");
+    }
+}
+
+/// Entry point for `nephyra power profile ...`.
+pub fn run(args: &[String]) {
+    let dry_run = args.iter().any(|a| a == "--dry-run");
+    let positional: Vec<&String> = args.iter().filter(|a| !a.starts_with("--")).collect();
+
+    if args.iter().any(|a| a == "--restore") {
+        restore(dry_run);
+        return;
+    }
+    match positional.first().map(|s| Profile::parse(s)) {
+        Some(Some(profile)) => apply_profile(profile, dry_run),
+        Some(None) => {
+            eprintln!("❌ Unknown profile: {}", positional[0]);
+            println!("Available profiles: performance, balanced, powersave");
+        }
+        None => {
+            if let Some((profile, source)) = active_profile() {
+                println!("Active power profile: {} ({})", profile, source);
+            }
+            println!("ℹ️ Usage: nephyra power profile <performance|balanced|powersave> [--dry-run]");
+            println!("         nephyra power profile --restore [--dry-run]");
+        }
+    }
+}
@@ -1,70 +1,375 @@
 // power_status.rs
 
-use std::fs;
+use std::path::Path;
 
-pub fn run() {
+use super::battery_threshold;
+use super::cpu_freq;
+use super::dmi_info;
+use super::kernel_check;
+use super::power_measure;
+use super::power_profile;
+use super::power_tunables;
+use super::sleep_check;
+use super::sysfs;
+
+pub const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SupplyKind {
+    Battery,
+    Mains,
+    Usb,
+    Ups,
+    Wireless,
+    Unknown(String),
+}
+
+impl SupplyKind {
+    fn from_sysfs(kind: &str) -> Self {
+        match kind {
+            "Battery" => SupplyKind::Battery,
+            "Mains" => SupplyKind::Mains,
+            "USB" => SupplyKind::Usb,
+            "UPS" => SupplyKind::Ups,
+            "Wireless" => SupplyKind::Wireless,
+            other => SupplyKind::Unknown(other.to_string()),
+        }
+    }
+
+    fn label(&self) -> &str {
+        match self {
+            SupplyKind::Battery => "Battery",
+            SupplyKind::Mains => "Mains",
+            SupplyKind::Usb => "USB",
+            SupplyKind::Ups => "UPS",
+            SupplyKind::Wireless => "Wireless",
+            SupplyKind::Unknown(s) => s,
+        }
+    }
+}
+
+/// Unit of the now/full/design values: energy (Wh) or charge (Ah), depending on what the firmware exposes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CapacityUnit {
+    WattHours,
+    AmpHours,
+}
+
+impl CapacityUnit {
+    fn suffix(self) -> &'static str {
+        match self {
+            CapacityUnit::WattHours => "Wh",
+            CapacityUnit::AmpHours => "Ah",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BatteryInfo {
+    pub status: String,
+    pub capacity: Option<u64>,
+    pub health: Option<String>,
+    pub technology: Option<String>,
+    pub model: Option<String>,
+    pub manufacturer: Option<String>,
+    /// True for peripheral batteries (mice, keyboards, headsets) that don't power the system.
+    pub peripheral: bool,
+    pub unit: CapacityUnit,
+    pub now: Option<f64>,
+    pub full: Option<f64>,
+    pub design: Option<f64>,
+    pub cycle_count: Option<u64>,
+    pub charge_start_threshold: Option<u64>,
+    pub charge_end_threshold: Option<u64>,
+    /// Charge/discharge rate in W (energy batteries) or A (charge batteries).
+    pub rate: Option<f64>,
+    pub power_w: Option<f64>,
+    time_to_empty_firmware: Option<f64>,
+    time_to_full_firmware: Option<f64>,
+}
+
+impl BatteryInfo {
+    /// Percentage of the design capacity that has been lost.
+    pub fn wear_percent(&self) -> Option<f64> {
+        match (self.full, self.design) {
+            (Some(full), Some(design)) if design > 0.0 => Some((100.0 - full / design * 100.0).max(0.0)),
+            _ => None,
+        }
+    }
+
+    /// Estimated hours until the battery is empty, only meaningful while discharging.
+    pub fn time_to_empty_hours(&self) -> Option<f64> {
+        if self.status != "Discharging" {
+            return None;
+        }
+        if self.time_to_empty_firmware.is_some() {
+            return self.time_to_empty_firmware;
+        }
+        match (self.now, self.rate) {
+            (Some(now), Some(rate)) if rate > 0.0 => Some(now / rate),
+            _ => None,
+        }
+    }
+
+    /// Estimated hours until the battery is full, only meaningful while charging.
+    pub fn time_to_full_hours(&self) -> Option<f64> {
+        if self.status != "Charging" {
+            return None;
+        }
+        if self.time_to_full_firmware.is_some() {
+            return self.time_to_full_firmware;
+        }
+        match (self.now, self.full, self.rate) {
+            (Some(now), Some(full), Some(rate)) if rate > 0.0 && full > now => Some((full - now) / rate),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PowerSupply {
+    pub name: String,
+    pub kind: SupplyKind,
+    pub online: Option<bool>,
+    /// Active USB power negotiation mode (e.g. "PD", "C"), taken from the bracketed entry in `usb_type`.
+    pub usb_type: Option<String>,
+    pub battery: Option<BatteryInfo>,
+}
+
+fn read_battery(path: &Path) -> BatteryInfo {
+    let attr = |name: &str| sysfs::read_trimmed(path.join(name)).filter(|s| !s.is_empty());
+    let micro = |name: &str| sysfs::read_i64(path.join(name)).map(|v| v.unsigned_abs() as f64 / 1_000_000.0);
+
+    let voltage_now = micro("voltage_now");
+    let voltage_design = micro("voltage_min_design").or(voltage_now);
+
+    // Prefer energy_* (µWh); fall back to charge_* (µAh) when the firmware only reports charge.
+    let (unit, now, full, design) = if micro("energy_full").is_some() || micro("energy_now").is_some() {
+        (CapacityUnit::WattHours, micro("energy_now"), micro("energy_full"), micro("energy_full_design"))
+    } else {
+        (CapacityUnit::AmpHours, micro("charge_now"), micro("charge_full"), micro("charge_full_design"))
+    };
+
+    let current_a = micro("current_now");
+    let derived_power = match (current_a, voltage_now) {
+        (Some(a), Some(v)) => Some(a * v),
+        _ => None,
+    };
+    let power_w = micro("power_now").or(derived_power);
+    let rate = match unit {
+        CapacityUnit::WattHours => power_w,
+        CapacityUnit::AmpHours => current_a.or_else(|| match (power_w, voltage_design) {
+            (Some(w), Some(v)) if v > 0.0 => Some(w / v),
+            _ => None,
+        }),
+    };
+
+    let seconds_to_hours = |name: &str| sysfs::read_u64(path.join(name)).map(|s| s as f64 / 3600.0);
+
+    BatteryInfo {
+        status: attr("status").unwrap_or_else(|| "Unknown".to_string()),
+        capacity: sysfs::read_u64(path.join("capacity")),
+        health: attr("health"),
+        technology: attr("technology"),
+        model: attr("model_name"),
+        manufacturer: attr("manufacturer"),
+        peripheral: attr("scope").as_deref() == Some("Device"),
+        unit,
+        now,
+        full,
+        design,
+        // Many firmwares report 0 when they don't track cycles.
+        cycle_count: sysfs::read_u64(path.join("cycle_count")).filter(|&c| c > 0),
+        charge_start_threshold: sysfs::read_u64(path.join("charge_control_start_threshold")),
+        charge_end_threshold: sysfs::read_u64(path.join("charge_control_end_threshold")),
+        rate,
+        power_w,
+        time_to_empty_firmware: seconds_to_hours("time_to_empty_now"),
+        time_to_full_firmware: seconds_to_hours("time_to_full_now"),
+    }
+}
+
+/// Enumerate every entry under /sys/class/power_supply and classify it by its `type` attribute.
+pub fn scan_power_supplies() -> Vec<PowerSupply> {
+    sysfs::list_dir_prefixed(POWER_SUPPLY_DIR, "")
+        .into_iter()
+        .map(|path| {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let kind = SupplyKind::from_sysfs(&sysfs::read_trimmed(path.join("type")).unwrap_or_default());
+            let online = sysfs::read_u64(path.join("online")).map(|v| v != 0);
+            let usb_type = sysfs::read_trimmed(path.join("usb_type")).and_then(|s| sysfs::bracketed_choice(&s));
+            let battery = if kind == SupplyKind::Battery { Some(read_battery(&path)) } else { None };
+            PowerSupply { name, kind, online, usb_type, battery }
+        })
+        .collect()
+}
+
+/// Whether the system is running from external power. None when no adapter is exposed (e.g. most desktops).
+pub fn on_ac_power(supplies: &[PowerSupply]) -> Option<bool> {
+    let adapters: Vec<&PowerSupply> = supplies
+        .iter()
+        .filter(|s| matches!(s.kind, SupplyKind::Mains | SupplyKind::Usb | SupplyKind::Ups | SupplyKind::Wireless))
+        .filter(|s| s.online.is_some())
+        .collect();
+    if adapters.is_empty() {
+        return None;
+    }
+    Some(adapters.iter().any(|s| s.online == Some(true)))
+}
+
+fn format_hours(hours: f64) -> String {
+    let total_minutes = (hours * 60.0).round() as u64;
+    format!("{}h {:02}m", total_minutes / 60, total_minutes % 60)
+}
+
+fn print_battery(name: &str, bat: &BatteryInfo) {
+    let mut descr = vec![if bat.peripheral { "Peripheral" } else { "System" }.to_string()];
+    if let Some(tech) = &bat.technology {
+        descr.push(tech.clone());
+    }
+    let model = [bat.manufacturer.as_deref(), bat.model.as_deref()]
+        .iter()
+        .flatten()
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if !model.is_empty() {
+        descr.push(model);
+    }
+    println!("Battery {} ({}):", name, descr.join(", "));
+    println!("  Status   : {}", bat.status);
+    if let Some(cap) = bat.capacity {
+        println!("  Capacity : {}%", cap);
+    }
+    let unit = bat.unit.suffix();
+    if let (Some(now), Some(full)) = (bat.now, bat.full) {
+        let design = bat.design.map(|d| format!(" (design {:.2} {})", d, unit)).unwrap_or_default();
+        let label = if bat.unit == CapacityUnit::WattHours { "Energy" } else { "Charge" };
+        println!("  {:<9}: {:.2} / {:.2} {}{}", label, now, full, unit, design);
+    }
+    if let Some(wear) = bat.wear_percent() {
+        println!("  Wear     : {:.1}%", wear);
+    }
+    if let Some(cycles) = bat.cycle_count {
+        println!("  Cycles   : {}", cycles);
+    }
+    if let Some(w) = bat.power_w.filter(|&w| w > 0.0) {
+        println!("  Draw     : {:.2} W", w);
+    }
+    if let Some(h) = bat.time_to_empty_hours() {
+        println!("  To empty : {}", format_hours(h));
+    }
+    if let Some(h) = bat.time_to_full_hours() {
+        println!("  To full  : {}", format_hours(h));
+    }
+    if let Some(health) = &bat.health {
+        println!("  Health   : {}", health);
+    }
+    if bat.charge_start_threshold.is_some() || bat.charge_end_threshold.is_some() {
+        let fmt = |v: Option<u64>| v.map(|t| format!("{}%", t)).unwrap_or_else(|| "n/a".to_string());
+        println!(
+            "  Charge limits: start {}, end {}",
+            fmt(bat.charge_start_threshold),
+            fmt(bat.charge_end_threshold)
+        );
+    }
+}
+
+pub fn run(args: &[String]) {
+    match args.first().map(|s| s.as_str()) {
+        Some("profile") => power_profile::run(&args[1..]),
+        Some("thresholds") => battery_threshold::run(&args[1..]),
+        Some("measure") => power_measure::run(&args[1..]),
+        Some("tunables") => power_tunables::run(&args[1..]),
+        Some("sleep") => sleep_check::run(),
+        Some(other) => {
+            eprintln!("❌ Unknown power command: {}", other);
+            println!("Try: nephyra power [profile|thresholds|measure|tunables|sleep]");
+        }
+        None => show_status(),
+    }
+}
+
+fn show_status() {
     println!("🔋 Power Status\n");
 
-    // Try to find a battery device (BAT0, BAT1, etc.)
+    let supplies = scan_power_supplies();
+
     let mut found_battery = false;
-    for idx in 0..2 {
-        let battery_path = format!("/sys/class/power_supply/BAT{}", idx);
-        if fs::metadata(&battery_path).is_ok() {
+    for supply in &supplies {
+        if let Some(bat) = &supply.battery {
             found_battery = true;
-            let status = fs::read_to_string(format!("{}/status", battery_path)).unwrap_or_else(|_| "Unknown".to_string());
-            let capacity = fs::read_to_string(format!("{}/capacity", battery_path)).unwrap_or_else(|_| "Unknown".to_string());
-            let health = fs::read_to_string(format!("{}/health", battery_path)).unwrap_or_else(|_| "Unknown".to_string());
-            println!("Battery {}:", idx);
-            println!("  Status   : {}", status.trim());
-            println!("  Capacity : {}%", capacity.trim());
-            println!("  Health   : {}", health.trim());
+            print_battery(&supply.name, bat);
         }
     }
     if !found_battery {
         println!("Battery: Not detected");
     }
 
-    // AC/charging state
-    let ac_path = "/sys/class/power_supply/AC";
-    if fs::metadata(ac_path).is_ok() {
-        let online = fs::read_to_string(format!("{}/online", ac_path)).unwrap_or_else(|_| "Unknown".to_string());
-        let ac_state = match online.trim() {
-            "1" => "Connected (Charging)",
-            "0" => "Disconnected (On battery)",
-            _ => "Unknown",
-        };
-        println!("AC Adapter: {}", ac_state);
+    let adapters: Vec<&PowerSupply> = supplies.iter().filter(|s| s.battery.is_none()).collect();
+    if !adapters.is_empty() {
+        println!("\nPower supplies:");
+        for supply in adapters {
+            let kind = match &supply.usb_type {
+                Some(t) => format!("{} ({})", supply.kind.label(), t),
+                None => supply.kind.label().to_string(),
+            };
+            let state = match supply.online {
+                Some(true) => "Online",
+                Some(false) => "Offline",
+                None => "Unknown",
+            };
+            println!("  {}: {}, {}", supply.name, kind, state);
+        }
+    }
+
+    let on_ac = on_ac_power(&supplies);
+    let ac_state = match on_ac {
+        Some(true) => "Connected",
+        Some(false) => "Disconnected (On battery)",
+        None => "Unknown",
+    };
+    println!("\nAC Adapter: {}", ac_state);
+    if let Some((profile, source)) = power_profile::active_profile() {
+        println!("Power Profile: {} ({})", profile, source);
+    }
+    let form_factor = dmi_info::form_factor();
+    println!("Form factor: {}", form_factor.label());
+
+    // Servers get throughput-oriented scaling advice even if no server software was detected
+    let mut use_cases = kernel_check::effective_use_cases();
+    if form_factor == dmi_info::FormFactor::Server && !use_cases.iter().any(|c| c == "server") {
+        use_cases.push("server".to_string());
     }
+    cpu_freq::run(on_ac, &use_cases);
 }
 
 pub fn get_summary() -> String {
-    let mut battery_summaries = Vec::new();
-    let mut found_battery = false;
-    for idx in 0..2 {
-        let battery_path = format!("/sys/class/power_supply/BAT{}", idx);
-        if std::fs::metadata(&battery_path).is_ok() {
-            found_battery = true;
-            let status = std::fs::read_to_string(format!("{}/status", battery_path)).unwrap_or_else(|_| "Unknown".to_string());
-            let capacity = std::fs::read_to_string(format!("{}/capacity", battery_path)).unwrap_or_else(|_| "Unknown".to_string());
-            battery_summaries.push(format!("Battery {}: {} ({}%)", idx, status.trim(), capacity.trim()));
-        }
-    }
-    let battery_summary = if found_battery {
-        battery_summaries.join(" | ")
-    } else {
+    let supplies = scan_power_supplies();
+    let battery_summaries: Vec<String> = supplies
+        .iter()
+        .filter_map(|s| s.battery.as_ref().map(|b| (s, b)))
+        .map(|(s, bat)| {
+            let mut details = vec![bat.capacity.map(|c| format!("{}%", c)).unwrap_or_else(|| "?%".to_string())];
+            if let Some(wear) = bat.wear_percent() {
+                details.push(format!("wear {:.0}%", wear));
+            }
+            if let Some(h) = bat.time_to_empty_hours() {
+                details.push(format!("{} left", format_hours(h)));
+            }
+            let label = if bat.peripheral { "Peripheral battery" } else { "Battery" };
+            format!("{} {}: {} ({})", label, s.name, bat.status, details.join(", "))
+        })
+        .collect();
+    let battery_summary = if battery_summaries.is_empty() {
         "Battery: Not detected".to_string()
-    };
-    let ac_path = "/sys/class/power_supply/AC";
-    let ac_summary = if std::fs::metadata(ac_path).is_ok() {
-        let online = std::fs::read_to_string(format!("{}/online", ac_path)).unwrap_or_else(|_| "Unknown".to_string());
-        match online.trim() {
-            "1" => "AC: Connected".to_string(),
-            "0" => "AC: Disconnected".to_string(),
-            _ => "AC: Unknown".to_string(),
-        }
     } else {
-        "AC: Unknown".to_string()
+        battery_summaries.join(" | ")
     };
-    format!("{} | {}", battery_summary, ac_summary)
+    let ac_summary = match on_ac_power(&supplies) {
+        Some(true) => "AC: Connected",
+        Some(false) => "AC: Disconnected",
+        None => "AC: Unknown",
+    };
+    format!("{} | {}\n{}", battery_summary, ac_summary, cpu_freq::get_summary())
 }
-
@@ -0,0 +1,308 @@
+// power_tunables.rs
+// Audit runtime power knobs (powertop "Tunables" style), apply recommendations and revert them
+
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use super::power_status;
+use super::state;
+use super::sysfs;
+
+const STATE_FILE: &str = "power_tunables.toml";
+const HDA_POWER_SAVE_PATH: &str = "/sys/module/snd_hda_intel/parameters/power_save";
+const NMI_WATCHDOG_PATH: &str = "/proc/sys/kernel/nmi_watchdog";
+const DIRTY_WRITEBACK_PATH: &str = "/proc/sys/vm/dirty_writeback_centisecs";
+const USB_HID_CLASS: &str = "03";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Knob {
+    /// A sysfs or procfs file.
+    File(PathBuf),
+    /// Wi-Fi power save for an interface, controlled through `iw`.
+    WifiPowerSave(String),
+}
+
+#[derive(Debug, Clone)]
+struct Tunable {
+    description: String,
+    knob: Knob,
+    current: String,
+    /// None means any value is fine for the current power source.
+    recommended: Option<String>,
+}
+
+impl Tunable {
+    fn is_good(&self) -> bool {
+        self.recommended.as_ref().is_none_or(|r| *r == self.current)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct SavedTunable {
+    path: Option<String>,
+    wifi_interface: Option<String>,
+    value: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct SavedTunables {
+    saved_at: String,
+    tunables: Vec<SavedTunable>,
+}
+
+fn file_tunable(description: String, path: PathBuf, recommended: Option<&str>) -> Option<Tunable> {
+    let current = sysfs::read_trimmed(&path)?;
+    Some(Tunable { description, knob: Knob::File(path), current, recommended: recommended.map(|s| s.to_string()) })
+}
+
+fn pci_runtime_pm() -> Vec<Tunable> {
+    sysfs::list_dir_prefixed("/sys/bus/pci/devices", "")
+        .into_iter()
+        .filter_map(|dev| {
+            let name = dev.file_name()?.to_string_lossy().to_string();
+            let driver = std::fs::read_link(dev.join("driver"))
+                .ok()
+                .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+                .unwrap_or_else(|| "no driver".to_string());
+            file_tunable(
+                format!("Runtime PM for PCI device {} ({})", name, driver),
+                dev.join("power/control"),
+                Some("auto"),
+            )
+        })
+        .collect()
+}
+
+/// Whether any interface of a USB device is HID; autosuspending mice and keyboards makes them laggy.
+fn is_usb_hid(dev: &Path) -> bool {
+    sysfs::list_dir_prefixed(dev, "")
+        .iter()
+        .any(|intf| sysfs::read_trimmed(intf.join("bInterfaceClass")).as_deref() == Some(USB_HID_CLASS))
+}
+
+fn usb_autosuspend() -> Vec<Tunable> {
+    sysfs::list_dir_prefixed("/sys/bus/usb/devices", "")
+        .into_iter()
+        .filter(|dev| dev.join("idVendor").exists() && !is_usb_hid(dev))
+        .filter_map(|dev| {
+            let name = dev.file_name()?.to_string_lossy().to_string();
+            let product = sysfs::read_trimmed(dev.join("product")).unwrap_or_else(|| "unknown".to_string());
+            file_tunable(format!("Autosuspend for USB device {} ({})", name, product), dev.join("power/control"), Some("auto"))
+        })
+        .collect()
+}
+
+fn sata_alpm() -> Vec<Tunable> {
+    sysfs::list_dir_prefixed("/sys/class/scsi_host", "host")
+        .into_iter()
+        .filter_map(|host| {
+            let name = host.file_name()?.to_string_lossy().to_string();
+            file_tunable(
+                format!("SATA link power management for {}", name),
+                host.join("link_power_management_policy"),
+                Some("med_power_with_dipm"),
+            )
+        })
+        .collect()
+}
+
+fn wireless_interfaces() -> Vec<String> {
+    sysfs::list_dir_prefixed("/sys/class/net", "")
+        .into_iter()
+        .filter(|iface| iface.join("wireless").exists() || iface.join("phy80211").exists())
+        .filter_map(|iface| iface.file_name().map(|n| n.to_string_lossy().to_string()))
+        .collect()
+}
+
+fn wifi_power_save(iface: &str) -> Option<String> {
+    let output = Command::new("iw").args(["dev", iface, "get", "power_save"]).output().ok()?;
+    // "Power save: on"
+    let text = String::from_utf8_lossy(&output.stdout);
+    text.split_once(':').map(|(_, v)| v.trim().to_string())
+}
+
+fn audit(on_battery: bool) -> Vec<Tunable> {
+    let battery_only = |value: &'static str| if on_battery { Some(value) } else { None };
+    let mut tunables = Vec::new();
+    tunables.extend(pci_runtime_pm());
+    tunables.extend(usb_autosuspend());
+    tunables.extend(sata_alpm());
+    tunables.extend(file_tunable(
+        "Audio codec power save (snd_hda_intel)".to_string(),
+        PathBuf::from(HDA_POWER_SAVE_PATH),
+        battery_only("1"),
+    ));
+    tunables.extend(file_tunable("NMI watchdog".to_string(), PathBuf::from(NMI_WATCHDOG_PATH), battery_only("0")));
+    tunables.extend(file_tunable(
+        "VM writeback timeout (vm.dirty_writeback_centisecs)".to_string(),
+        PathBuf::from(DIRTY_WRITEBACK_PATH),
+        battery_only("1500"),
+    ));
+    for iface in wireless_interfaces() {
+        if let Some(current) = wifi_power_save(&iface) {
+            tunables.push(Tunable {
+                description: format!("Wi-Fi power save for {}", iface),
+                knob: Knob::WifiPowerSave(iface),
+                current,
+                recommended: battery_only("on").map(|s| s.to_string()),
+            });
+        }
+    }
+    tunables
+}
+
+fn write_knob(knob: &Knob, value: &str) -> io::Result<()> {
+    match knob {
+        Knob::File(path) => sysfs::write_attr(path, value),
+        Knob::WifiPowerSave(iface) => {
+            let status = Command::new("iw").args(["dev", iface, "set", "power_save", value]).status()?;
+            if status.success() {
+                Ok(())
+            } else {
+                Err(io::Error::other(format!("iw exited with {}", status)))
+            }
+        }
+    }
+}
+
+fn knob_label(knob: &Knob) -> String {
+    match knob {
+        Knob::File(path) => path.display().to_string(),
+        Knob::WifiPowerSave(iface) => format!("iw dev {} power_save", iface),
+    }
+}
+
+fn record_previous(bad: &[&Tunable]) {
+    let mut saved = state::load::<SavedTunables>(STATE_FILE).unwrap_or_default();
+    for t in bad {
+        let (path, wifi_interface) = match &t.knob {
+            Knob::File(p) => (Some(p.display().to_string()), None),
+            Knob::WifiPowerSave(iface) => (None, Some(iface.clone())),
+        };
+        // Keep the first recorded value so repeated applies still revert to the original.
+        if saved.tunables.iter().any(|s| s.path == path && s.wifi_interface == wifi_interface) {
+            continue;
+        }
+        saved.tunables.push(SavedTunable { path, wifi_interface, value: t.current.clone() });
+    }
+    saved.saved_at = Local::now().to_rfc3339();
+    match state::save(STATE_FILE, &saved) {
+        Ok(path) => println!("💾 Previous values recorded in {}", path.display()),
+        Err(e) => eprintln!("⚠️ Failed to record previous values: {}", e),
+    }
+}
+
+fn apply(bad: &[&Tunable], dry_run: bool) {
+    if dry_run {
+        for t in bad {
+            if let Some(value) = &t.recommended {
+                println!("  Would set {} to {}", knob_label(&t.knob), value);
+            }
+        }
+        return;
+    }
+    if !sysfs::is_root() {
+        eprintln!("❌ Applying tunables requires root. Re-run with sudo.");
+        return;
+    }
+    record_previous(bad);
+    for t in bad {
+        let Some(value) = &t.recommended else {
+            continue;
+        };
+        match write_knob(&t.knob, value) {
+            Ok(()) => println!("  ✅ {} = {}", knob_label(&t.knob), value),
+            Err(e) => eprintln!("  ❌ {} = {}: {}", knob_label(&t.knob), value, e),
+        }
+    }
+}
+
+fn restore(dry_run: bool) {
+    let Some(saved) = state::load::<SavedTunables>(STATE_FILE) else {
+        println!("ℹ️ No recorded tunable values to restore.");
+        return;
+    };
+    println!("⏪ Restoring {} tunable(s) recorded at {}", saved.tunables.len(), saved.saved_at);
+    if !dry_run && !sysfs::is_root() {
+        eprintln!("❌ Restoring tunables requires root. Re-run with sudo.");
+        return;
+    }
+    let mut failures = 0;
+    for s in &saved.tunables {
+        let knob = match (&s.path, &s.wifi_interface) {
+            (Some(path), _) => Knob::File(PathBuf::from(path)),
+            (None, Some(iface)) => Knob::WifiPowerSave(iface.clone()),
+            (None, None) => continue,
+        };
+        if dry_run {
+            println!("  Would set {} to {}", knob_label(&knob), s.value);
+            continue;
+        }
+        match write_knob(&knob, &s.value) {
+            Ok(()) => println!("  ✅ {} = {}", knob_label(&knob), s.value),
+            Err(e) => {
+                failures += 1;
+                eprintln!("  ❌ {} = {}: {}", knob_label(&knob), s.value, e);
+            }
+        }
+    }
+    if dry_run {
+        return;
+    }
+    if failures == 0 {
+        if let Err(e) = state::remove(STATE_FILE) {
+            eprintln!("⚠️ Failed to remove revert record: {}", e);
+        }
+    } else {
+        eprintln!("⚠️ Revert record kept; {} value(s) could not be restored.", failures);
+    }
+}
+
+fn confirm(prompt: &str) -> bool {
+    if !io::stdin().is_terminal() {
+        return false;
+    }
+    print!("{} [y/N]: ", prompt);
+    io::stdout().flush().ok();
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).is_ok() && input.trim().eq_ignore_ascii_case("y")
+}
+
+/// Entry point for `nephyra power tunables [--apply] [--restore] [--dry-run]`.
+pub fn run(args: &[String]) {
+    let dry_run = args.iter().any(|a| a == "--dry-run");
+    if args.iter().any(|a| a == "--restore") {
+        restore(dry_run);
+        return;
+    }
+
+    let on_battery = power_status::on_ac_power(&power_status::scan_power_supplies()) == Some(false);
+    println!("🔧 Power tunables ({})\n", if on_battery { "on battery" } else { "on AC power" });
+    let tunables = audit(on_battery);
+    if tunables.is_empty() {
+        println!("No tunable power knobs found.");
+        return;
+    }
+    for t in &tunables {
+        if t.is_good() {
+            println!("  Good  {} = {}", t.description, t.current);
+        } else {
+            println!("  Bad   {} = {} (recommended {})", t.description, t.current, t.recommended.as_deref().unwrap_or("?"));
+        }
+    }
+
+    let bad: Vec<&Tunable> = tunables.iter().filter(|t| !t.is_good()).collect();
+    println!("\n{} of {} tunables are Good.", tunables.len() - bad.len(), tunables.len());
+    if bad.is_empty() {
+        return;
+    }
+    if args.iter().any(|a| a == "--apply") || dry_run || confirm("\nApply the recommended values?") {
+        apply(&bad, dry_run);
+    } else {
+        println!("No tunables were changed. Use --apply to change them, --restore to revert later.");
+    }
+}
@@ -0,0 +1,926 @@
+// security_advisories.rs
+// Installed packages cross-referenced with the distribution's security advisories, cached for offline use
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::process::Command;
+use std::time::Duration;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::os_release;
+use super::package_manager::{PackageManager, Upgrade};
+use super::state;
+
+const CACHE_FILE: &str = "security_advisories.toml";
+const ARCH_SECURITY_JSON: &str = "https://security.archlinux.org/all.json";
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Unknown,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    /// Map the vocabularies of the different trackers (Red Hat "Important", SUSE "moderate",
+    /// Debian "high urgency") onto one scale.
+    pub fn parse(text: &str) -> Severity {
+        let lower = text.to_lowercase();
+        if lower.contains("critical") {
+            Severity::Critical
+        } else if lower.contains("high") || lower.contains("important") {
+            Severity::High
+        } else if lower.contains("medium") || lower.contains("moderate") {
+            Severity::Medium
+        } else if lower.contains("low") {
+            Severity::Low
+        } else {
+            Severity::Unknown
+        }
+    }
+
+    fn marker(self) -> &'static str {
+        match self {
+            Severity::Critical | Severity::High => "❗",
+            Severity::Medium => "⚠️",
+            Severity::Low | Severity::Unknown => "ℹ️",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Advisory {
+    pub package: String,
+    /// Tracker id (AVG, FEDORA-…, GLSA, SUSE patch), when the source has one.
+    pub id: Option<String>,
+    pub cves: Vec<String>,
+    pub severity: Severity,
+    /// A fix has been published by the distribution.
+    pub fixed: bool,
+    /// First fixed version, when the source says.
+    pub fixed_version: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct AdvisoryCache {
+    fetched_at: String,
+    source: String,
+    advisories: Vec<Advisory>,
+}
+
+/// Per-package view after matching against what is installed now.
+#[derive(Debug)]
+struct Finding {
+    version: String,
+    severity: Severity,
+    cves: Vec<String>,
+    fixed: bool,
+    fixed_version: Option<String>,
+    upgrade: Option<Upgrade>,
+}
+
+/// Version ordering of the distribution's package format.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VersionScheme {
+    /// pacman's vercmp: "1.0rc1" < "1.0", and pkgrel only counts when both sides have one.
+    Alpm,
+    /// dpkg: "~" sorts before the end of the string, letters before other punctuation.
+    Dpkg,
+    /// rpmvercmp: "~" sorts before and "^" after the base version, "1.0a" > "1.0".
+    Rpm,
+    /// Portage: numeric components, a letter, _alpha/_beta/_pre/_rc/_p suffixes and -rN.
+    Portage,
+}
+
+impl VersionScheme {
+    pub fn for_manager(name: &str) -> VersionScheme {
+        match name {
+            "pacman" => VersionScheme::Alpm,
+            "apt" => VersionScheme::Dpkg,
+            "emerge" => VersionScheme::Portage,
+            _ => VersionScheme::Rpm,
+        }
+    }
+
+    /// Compare two full package versions ("epoch:version-release" where the format has them).
+    pub fn compare(self, a: &str, b: &str) -> Ordering {
+        match self {
+            VersionScheme::Alpm => compare_evr(a, b, alpm_vercmp, false),
+            VersionScheme::Rpm => compare_evr(a, b, rpm_vercmp, false),
+            VersionScheme::Dpkg => compare_evr(a, b, dpkg_vercmp, true),
+            VersionScheme::Portage => portage_vercmp(a, b),
+        }
+    }
+}
+
+/// Split "epoch:version-release" into its parts; the release is whatever follows the last '-'.
+fn split_evr(version: &str) -> (u64, &str, Option<&str>) {
+    let (epoch, rest) = match version.split_once(':') {
+        Some((epoch, rest)) if !epoch.is_empty() && epoch.chars().all(|c| c.is_ascii_digit()) => (epoch.parse().unwrap_or(u64::MAX), rest),
+        _ => (0, version),
+    };
+    match rest.rsplit_once('-') {
+        Some((version, release)) => (epoch, version, Some(release)),
+        None => (epoch, rest, None),
+    }
+}
+
+/// Epoch, then version, then release. pacman and rpm skip the release when one side has none
+/// (an advisory's "fixed in 1.2" covers every 1.2 build); dpkg treats a missing revision as empty.
+fn compare_evr(a: &str, b: &str, vercmp: fn(&[u8], &[u8]) -> Ordering, missing_release_is_empty: bool) -> Ordering {
+    let (epoch_a, version_a, release_a) = split_evr(a);
+    let (epoch_b, version_b, release_b) = split_evr(b);
+    epoch_a.cmp(&epoch_b).then_with(|| vercmp(version_a.as_bytes(), version_b.as_bytes())).then_with(|| match (release_a, release_b) {
+        (Some(ra), Some(rb)) => vercmp(ra.as_bytes(), rb.as_bytes()),
+        _ if missing_release_is_empty => vercmp(release_a.unwrap_or_default().as_bytes(), release_b.unwrap_or_default().as_bytes()),
+        _ => Ordering::Equal,
+    })
+}
+
+/// Numbers compare by value (without leading zeros, so any length works), letters bytewise.
+fn compare_run(a: &[u8], b: &[u8], numeric: bool) -> Ordering {
+    if !numeric {
+        return a.cmp(b);
+    }
+    let zeros = |s: &[u8]| s.iter().take_while(|&&c| c == b'0').count();
+    let (a, b) = (&a[zeros(a)..], &b[zeros(b)..]);
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+/// End of the digit or letter run starting at `start`.
+fn run_end(s: &[u8], start: usize, numeric: bool) -> usize {
+    start + s[start..].iter().take_while(|c| if numeric { c.is_ascii_digit() } else { c.is_ascii_alphabetic() }).count()
+}
+
+/// libalpm's rpmvercmp variant: separator lengths matter and a trailing letter run loses.
+fn alpm_vercmp(a: &[u8], b: &[u8]) -> Ordering {
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        let (sep_i, sep_j) = (i, j);
+        while i < a.len() && !a[i].is_ascii_alphanumeric() {
+            i += 1;
+        }
+        while j < b.len() && !b[j].is_ascii_alphanumeric() {
+            j += 1;
+        }
+        if i == a.len() || j == b.len() {
+            break;
+        }
+        if i - sep_i != j - sep_j {
+            return (i - sep_i).cmp(&(j - sep_j));
+        }
+        let numeric = a[i].is_ascii_digit();
+        let (end_i, end_j) = (run_end(a, i, numeric), run_end(b, j, numeric));
+        if end_j == j {
+            // A number beats letters
+            return if numeric { Ordering::Greater } else { Ordering::Less };
+        }
+        let order = compare_run(&a[i..end_i], &b[j..end_j], numeric);
+        if order != Ordering::Equal {
+            return order;
+        }
+        (i, j) = (end_i, end_j);
+    }
+    match (a.get(i), b.get(j)) {
+        (None, None) => Ordering::Equal,
+        (None, Some(c)) if !c.is_ascii_alphabetic() => Ordering::Less,
+        (Some(c), _) if c.is_ascii_alphabetic() => Ordering::Less,
+        _ => Ordering::Greater,
+    }
+}
+
+/// rpm's rpmvercmp, including the "~" and "^" separators.
+fn rpm_vercmp(a: &[u8], b: &[u8]) -> Ordering {
+    let (mut i, mut j) = (0, 0);
+    let separator = |c: &u8| !c.is_ascii_alphanumeric() && *c != b'~' && *c != b'^';
+    loop {
+        while a.get(i).is_some_and(separator) {
+            i += 1;
+        }
+        while b.get(j).is_some_and(separator) {
+            j += 1;
+        }
+        let (ca, cb) = (a.get(i).copied(), b.get(j).copied());
+        if ca == Some(b'~') || cb == Some(b'~') {
+            if ca != Some(b'~') {
+                return Ordering::Greater;
+            }
+            if cb != Some(b'~') {
+                return Ordering::Less;
+            }
+            (i, j) = (i + 1, j + 1);
+            continue;
+        }
+        if ca == Some(b'^') || cb == Some(b'^') {
+            match (ca, cb) {
+                (None, _) => return Ordering::Less,
+                (_, None) => return Ordering::Greater,
+                (Some(b'^'), Some(b'^')) => {}
+                (_, Some(b'^')) => return Ordering::Greater,
+                _ => return Ordering::Less,
+            }
+            (i, j) = (i + 1, j + 1);
+            continue;
+        }
+        let (Some(ca), Some(_)) = (ca, cb) else {
+            break;
+        };
+        let numeric = ca.is_ascii_digit();
+        let (end_i, end_j) = (run_end(a, i, numeric), run_end(b, j, numeric));
+        if end_j == j {
+            return if numeric { Ordering::Greater } else { Ordering::Less };
+        }
+        let order = compare_run(&a[i..end_i], &b[j..end_j], numeric);
+        if order != Ordering::Equal {
+            return order;
+        }
+        (i, j) = (end_i, end_j);
+    }
+    // Whichever version still has characters left wins
+    (i < a.len()).cmp(&(j < b.len()))
+}
+
+/// dpkg's verrevcmp: alternating non-digit and digit runs; in the former "~" sorts first, then
+/// the end of the string, then letters, then everything else.
+fn dpkg_vercmp(a: &[u8], b: &[u8]) -> Ordering {
+    let order = |c: Option<&u8>| match c {
+        None => 0,
+        Some(c) if c.is_ascii_digit() => 0,
+        Some(c) if c.is_ascii_alphabetic() => *c as i32,
+        Some(b'~') => -1,
+        Some(c) => *c as i32 + 256,
+    };
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        while (i < a.len() && !a[i].is_ascii_digit()) || (j < b.len() && !b[j].is_ascii_digit()) {
+            let (ac, bc) = (order(a.get(i)), order(b.get(j)));
+            if ac != bc {
+                return ac.cmp(&bc);
+            }
+            (i, j) = (i + 1, j + 1);
+        }
+        let (end_i, end_j) = (run_end(a, i, true), run_end(b, j, true));
+        let order = compare_run(&a[i..end_i], &b[j..end_j], true);
+        if order != Ordering::Equal {
+            return order;
+        }
+        (i, j) = (end_i, end_j);
+    }
+    Ordering::Equal
+}
+
+/// Portage (PMS 3.3): "1.2.3a_rc1_p2-r1".
+fn portage_vercmp(a: &str, b: &str) -> Ordering {
+    fn split_revision(v: &str) -> (&str, &str) {
+        match v.rsplit_once("-r") {
+            Some((rest, rev)) if !rev.is_empty() && rev.chars().all(|c| c.is_ascii_digit()) => (rest, rev),
+            _ => (v, "0"),
+        }
+    }
+    fn suffix_rank(suffix: &str) -> (u8, &str) {
+        for (rank, name) in ["alpha", "beta", "pre", "rc", "p"].iter().enumerate() {
+            if let Some(number) = suffix.strip_prefix(name)
+                && number.chars().all(|c| c.is_ascii_digit())
+            {
+                return (rank as u8, number);
+            }
+        }
+        (0, suffix)
+    }
+    let (a, rev_a) = split_revision(a);
+    let (b, rev_b) = split_revision(b);
+    let mut parts_a = a.split('_');
+    let mut parts_b = b.split('_');
+    let (base_a, base_b) = (parts_a.next().unwrap_or_default(), parts_b.next().unwrap_or_default());
+    let letter = |base: &str| base.chars().last().filter(|c| c.is_ascii_alphabetic());
+    let (letter_a, letter_b) = (letter(base_a), letter(base_b));
+    let numbers_a: Vec<&str> = base_a.trim_end_matches(|c: char| c.is_ascii_alphabetic()).split('.').collect();
+    let numbers_b: Vec<&str> = base_b.trim_end_matches(|c: char| c.is_ascii_alphabetic()).split('.').collect();
+
+    for (index, (na, nb)) in numbers_a.iter().zip(&numbers_b).enumerate() {
+        // Later components with a leading zero compare as decimals ("1.02" < "1.1")
+        let order = if index > 0 && (na.starts_with('0') || nb.starts_with('0')) {
+            na.trim_end_matches('0').cmp(nb.trim_end_matches('0'))
+        } else {
+            compare_run(na.as_bytes(), nb.as_bytes(), true)
+        };
+        if order != Ordering::Equal {
+            return order;
+        }
+    }
+    let order = numbers_a.len().cmp(&numbers_b.len()).then(letter_a.cmp(&letter_b));
+    if order != Ordering::Equal {
+        return order;
+    }
+    loop {
+        match (parts_a.next().map(suffix_rank), parts_b.next().map(suffix_rank)) {
+            (None, None) => break,
+            // A trailing _p is newer than no suffix, anything else older
+            (Some((rank, _)), None) => return if rank == 4 { Ordering::Greater } else { Ordering::Less },
+            (None, Some((rank, _))) => return if rank == 4 { Ordering::Less } else { Ordering::Greater },
+            (Some((rank_a, num_a)), Some((rank_b, num_b))) => {
+                let order = rank_a.cmp(&rank_b).then_with(|| compare_run(num_a.as_bytes(), num_b.as_bytes(), true));
+                if order != Ordering::Equal {
+                    return order;
+                }
+            }
+        }
+    }
+    compare_run(rev_a.as_bytes(), rev_b.as_bytes(), true)
+}
+
+/// Every CVE id mentioned in `text`, in order and without duplicates.
+pub fn extract_cves(text: &str) -> Vec<String> {
+    let mut cves: Vec<String> = Vec::new();
+    for word in text.split(|c: char| !(c.is_ascii_alphanumeric() || c == '-')) {
+        if word.starts_with("CVE-") && word.len() > 8 && !cves.iter().any(|c| c == word) {
+            cves.push(word.to_string());
+        }
+    }
+    cves
+}
+
+/// Run an advisory tool; unlike package queries, a failing exit status means the data is
+/// unavailable (usually no network) and the cache should be used instead. Runs in the C locale
+/// like `package_manager::query`, so column names and severities stay parseable.
+fn run_tool(cmd: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(cmd).args(args).env("LC_ALL", "C").output().map_err(|e| format!("{}: {}", cmd, e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{}: {}", cmd, stderr.lines().next().unwrap_or("failed")));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// ---------------------------------------------------------------------------------------------
+// Sources
+
+/// `arch-audit --format '%n|%s|%v|%c'`: one affected package per line; empty fixed version when
+/// no fix has been released.
+pub fn parse_arch_audit(output: &str) -> Vec<Advisory> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let fields: Vec<&str> = l.splitn(4, '|').collect();
+            let [name, severity, fixed, cves] = fields.as_slice() else {
+                return None;
+            };
+            Some(Advisory {
+                package: name.to_string(),
+                id: None,
+                cves: extract_cves(cves),
+                severity: Severity::parse(severity),
+                fixed: !fixed.is_empty(),
+                fixed_version: Some(fixed.to_string()).filter(|f| !f.is_empty()),
+            })
+        })
+        .collect()
+}
+
+/// The Arch security tracker's all.json: AVGs with their packages, status and fixed version.
+pub fn parse_arch_security_json(json: &str) -> Vec<Advisory> {
+    let Ok(Value::Array(groups)) = serde_json::from_str::<Value>(json) else {
+        return Vec::new();
+    };
+    let mut advisories = Vec::new();
+    for group in &groups {
+        let status = group["status"].as_str().unwrap_or_default();
+        if status == "Not affected" {
+            continue;
+        }
+        let fixed_version = group["fixed"].as_str().map(|f| f.to_string());
+        let cves: Vec<String> = group["issues"].as_array().into_iter().flatten().filter_map(|i| i.as_str()).map(|i| i.to_string()).collect();
+        for package in group["packages"].as_array().into_iter().flatten().filter_map(|p| p.as_str()) {
+            advisories.push(Advisory {
+                package: package.to_string(),
+                id: group["name"].as_str().map(|n| n.to_string()),
+                cves: cves.clone(),
+                severity: Severity::parse(group["severity"].as_str().unwrap_or_default()),
+                fixed: fixed_version.is_some() || status == "Fixed",
+                fixed_version: fixed_version.clone(),
+            });
+        }
+    }
+    advisories
+}
+
+async fn download(url: &str) -> Result<String, String> {
+    let client = reqwest::Client::builder().timeout(FETCH_TIMEOUT).build().map_err(|e| e.to_string())?;
+    let response = client.get(url).send().await.map_err(|e| e.to_string())?;
+    let response = response.error_for_status().map_err(|e| e.to_string())?;
+    response.text().await.map_err(|e| e.to_string())
+}
+
+fn fetch_arch(installed: &HashMap<String, String>) -> Result<(String, Vec<Advisory>), String> {
+    if let Ok(output) = run_tool("arch-audit", &["--format", "%n|%s|%v|%c"]) {
+        return Ok(("arch-audit".to_string(), parse_arch_audit(&output)));
+    }
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().map_err(|e| e.to_string())?;
+    let json = runtime.block_on(download(ARCH_SECURITY_JSON))?;
+    // The tracker covers every package in the repositories; keep what is installed here
+    let advisories = parse_arch_security_json(&json).into_iter().filter(|a| installed.contains_key(&a.package)).collect();
+    Ok((ARCH_SECURITY_JSON.to_string(), advisories))
+}
+
+/// `debsecan --format summary`: "CVE-2023-0286 openssl (fixed, remotely exploitable, high urgency)".
+pub fn parse_debsecan(output: &str) -> Vec<Advisory> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let mut fields = l.split_whitespace();
+            let cve = fields.next().filter(|c| c.starts_with("CVE-") || c.starts_with("TEMP-"))?;
+            let package = fields.next()?;
+            let flags = l.split_once('(').map(|(_, f)| f.trim_end_matches(')')).unwrap_or_default();
+            Some(Advisory {
+                package: package.to_string(),
+                id: None,
+                cves: vec![cve.to_string()],
+                severity: Severity::parse(flags),
+                fixed: flags.split(", ").any(|f| f == "fixed"),
+                fixed_version: None,
+            })
+        })
+        .collect()
+}
+
+/// Split an rpm NEVRA ("openssl-libs-1:3.1.1-4.fc39.x86_64") into name and "epoch:version-release".
+pub fn split_nevra(nevra: &str) -> Option<(String, String)> {
+    let without_arch = nevra.rsplit_once('.').map(|(rest, _)| rest).unwrap_or(nevra);
+    let (rest, release) = without_arch.rsplit_once('-')?;
+    let (name, version) = rest.rsplit_once('-')?;
+    Some((name.to_string(), format!("{}-{}", version, release)))
+}
+
+/// `dnf updateinfo list --security --with-cve`. dnf4 prints "CVE-2024-0727 Moderate/Sec. openssl-libs-1:3.1.1-4.fc39.x86_64",
+/// dnf5 has Name, Type, Severity, Package and Issued columns: "CVE-2024-0727 security Moderate openssl-libs-… 2024-02-01 01:23:45".
+pub fn parse_dnf_updateinfo(output: &str) -> Vec<Advisory> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let fields: Vec<&str> = l.split_whitespace().collect();
+            let (id, severity, nevra) = match fields.as_slice() {
+                [id, severity, nevra] if severity.ends_with("Sec.") => (id, severity.trim_end_matches("/Sec."), nevra),
+                [id, "security", severity, nevra, ..] => (id, *severity, nevra),
+                _ => return None,
+            };
+            let (package, version) = split_nevra(nevra)?;
+            Some(Advisory {
+                package,
+                id: (!id.starts_with("CVE-")).then(|| id.to_string()),
+                cves: extract_cves(id),
+                severity: Severity::parse(severity),
+                fixed: true,
+                fixed_version: Some(version.strip_prefix("0:").unwrap_or(&version).to_string()),
+            })
+        })
+        .collect()
+}
+
+fn fetch_dnf() -> Result<(String, Vec<Advisory>), String> {
+    let output = run_tool("dnf", &["--quiet", "updateinfo", "list", "--security", "--with-cve"])?;
+    let advisories = parse_dnf_updateinfo(&output);
+    // An empty list is fine, rows in a layout we do not know are not
+    let rows = output.lines().filter(|l| !l.trim().is_empty() && !l.starts_with("Name ")).count();
+    if advisories.is_empty() && rows > 0 {
+        return Err(format!("unparsed output from dnf updateinfo ({} lines)", rows));
+    }
+    Ok(("dnf updateinfo".to_string(), advisories))
+}
+
+/// `zypper list-patches --cve`: "Issue | No. | Patch | Category | Severity | Interactive | Status | Summary".
+/// Patches cover several packages and zypper lists only those that apply to this system, so the
+/// patch id stands in for the package name.
+pub fn parse_zypper_patches(output: &str) -> Vec<Advisory> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let cols: Vec<&str> = l.split('|').map(str::trim).collect();
+            if cols.len() < 8 || cols[3] != "security" || cols[2].is_empty() {
+                return None;
+            }
+            Some(Advisory {
+                package: cols[2].to_string(),
+                id: None,
+                cves: extract_cves(cols[1]),
+                severity: Severity::parse(cols[4]),
+                fixed: true,
+                fixed_version: None,
+            })
+        })
+        .collect()
+}
+
+/// `glsa-check --list affected`: "202401-01 [N] OpenSSL: Multiple vulnerabilities ( dev-libs/openssl )".
+pub fn parse_glsa_list(output: &str) -> Vec<(String, Vec<String>)> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let id = l.split_whitespace().next().filter(|id| id.contains('-') && id.starts_with(|c: char| c.is_ascii_digit()))?;
+            let packages = l.rsplit_once('(')?.1.trim_end_matches(')').split_whitespace().map(|p| p.to_string()).collect();
+            Some((id.to_string(), packages))
+        })
+        .collect()
+}
+
+/// `glsa-check --dump <id>`: CVE references, severity and the first unaffected version.
+pub fn parse_glsa_dump(id: &str, packages: &[String], dump: &str) -> Vec<Advisory> {
+    let field = |name: &str| dump.lines().find_map(|l| l.trim().strip_prefix(name).map(|v| v.trim().to_string()));
+    // "Unaffected:  >=3.0.12": keep the version, not the range operator
+    let fixed_version = field("Unaffected:")
+        .and_then(|u| u.split_whitespace().last().map(|v| v.trim_start_matches(['>', '<', '=', '~']).to_string()))
+        .filter(|v| !v.is_empty());
+    packages
+        .iter()
+        .map(|package| Advisory {
+            package: package.clone(),
+            id: Some(format!("GLSA {}", id)),
+            cves: extract_cves(dump),
+            severity: Severity::parse(&field("Severity:").unwrap_or_default()),
+            fixed: fixed_version.is_some(),
+            fixed_version: fixed_version.clone(),
+        })
+        .collect()
+}
+
+fn fetch_glsa() -> Result<(String, Vec<Advisory>), String> {
+    let list = run_tool("glsa-check", &["--list", "affected"])?;
+    let affected = parse_glsa_list(&list);
+    let mut advisories = Vec::new();
+    let mut failed = 0;
+    for (id, packages) in &affected {
+        // One unreadable GLSA should not cost the others
+        match run_tool("glsa-check", &["--dump", id]) {
+            Ok(dump) => advisories.extend(parse_glsa_dump(id, packages, &dump)),
+            Err(e) => {
+                eprintln!("⚠️ Skipping GLSA {}: {}", id, e);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 && failed == affected.len() {
+        return Err("glsa-check --dump failed for every affected GLSA".to_string());
+    }
+    Ok(("glsa-check".to_string(), advisories))
+}
+
+/// Query the advisory source for this package manager: (source description, advisories).
+fn fetch(pm: &dyn PackageManager, installed: &HashMap<String, String>) -> Result<(String, Vec<Advisory>), String> {
+    match pm.name() {
+        "pacman" => fetch_arch(installed),
+        "apt" => {
+            let codename = os_release::read().version_codename;
+            let mut args = vec!["--format", "summary"];
+            if !codename.is_empty() {
+                // Without a suite debsecan cannot tell whether a fix is available
+                args.extend(["--suite", codename.as_str()]);
+            }
+            Ok(("debsecan".to_string(), parse_debsecan(&run_tool("debsecan", &args)?)))
+        }
+        "dnf" => fetch_dnf(),
+        "zypper" => Ok(("zypper list-patches".to_string(), parse_zypper_patches(&run_tool("zypper", &["--quiet", "list-patches", "--cve"])?))),
+        "emerge" => fetch_glsa(),
+        other => Err(format!("no advisory source for {}", other)),
+    }
+}
+
+/// Match advisories against the installed versions: drop packages that are not installed or
+/// already at the fixed version, merge the rest per package. `patches` advisories are keyed by
+/// patch id and already limited to this system, so they are kept as they are.
+fn match_installed(advisories: &[Advisory], installed: &HashMap<String, String>, upgrades: &[Upgrade], scheme: VersionScheme, patches: bool) -> BTreeMap<String, Finding> {
+    let mut findings: BTreeMap<String, Finding> = BTreeMap::new();
+    let no_version = String::new();
+    for advisory in advisories {
+        let version = match installed.get(&advisory.package) {
+            Some(version) => version,
+            None if patches => &no_version,
+            None => continue,
+        };
+        if let Some(fixed) = &advisory.fixed_version
+            && scheme.compare(version, fixed) != Ordering::Less
+        {
+            continue;
+        }
+        let finding = findings.entry(advisory.package.clone()).or_insert_with(|| Finding {
+            version: version.clone(),
+            severity: Severity::Unknown,
+            cves: Vec::new(),
+            fixed: true,
+            fixed_version: None,
+            upgrade: upgrades.iter().find(|u| u.name == advisory.package).cloned(),
+        });
+        finding.severity = finding.severity.max(advisory.severity);
+        // A package counts as fixed only once every advisory against it is
+        finding.fixed &= advisory.fixed;
+        if let Some(fixed) = &advisory.fixed_version
+            && finding.fixed_version.as_ref().is_none_or(|f| scheme.compare(fixed, f) == Ordering::Greater)
+        {
+            finding.fixed_version = Some(fixed.clone());
+        }
+        let ids = advisory.cves.iter().cloned().chain(advisory.id.clone().filter(|_| advisory.cves.is_empty()));
+        for id in ids {
+            if !finding.cves.contains(&id) {
+                finding.cves.push(id);
+            }
+        }
+    }
+    findings
+}
+
+/// Print security advisories affecting installed packages. Fresh data is fetched unless
+/// `offline` is set; the cached advisory file is used when fetching is not possible.
+pub fn report(pm: &dyn PackageManager, offline: bool) {
+    println!("\n🛡️ Security advisories");
+    let installed: HashMap<String, String> = pm.installed().into_iter().map(|p| (p.name, p.version)).collect();
+    let fetched = if offline { Err("offline mode".to_string()) } else { fetch(pm, &installed) };
+    let cache = match fetched {
+        Ok((source, advisories)) => {
+            let cache = AdvisoryCache { fetched_at: Local::now().format("%Y-%m-%d %H:%M").to_string(), source, advisories };
+            if let Err(e) = state::save(CACHE_FILE, &cache) {
+                eprintln!("⚠️ Failed to cache advisories: {}", e);
+            }
+            cache
+        }
+        Err(reason) => match state::load::<AdvisoryCache>(CACHE_FILE) {
+            Some(cache) => {
+                println!("ℹ️ Using cached advisories from {} ({}).", cache.fetched_at, reason);
+                cache
+            }
+            None => {
+                println!("ℹ️ No advisory data available ({}) and no cached copy yet.", reason);
+                match pm.name() {
+                    "pacman" => println!("💡 Install arch-audit, or connect to the network once to build the cache."),
+                    "apt" => println!("💡 Install debsecan to match packages against the Debian security tracker."),
+                    "emerge" => println!("💡 Install app-portage/gentoolkit for glsa-check."),
+                    _ => {}
+                }
+                return;
+            }
+        },
+    };
+    println!("Source: {} (fetched {})", cache.source, cache.fetched_at);
+
+    let upgrades = pm.upgradable();
+    let patches = pm.name() == "zypper";
+    let findings = match_installed(&cache.advisories, &installed, &upgrades, VersionScheme::for_manager(pm.name()), patches);
+    if findings.is_empty() {
+        println!("✅ No known vulnerabilities in installed packages.");
+        return;
+    }
+    let mut sorted: Vec<(&String, &Finding)> = findings.iter().collect();
+    sorted.sort_by(|a, b| b.1.severity.cmp(&a.1.severity).then_with(|| a.0.cmp(b.0)));
+    for (package, finding) in &sorted {
+        println!("{} {} — {:?}", finding.severity.marker(), format!("{} {}", package, finding.version).trim_end(), finding.severity);
+        if !finding.cves.is_empty() {
+            println!("   {}", finding.cves.join(", "));
+        }
+        match (&finding.upgrade, finding.fixed) {
+            _ if patches => println!("   ✅ Patch available: sudo zypper patch --category security"),
+            (Some(upgrade), true) => println!("   ✅ Fixed in {}: update available", finding.fixed_version.as_deref().unwrap_or(&upgrade.available)),
+            (_, true) => println!(
+                "   ✅ Fixed{}; sync the package database to get the update",
+                finding.fixed_version.as_ref().map(|v| format!(" in {}", v)).unwrap_or_default()
+            ),
+            (_, false) => println!("   ❌ Unfixed: no update published yet"),
+        }
+    }
+    let urgent = sorted.iter().filter(|(_, f)| f.fixed && f.severity >= Severity::High).count();
+    let unfixed = sorted.iter().filter(|(_, f)| !f.fixed).count();
+    println!("\n{} vulnerable package(s): {} with high/critical fixes to install, {} without a fix.", sorted.len(), urgent, unfixed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zypper_patches_keyed_by_patch() {
+        let output = "\
+Issue | No.           | Patch                       | Category    | Severity  | Interactive | Status | Summary
+------+---------------+-----------------------------+-------------+-----------+-------------+--------+--------------------------------------
+cve   | CVE-2024-0727 | openSUSE-SLE-15.5-2024-1234 | security    | moderate  | ---         | needed | Security update for openssl-1_1
+cve   | CVE-2024-2511 | openSUSE-SLE-15.5-2024-1234 | security    | moderate  | ---         | needed | Security update for openssl-1_1
+cve   | CVE-2024-1234 | openSUSE-SLE-15.5-2024-2000 | recommended | low       | ---         | needed | Recommended update for the kernel
+";
+        let advisories = parse_zypper_patches(output);
+        assert_eq!(advisories.len(), 2);
+        assert_eq!(advisories[0].package, "openSUSE-SLE-15.5-2024-1234");
+        assert_eq!(advisories[0].cves, ["CVE-2024-0727"]);
+
+        let findings = match_installed(&advisories, &HashMap::new(), &[], VersionScheme::Rpm, true);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings["openSUSE-SLE-15.5-2024-1234"].cves, ["CVE-2024-0727", "CVE-2024-2511"]);
+        assert!(match_installed(&advisories, &HashMap::new(), &[], VersionScheme::Rpm, false).is_empty());
+    }
+
+    #[test]
+    fn dnf_updateinfo_layouts() {
+        let dnf4 = "CVE-2024-0727 Moderate/Sec. openssl-libs-1:3.1.1-4.fc39.x86_64\nFEDORA-2024-1a2b3c Important/Sec. kernel-6.7.4-200.fc39.x86_64\n";
+        let dnf5 = "\
+Name          Type     Severity  Package                            Issued
+CVE-2024-0727 security Moderate  openssl-libs-1:3.1.1-4.fc39.x86_64 2024-02-01 01:23:45
+FEDORA-2024-1a2b3c security Important kernel-6.7.4-200.fc39.x86_64 2024-02-06 02:00:00
+";
+        for output in [dnf4, dnf5] {
+            let advisories = parse_dnf_updateinfo(output);
+            assert_eq!(advisories.len(), 2);
+            assert_eq!(advisories[0].package, "openssl-libs");
+            assert_eq!(advisories[0].cves, ["CVE-2024-0727"]);
+            assert_eq!(advisories[0].severity, Severity::Medium);
+            assert_eq!(advisories[0].fixed_version.as_deref(), Some("1:3.1.1-4.fc39"));
+            assert_eq!(advisories[1].package, "kernel");
+            assert_eq!(advisories[1].id.as_deref(), Some("FEDORA-2024-1a2b3c"));
+            assert_eq!(advisories[1].fixed_version.as_deref(), Some("6.7.4-200.fc39"));
+        }
+    }
+
+    #[test]
+    fn arch_audit_lines() {
+        let output = "\
+openssl|High|3.1.4-1|CVE-2023-5363,CVE-2023-5678
+libtiff|Medium||CVE-2023-6228
+not a record
+";
+        let advisories = parse_arch_audit(output);
+        assert_eq!(advisories.len(), 2);
+        assert_eq!(advisories[0].package, "openssl");
+        assert_eq!(advisories[0].severity, Severity::High);
+        assert_eq!(advisories[0].cves, ["CVE-2023-5363", "CVE-2023-5678"]);
+        assert!(advisories[0].fixed);
+        assert_eq!(advisories[0].fixed_version.as_deref(), Some("3.1.4-1"));
+        assert!(!advisories[1].fixed);
+        assert_eq!(advisories[1].fixed_version, None);
+
+        let installed = HashMap::from([("openssl".to_string(), "3.1.4-1".to_string()), ("libtiff".to_string(), "4.6.0-1".to_string())]);
+        let findings = match_installed(&advisories, &installed, &[], VersionScheme::Alpm, false);
+        assert_eq!(findings.keys().collect::<Vec<_>>(), ["libtiff"], "openssl is already at the fixed version");
+    }
+
+    #[test]
+    fn arch_security_tracker_json() {
+        let json = r#"[
+            {"name": "AVG-2843", "packages": ["openssl", "lib32-openssl"], "status": "Fixed", "severity": "High",
+             "type": "denial of service", "affected": "3.1.3-1", "fixed": "3.1.4-1", "issues": ["CVE-2023-5363"]},
+            {"name": "AVG-2850", "packages": ["libtiff"], "status": "Vulnerable", "severity": "Medium",
+             "type": "arbitrary code execution", "affected": "4.6.0-1", "fixed": null, "issues": ["CVE-2023-6228", "CVE-2023-6277"]},
+            {"name": "AVG-2100", "packages": ["curl"], "status": "Not affected", "severity": "Low",
+             "type": "unknown", "affected": "7.0-1", "fixed": null, "issues": ["CVE-2021-0001"]}
+        ]"#;
+        let advisories = parse_arch_security_json(json);
+        assert_eq!(advisories.len(), 3);
+        assert_eq!(advisories[1].package, "lib32-openssl");
+        assert_eq!(advisories[1].id.as_deref(), Some("AVG-2843"));
+        assert_eq!(advisories[1].fixed_version.as_deref(), Some("3.1.4-1"));
+        assert_eq!(advisories[2].package, "libtiff");
+        assert_eq!(advisories[2].severity, Severity::Medium);
+        assert_eq!(advisories[2].cves, ["CVE-2023-6228", "CVE-2023-6277"]);
+        assert!(!advisories[2].fixed);
+        assert!(parse_arch_security_json("<html>502</html>").is_empty());
+    }
+
+    #[test]
+    fn debsecan_summary() {
+        let output = "\
+CVE-2023-0286 openssl (fixed, remotely exploitable, high urgency)
+CVE-2023-4039 gcc-12 (low urgency)
+TEMP-0841856-B18BAF bash
+";
+        let advisories = parse_debsecan(output);
+        assert_eq!(advisories.len(), 3);
+        assert_eq!(advisories[0].package, "openssl");
+        assert_eq!(advisories[0].cves, ["CVE-2023-0286"]);
+        assert_eq!(advisories[0].severity, Severity::High);
+        assert!(advisories[0].fixed);
+        assert_eq!(advisories[1].severity, Severity::Low);
+        assert!(!advisories[1].fixed);
+        assert_eq!(advisories[2].package, "bash");
+        assert_eq!(advisories[2].severity, Severity::Unknown);
+    }
+
+    #[test]
+    fn glsa_list_and_dump() {
+        let list = "\
+202401-01 [N] OpenSSL: Multiple vulnerabilities ( dev-libs/openssl )
+202402-05 [A] GNU C Library: Multiple vulnerabilities ( sys-libs/glibc cross-x86_64-linux-gnu/glibc )
+This system is affected by the following GLSAs:
+";
+        let affected = parse_glsa_list(list);
+        assert_eq!(affected.len(), 2);
+        assert_eq!(affected[0], ("202401-01".to_string(), vec!["dev-libs/openssl".to_string()]));
+        assert_eq!(affected[1].1, ["sys-libs/glibc", "cross-x86_64-linux-gnu/glibc"]);
+
+        let dump = "\
+                    GLSA 202401-01: OpenSSL: Multiple vulnerabilities
+============================================================================
+Synopsis:          Multiple vulnerabilities have been discovered in OpenSSL.
+Announced:         2024-01-05
+Last Revised:      2024-01-05
+Severity:          high
+Exploitable:       remote
+Affected packages:
+-------------------
+ Package:           dev-libs/openssl
+ Vulnerable:        <3.0.12
+ Unaffected:        >=3.0.12
+References:
+     CVE-2023-2650 : https://nvd.nist.gov/vuln/detail/CVE-2023-2650
+     CVE-2023-2975 : https://nvd.nist.gov/vuln/detail/CVE-2023-2975
+";
+        let advisories = parse_glsa_dump("202401-01", &affected[0].1, dump);
+        assert_eq!(advisories.len(), 1);
+        assert_eq!(advisories[0].package, "dev-libs/openssl");
+        assert_eq!(advisories[0].id.as_deref(), Some("GLSA 202401-01"));
+        assert_eq!(advisories[0].severity, Severity::High);
+        assert_eq!(advisories[0].cves, ["CVE-2023-2650", "CVE-2023-2975"]);
+        assert_eq!(advisories[0].fixed_version.as_deref(), Some("3.0.12"));
+    }
+
+    #[test]
+    fn cves_in_free_text() {
+        assert_eq!(
+            extract_cves("Fixes CVE-2024-0727, CVE-2024-2511 (see CVE-2024-0727); not CVE- or CVE-12"),
+            ["CVE-2024-0727", "CVE-2024-2511"]
+        );
+        assert!(extract_cves("no identifiers here").is_empty());
+    }
+
+    fn assert_order(scheme: VersionScheme, pairs: &[(&str, &str, Ordering)]) {
+        for (a, b, expected) in pairs {
+            assert_eq!(scheme.compare(a, b), *expected, "{:?}: {} vs {}", scheme, a, b);
+            assert_eq!(scheme.compare(b, a), expected.reverse(), "{:?}: {} vs {}", scheme, b, a);
+        }
+    }
+
+    #[test]
+    fn alpm_versions() {
+        use Ordering::*;
+        assert_order(VersionScheme::Alpm, &[
+            ("1.0rc1", "1.0", Less),
+            ("1.0a", "1.0", Less),
+            ("1.0", "1.0.1", Less),
+            ("1.0-2", "1.0.1-1", Less),
+            ("1.0-1", "1.0-2", Less),
+            ("1.0-7", "1.0", Equal),
+            ("1:1.0-1", "2.0-1", Greater),
+            ("6.9.7.arch1-1", "6.9.8.arch1-1", Less),
+            ("1.0.0", "1.0..0", Less),
+            ("1.001", "1.1", Equal),
+        ]);
+    }
+
+    #[test]
+    fn dpkg_versions() {
+        use Ordering::*;
+        assert_order(VersionScheme::Dpkg, &[
+            ("1.0~rc1", "1.0", Less),
+            ("1.0~rc1", "1.0~rc1~1", Greater),
+            ("1.0", "1.0+b1", Less),
+            ("1.0a", "1.0", Greater),
+            ("1.0a", "1.0+", Less),
+            ("3.0.11-1~deb12u2", "3.0.11-1", Less),
+            ("3.0.11-1", "3.0.11-1+deb12u1", Less),
+            ("1.0-2", "1.0.1-1", Less),
+            ("2:1.0", "1:9.9", Greater),
+            ("1.2.13.dfsg-1", "1.2.13.dfsg-1", Equal),
+        ]);
+    }
+
+    #[test]
+    fn rpm_versions() {
+        use Ordering::*;
+        assert_order(VersionScheme::Rpm, &[
+            ("1.0~rc1", "1.0", Less),
+            ("1.0^git1", "1.0", Greater),
+            ("1.0^git1", "1.0.1", Less),
+            ("1.0^git1", "1.0^git2", Less),
+            ("1.0a", "1.0", Greater),
+            ("1.0-2.fc40", "1.0.1-1.fc40", Less),
+            ("3.1.1-4.fc39", "3.1.1", Equal),
+            ("1:3.1.1-4.fc39", "3.2.0-1.fc40", Greater),
+            ("1.01", "1.1", Equal),
+            ("1.0_1", "1.0.1", Equal),
+        ]);
+    }
+
+    #[test]
+    fn portage_versions() {
+        use Ordering::*;
+        assert_order(VersionScheme::Portage, &[
+            ("3.0.13", "3.0.13-r1", Less),
+            ("1.0_rc1", "1.0", Less),
+            ("1.0_alpha", "1.0_beta2", Less),
+            ("1.0", "1.0_p1", Less),
+            ("1.0a", "1.0", Greater),
+            ("1.0a", "1.0.1", Less),
+            ("1.02", "1.1", Less),
+            ("1.10", "1.9", Greater),
+            ("1.0_rc1_p2", "1.0_rc1", Greater),
+            ("20240101", "20231231", Greater),
+        ]);
+    }
+}
@@ -0,0 +1,351 @@
+// sensors.rs
+// Thermal zones, hwmon sensors (temp/fan/in/power/curr) and CPU throttling counters
+
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use super::sysfs;
+
+const THERMAL_DIR: &str = "/sys/class/thermal";
+const HWMON_DIR: &str = "/sys/class/hwmon";
+const CPU_DIR: &str = "/sys/devices/system/cpu";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SensorKind {
+    Temperature,
+    Fan,
+    Voltage,
+    Power,
+    Current,
+}
+
+impl SensorKind {
+    /// sysfs file prefix, unit suffix and divisor turning the raw value into that unit.
+    fn spec(self) -> (&'static str, &'static str, f64) {
+        match self {
+            SensorKind::Temperature => ("temp", "°C", 1000.0),
+            SensorKind::Fan => ("fan", "RPM", 1.0),
+            SensorKind::Voltage => ("in", "V", 1000.0),
+            SensorKind::Power => ("power", "W", 1_000_000.0),
+            SensorKind::Current => ("curr", "A", 1000.0),
+        }
+    }
+
+    fn unit(self) -> &'static str {
+        self.spec().1
+    }
+}
+
+const SENSOR_KINDS: [SensorKind; 5] = [
+    SensorKind::Temperature,
+    SensorKind::Fan,
+    SensorKind::Voltage,
+    SensorKind::Power,
+    SensorKind::Current,
+];
+
+#[derive(Debug, Clone)]
+pub struct SensorReading {
+    pub kind: SensorKind,
+    pub label: String,
+    pub value: f64,
+    pub max: Option<f64>,
+    pub crit: Option<f64>,
+}
+
+impl SensorReading {
+    fn is_alarming(&self) -> bool {
+        self.crit.is_some_and(|c| c > 0.0 && self.value >= c) || self.max.is_some_and(|m| m > 0.0 && self.value >= m)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HwmonDevice {
+    pub name: String,
+    pub readings: Vec<SensorReading>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ThermalZone {
+    pub name: String,
+    pub kind: String,
+    pub temp_c: f64,
+    pub crit_c: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ThrottleCounters {
+    pub core_events: u64,
+    pub package_events: u64,
+}
+
+/// The temperatures people usually care about, picked out of the hwmon devices.
+#[derive(Debug, Clone, Default)]
+pub struct KeyTemperatures {
+    pub cpu_package: Option<f64>,
+    pub gpu: Vec<(String, f64)>,
+    pub nvme: Vec<(String, f64)>,
+    pub chipset: Option<f64>,
+}
+
+fn read_hwmon_channel(dir: &Path, kind: SensorKind, index: u32) -> Option<SensorReading> {
+    let (prefix, _, divisor) = kind.spec();
+    let base = format!("{}{}", prefix, index);
+    // Power sensors may only expose a running average.
+    let raw = sysfs::read_i64(dir.join(format!("{}_input", base)))
+        .or_else(|| if kind == SensorKind::Power { sysfs::read_i64(dir.join(format!("{}_average", base))) } else { None })?;
+    let scaled = |suffix: &str| sysfs::read_i64(dir.join(format!("{}_{}", base, suffix))).map(|v| v as f64 / divisor);
+    Some(SensorReading {
+        kind,
+        label: sysfs::read_trimmed(dir.join(format!("{}_label", base))).unwrap_or(base.clone()),
+        value: raw as f64 / divisor,
+        max: scaled("max"),
+        crit: scaled("crit"),
+    })
+}
+
+fn read_hwmon_device(dir: &Path) -> HwmonDevice {
+    let name = sysfs::read_trimmed(dir.join("name")).unwrap_or_else(|| "unknown".to_string());
+    let mut readings = Vec::new();
+    for kind in SENSOR_KINDS {
+        let (prefix, _, _) = kind.spec();
+        // Collect channel indices from files like temp1_input, fan2_input, power1_average.
+        let mut indices: Vec<u32> = std::fs::read_dir(dir)
+            .map(|rd| {
+                rd.flatten()
+                    .filter_map(|e| {
+                        let file = e.file_name().to_string_lossy().to_string();
+                        let rest = file.strip_prefix(prefix)?;
+                        let (index, suffix) = rest.split_once('_')?;
+                        if suffix == "input" || (kind == SensorKind::Power && suffix == "average") {
+                            index.parse().ok()
+                        } else {
+                            None
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        indices.sort_unstable();
+        indices.dedup();
+        readings.extend(indices.iter().filter_map(|&i| read_hwmon_channel(dir, kind, i)));
+    }
+    HwmonDevice { name, readings }
+}
+
+pub fn read_hwmon() -> Vec<HwmonDevice> {
+    sysfs::list_dir_prefixed(HWMON_DIR, "hwmon").iter().map(|d| read_hwmon_device(d)).collect()
+}
+
+pub fn read_thermal_zones() -> Vec<ThermalZone> {
+    sysfs::list_dir_prefixed(THERMAL_DIR, "thermal_zone")
+        .iter()
+        .filter_map(|dir| {
+            let temp_c = sysfs::read_i64(dir.join("temp"))? as f64 / 1000.0;
+            // The critical trip point is one of trip_point_N_type == "critical".
+            let crit_c = (0..16).find_map(|i| {
+                let kind = sysfs::read_trimmed(dir.join(format!("trip_point_{}_type", i)))?;
+                if kind == "critical" {
+                    sysfs::read_i64(dir.join(format!("trip_point_{}_temp", i))).map(|t| t as f64 / 1000.0)
+                } else {
+                    None
+                }
+            });
+            Some(ThermalZone {
+                name: dir.file_name()?.to_string_lossy().to_string(),
+                kind: sysfs::read_trimmed(dir.join("type")).unwrap_or_default(),
+                temp_c,
+                crit_c,
+            })
+        })
+        .collect()
+}
+
+/// Keep the highest value seen for `key`.
+fn record_max<K: PartialEq>(maxima: &mut Vec<(K, u64)>, key: K, count: u64) {
+    match maxima.iter_mut().find(|(k, _)| *k == key) {
+        Some((_, max)) => *max = (*max).max(count),
+        None => maxima.push((key, count)),
+    }
+}
+
+/// Sum the thermal_throttle counters over all CPUs. Core counters are shared by the SMT siblings
+/// of a core and package counters by every core of a package, so only the highest value per core
+/// and per package is counted.
+pub fn read_throttle_counters() -> ThrottleCounters {
+    let mut core_max: Vec<((String, String), u64)> = Vec::new();
+    let mut package_max: Vec<(String, u64)> = Vec::new();
+    for cpu in sysfs::list_dir_prefixed(CPU_DIR, "cpu") {
+        let throttle = cpu.join("thermal_throttle");
+        if !throttle.is_dir() {
+            continue;
+        }
+        let package_id = sysfs::read_trimmed(cpu.join("topology/physical_package_id")).unwrap_or_default();
+        let core_id = sysfs::read_trimmed(cpu.join("topology/core_id")).unwrap_or_default();
+        let core_count = sysfs::read_u64(throttle.join("core_throttle_count")).unwrap_or(0);
+        record_max(&mut core_max, (package_id.clone(), core_id), core_count);
+        let package_count = sysfs::read_u64(throttle.join("package_throttle_count")).unwrap_or(0);
+        record_max(&mut package_max, package_id, package_count);
+    }
+    ThrottleCounters {
+        core_events: core_max.iter().map(|(_, c)| c).sum(),
+        package_events: package_max.iter().map(|(_, c)| c).sum(),
+    }
+}
+
+fn first_temp(device: &HwmonDevice, labels: &[&str]) -> Option<f64> {
+    let temps = device.readings.iter().filter(|r| r.kind == SensorKind::Temperature);
+    for label in labels {
+        if let Some(r) = temps.clone().find(|r| r.label.starts_with(label)) {
+            return Some(r.value);
+        }
+    }
+    temps.clone().next().map(|r| r.value)
+}
+
+/// NVIDIA's proprietary driver does not register a hwmon device, so ask nvidia-smi.
+fn nvidia_smi_temps() -> Vec<(String, f64)> {
+    let Ok(output) = Command::new("nvidia-smi")
+        .args(["--query-gpu=name,temperature.gpu", "--format=csv,noheader,nounits"])
+        .output()
+    else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (name, temp) = line.rsplit_once(',')?;
+            Some((name.trim().to_string(), temp.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+pub fn key_temperatures(devices: &[HwmonDevice]) -> KeyTemperatures {
+    let mut key = KeyTemperatures::default();
+    for dev in devices {
+        match dev.name.as_str() {
+            "coretemp" => key.cpu_package = key.cpu_package.or(first_temp(dev, &["Package id"])),
+            "k10temp" | "zenpower" => key.cpu_package = key.cpu_package.or(first_temp(dev, &["Tctl", "Tdie"])),
+            "amdgpu" | "radeon" | "nouveau" | "i915" | "xe" => {
+                if let Some(t) = first_temp(dev, &["edge", "junction"]) {
+                    key.gpu.push((dev.name.clone(), t));
+                }
+            }
+            "nvme" => {
+                if let Some(t) = first_temp(dev, &["Composite"]) {
+                    key.nvme.push((dev.name.clone(), t));
+                }
+            }
+            name if name.starts_with("pch_") => key.chipset = key.chipset.or(first_temp(dev, &[])),
+            _ => {}
+        }
+    }
+    key.gpu.extend(nvidia_smi_temps());
+    key
+}
+
+fn format_reading(r: &SensorReading) -> String {
+    let unit = r.kind.unit();
+    let precision = if r.kind == SensorKind::Fan { 0 } else { 1 };
+    let mut s = format!("{:.*} {}", precision, r.value, unit);
+    let mut limits = Vec::new();
+    if let Some(max) = r.max.filter(|&m| m > 0.0) {
+        limits.push(format!("max {:.*} {}", precision, max, unit));
+    }
+    if let Some(crit) = r.crit.filter(|&c| c > 0.0) {
+        limits.push(format!("crit {:.*} {}", precision, crit, unit));
+    }
+    if !limits.is_empty() {
+        s.push_str(&format!(" ({})", limits.join(", ")));
+    }
+    s
+}
+
+fn format_key_temperatures(key: &KeyTemperatures) -> Vec<String> {
+    let mut parts = Vec::new();
+    if let Some(t) = key.cpu_package {
+        parts.push(format!("CPU {:.1}°C", t));
+    }
+    for (name, t) in &key.gpu {
+        parts.push(format!("GPU ({}) {:.1}°C", name, t));
+    }
+    for (i, (_, t)) in key.nvme.iter().enumerate() {
+        parts.push(format!("NVMe{} {:.1}°C", i, t));
+    }
+    if let Some(t) = key.chipset {
+        parts.push(format!("Chipset {:.1}°C", t));
+    }
+    parts
+}
+
+fn watch(interval_secs: u64) {
+    println!("🌡️ Watching temperatures every {}s (Ctrl+C to stop)", interval_secs);
+    loop {
+        let key = key_temperatures(&read_hwmon());
+        let throttle = read_throttle_counters();
+        let time = chrono::Local::now().format("%H:%M:%S");
+        println!(
+            "[{}] {} | throttle core/pkg: {}/{}",
+            time,
+            format_key_temperatures(&key).join(" | "),
+            throttle.core_events,
+            throttle.package_events
+        );
+        thread::sleep(Duration::from_secs(interval_secs));
+    }
+}
+
+pub fn run(args: &[String]) {
+    if let Some(pos) = args.iter().position(|a| a == "--watch") {
+        let interval = args.get(pos + 1).and_then(|s| s.parse().ok()).unwrap_or(2).max(1);
+        watch(interval);
+        return;
+    }
+
+    println!("🌡️ Nephyra: Sensors\n");
+    let devices = read_hwmon();
+    let key = key_temperatures(&devices);
+    let key_parts = format_key_temperatures(&key);
+    if key_parts.is_empty() {
+        println!("Key temperatures: Not detected");
+    } else {
+        println!("Key temperatures: {}", key_parts.join(" | "));
+    }
+
+    for dev in &devices {
+        if dev.readings.is_empty() {
+            continue;
+        }
+        println!("\n{}:", dev.name);
+        for r in &dev.readings {
+            let flag = if r.is_alarming() { " ⚠️" } else { "" };
+            println!("  {:<16} {}{}", r.label, format_reading(r), flag);
+        }
+    }
+
+    let zones = read_thermal_zones();
+    if !zones.is_empty() {
+        println!("\nThermal zones:");
+        for z in &zones {
+            let crit = z.crit_c.map(|c| format!(" (crit {:.1}°C)", c)).unwrap_or_default();
+            let flag = if z.crit_c.is_some_and(|c| z.temp_c >= c) { " ⚠️" } else { "" };
+            println!("  {} [{}]: {:.1}°C{}{}", z.name, z.kind, z.temp_c, crit, flag);
+        }
+    }
+
+    let throttle = read_throttle_counters();
+    println!("\nThermal throttling events: core {}, package {}", throttle.core_events, throttle.package_events);
+    if throttle.package_events > 0 || throttle.core_events > 0 {
+        println!("⚠️ The CPU has been thermally throttled since boot. Check cooling, fan curves and power profile.");
+    }
+}
+
+pub fn get_summary() -> String {
+    let key = key_temperatures(&read_hwmon());
+    let throttle = read_throttle_counters();
+    let parts = format_key_temperatures(&key);
+    let temps = if parts.is_empty() { "Not detected".to_string() } else { parts.join(" | ") };
+    format!("Temps: {} | Throttle events: {}", temps, throttle.core_events + throttle.package_events)
+}
@@ -0,0 +1,246 @@
+// sleep_check.rs
+// Suspend, hibernate and sleep-state diagnostics
+
+use std::fs;
+use std::path::Path;
+
+use super::memory_info;
+use super::sysfs;
+
+const POWER_STATE_PATH: &str = "/sys/power/state";
+const MEM_SLEEP_PATH: &str = "/sys/power/mem_sleep";
+const DISK_MODE_PATH: &str = "/sys/power/disk";
+const SUSPEND_STATS_DIR: &str = "/sys/power/suspend_stats";
+const LOCKDOWN_PATH: &str = "/sys/kernel/security/lockdown";
+const ACPI_WAKEUP_PATH: &str = "/proc/acpi/wakeup";
+const WAKEUP_CLASS_DIR: &str = "/sys/class/wakeup";
+const S2IDLE_SYSTEM_RESIDENCY_PATH: &str = "/sys/devices/system/cpu/cpuidle/low_power_idle_system_residency_us";
+const S2IDLE_CPU_RESIDENCY_PATH: &str = "/sys/devices/system/cpu/cpuidle/low_power_idle_cpu_residency_us";
+
+#[derive(Debug, Clone)]
+pub struct HibernationReadiness {
+    pub ram_kib: u64,
+    /// Disk-backed swap only; zram cannot hold a hibernation image.
+    pub swap_kib: u64,
+    pub resume_param: Option<String>,
+    pub resume_offset: Option<String>,
+    pub initramfs_resume: Option<(String, bool)>,
+    pub lockdown: Option<String>,
+}
+
+fn cmdline_param(name: &str) -> Option<String> {
+    let cmdline = fs::read_to_string("/proc/cmdline").ok()?;
+    cmdline
+        .split_whitespace()
+        .find_map(|p| p.strip_prefix(&format!("{}=", name)).map(|v| v.to_string()))
+}
+
+/// Check whether the initramfs generator is configured to resume from hibernation.
+/// Returns the generator name and whether a resume hook/module is present.
+fn initramfs_resume_hook() -> Option<(String, bool)> {
+    if let Ok(conf) = fs::read_to_string("/etc/mkinitcpio.conf") {
+        let hooks = conf
+            .lines()
+            .map(|l| l.trim())
+            .find(|l| l.starts_with("HOOKS="))
+            .unwrap_or("")
+            .to_string();
+        // The systemd hook handles resume itself.
+        let has_resume = hooks.split(|c: char| !c.is_alphanumeric() && c != '-' && c != '_').any(|h| h == "resume" || h == "systemd");
+        return Some(("mkinitcpio".to_string(), has_resume));
+    }
+    if Path::new("/etc/dracut.conf").exists() || Path::new("/etc/dracut.conf.d").is_dir() {
+        // dracut adds its resume module automatically unless it is omitted.
+        let omitted = sysfs::list_dir_prefixed("/etc/dracut.conf.d", "")
+            .into_iter()
+            .chain(std::iter::once(Path::new("/etc/dracut.conf").to_path_buf()))
+            .filter_map(|p| fs::read_to_string(p).ok())
+            .any(|c| c.lines().any(|l| l.trim_start().starts_with("omit_dracutmodules") && l.contains("resume")));
+        return Some(("dracut".to_string(), !omitted));
+    }
+    if Path::new("/etc/initramfs-tools").is_dir() {
+        let configured = fs::read_to_string("/etc/initramfs-tools/conf.d/resume")
+            .map(|c| c.lines().any(|l| l.trim_start().starts_with("RESUME=") && !l.contains("RESUME=none")))
+            .unwrap_or(false);
+        return Some(("initramfs-tools".to_string(), configured));
+    }
+    None
+}
+
+pub fn hibernation_readiness() -> HibernationReadiness {
+    HibernationReadiness {
+        ram_kib: memory_info::total_and_available(&memory_info::read_meminfo()).map_or(0, |(total, _)| total),
+        swap_kib: memory_info::read_swaps().iter().filter(|s| !s.name.starts_with("/dev/zram")).map(|s| s.size_kib).sum(),
+        resume_param: cmdline_param("resume"),
+        resume_offset: cmdline_param("resume_offset"),
+        initramfs_resume: initramfs_resume_hook(),
+        lockdown: sysfs::read_trimmed(LOCKDOWN_PATH).and_then(|v| sysfs::bracketed_choice(&v)),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AcpiWakeup {
+    pub device: String,
+    pub sleep_state: String,
+    pub enabled: bool,
+    pub sysfs_node: Option<String>,
+}
+
+fn parse_acpi_wakeup(content: &str) -> Vec<AcpiWakeup> {
+    // Device	S-state	  Status   Sysfs node
+    // XHC	  S3	*enabled   pci:0000:00:14.0
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut cols = line.split_whitespace();
+            let device = cols.next()?.to_string();
+            let sleep_state = cols.next()?.to_string();
+            let status = cols.next()?;
+            Some(AcpiWakeup {
+                device,
+                sleep_state,
+                enabled: status.trim_start_matches('*') == "enabled",
+                sysfs_node: cols.next().map(|s| s.to_string()),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct WakeupSource {
+    pub name: String,
+    pub event_count: u64,
+    pub wakeup_count: u64,
+}
+
+fn read_wakeup_sources() -> Vec<WakeupSource> {
+    let mut sources: Vec<WakeupSource> = sysfs::list_dir_prefixed(WAKEUP_CLASS_DIR, "wakeup")
+        .into_iter()
+        .filter_map(|dir| {
+            Some(WakeupSource {
+                name: sysfs::read_trimmed(dir.join("name"))?,
+                event_count: sysfs::read_u64(dir.join("event_count")).unwrap_or(0),
+                wakeup_count: sysfs::read_u64(dir.join("wakeup_count")).unwrap_or(0),
+            })
+        })
+        .collect();
+    sources.sort_by_key(|s| std::cmp::Reverse((s.wakeup_count, s.event_count)));
+    sources
+}
+
+fn report_states() {
+    let states = sysfs::read_trimmed(POWER_STATE_PATH)
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "none".to_string());
+    println!("Supported sleep states: {}", states);
+    let mem_sleep = sysfs::read_trimmed(MEM_SLEEP_PATH);
+    if let Some(raw) = &mem_sleep {
+        let selected = sysfs::bracketed_choice(raw).unwrap_or_else(|| raw.clone());
+        println!("Suspend mode (mem_sleep): {} (available: {})", selected, raw.replace(['[', ']'], ""));
+        if selected == "s2idle" && raw.contains("deep") {
+            println!("ℹ️ S3 (deep) is available; add mem_sleep_default=deep to the kernel cmdline if s2idle drains the battery.");
+        } else if selected == "s2idle" {
+            println!("ℹ️ Only s2idle (Modern Standby) is available; battery drain depends on reaching hardware sleep.");
+        }
+    }
+    if let Some(raw) = sysfs::read_trimmed(DISK_MODE_PATH) {
+        println!("Hibernation mode (disk): {}", sysfs::bracketed_choice(&raw).unwrap_or(raw));
+    }
+}
+
+fn report_hibernation() {
+    println!("\n💤 Hibernation readiness:");
+    let h = hibernation_readiness();
+    let swap_ok = h.swap_kib > 0 && h.swap_kib * 10 >= h.ram_kib * 6;
+    // The kernel compresses the image to ~2/5 of RAM by default, but a full-size swap avoids failures.
+    println!(
+        "  {} Disk swap {} for {} RAM{}",
+        if swap_ok { "✅" } else { "⚠️" },
+        sysfs::format_kib(h.swap_kib),
+        sysfs::format_kib(h.ram_kib),
+        if h.swap_kib >= h.ram_kib { "" } else if swap_ok { " (enough for a typical compressed image)" } else { " (too small)" }
+    );
+    match &h.resume_param {
+        Some(dev) => {
+            let offset = h.resume_offset.as_ref().map(|o| format!(", resume_offset={}", o)).unwrap_or_default();
+            println!("  ✅ resume={}{} on the kernel cmdline", dev, offset);
+        }
+        None => println!("  ⚠️ No resume= parameter on the kernel cmdline"),
+    }
+    match &h.initramfs_resume {
+        Some((generator, true)) => println!("  ✅ {} is configured to resume", generator),
+        Some((generator, false)) => println!("  ⚠️ {} has no resume hook/module configured", generator),
+        None => println!("  ℹ️ Initramfs generator not recognised; verify resume support manually"),
+    }
+    if let Some(mode) = &h.lockdown
+        && mode != "none"
+    {
+        println!("  ⚠️ Kernel lockdown is '{}' (usually Secure Boot); hibernation is disabled by the kernel", mode);
+    }
+}
+
+fn report_wakeups() {
+    println!("\n⏰ Wakeup sources:");
+    let acpi = fs::read_to_string(ACPI_WAKEUP_PATH).map(|c| parse_acpi_wakeup(&c)).unwrap_or_default();
+    let enabled: Vec<&AcpiWakeup> = acpi.iter().filter(|w| w.enabled).collect();
+    if enabled.is_empty() {
+        println!("  ACPI: none enabled");
+    } else {
+        println!("  ACPI devices allowed to wake the system:");
+        for w in enabled {
+            println!("    {} ({}) {}", w.device, w.sleep_state, w.sysfs_node.as_deref().unwrap_or(""));
+        }
+    }
+    let sources: Vec<WakeupSource> = read_wakeup_sources().into_iter().filter(|s| s.event_count > 0).take(10).collect();
+    if !sources.is_empty() {
+        println!("  Most active wakeup sources:");
+        for s in sources {
+            println!("    {:<32} wakeups {:>6}, events {:>8}", s.name, s.wakeup_count, s.event_count);
+        }
+    }
+}
+
+fn report_residency() {
+    println!("\n📊 Last suspend:");
+    let stats = Path::new(SUSPEND_STATS_DIR);
+    let success = sysfs::read_u64(stats.join("success"));
+    let fail = sysfs::read_u64(stats.join("fail"));
+    if let (Some(success), Some(fail)) = (success, fail) {
+        println!("  Suspends since boot: {} succeeded, {} failed", success, fail);
+        if fail > 0
+            && let Some(step) = sysfs::read_trimmed(stats.join("last_failed_step"))
+        {
+            let dev = sysfs::read_trimmed(stats.join("last_failed_dev")).unwrap_or_default();
+            println!("  ⚠️ Last failure at step '{}' {}", step, dev);
+        }
+    }
+    // last_hw_sleep (Linux 6.8+) is the time the last suspend spent in hardware sleep.
+    if let Some(hw_sleep) = sysfs::read_u64(stats.join("last_hw_sleep")) {
+        if success.unwrap_or(0) == 0 {
+            println!("  No suspend recorded since boot.");
+        } else if hw_sleep == 0 {
+            println!("  ⚠️ The last suspend never reached hardware sleep; a device or driver is blocking low-power states.");
+        } else {
+            println!("  ✅ Last suspend spent {:.1}s in hardware sleep", hw_sleep as f64 / 1_000_000.0);
+        }
+    }
+    let system = sysfs::read_u64(S2IDLE_SYSTEM_RESIDENCY_PATH);
+    let cpu = sysfs::read_u64(S2IDLE_CPU_RESIDENCY_PATH);
+    if system.is_some() || cpu.is_some() {
+        let fmt = |v: Option<u64>| v.map(|us| format!("{:.1}s", us as f64 / 1_000_000.0)).unwrap_or_else(|| "n/a".to_string());
+        println!("  s2idle low-power residency: system {}, CPU {}", fmt(system), fmt(cpu));
+        if system == Some(0) && success.unwrap_or(0) > 0 {
+            println!("  ⚠️ s2idle has never reached the platform's low-power state (S0ix) since boot.");
+        }
+    }
+}
+
+/// Entry point for `nephyra power sleep`.
+pub fn run() {
+    println!("😴 Suspend & Hibernate Diagnostics\n");
+    report_states();
+    report_hibernation();
+    report_wakeups();
+    report_residency();
+}
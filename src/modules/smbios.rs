@@ -0,0 +1,166 @@
+// smbios.rs
+// Decode SMBIOS structures exposed by the kernel under /sys/firmware/dmi (root only)
+
+use std::fs;
+use std::path::Path;
+
+use super::sysfs;
+
+const DMI_ENTRIES_DIR: &str = "/sys/firmware/dmi/entries";
+const DMI_TABLE_PATH: &str = "/sys/firmware/dmi/tables/DMI";
+/// Type 127 marks the end of the table.
+const END_OF_TABLE: u8 = 127;
+
+/// One SMBIOS structure: the formatted area (header included) and its string set.
+#[derive(Debug, Clone)]
+pub struct Structure {
+    pub kind: u8,
+    data: Vec<u8>,
+    strings: Vec<String>,
+}
+
+impl Structure {
+    /// Split a raw structure into formatted area and strings; returns the structure and its total length.
+    pub fn parse(raw: &[u8]) -> Option<(Structure, usize)> {
+        let kind = *raw.first()?;
+        let len = *raw.get(1)? as usize;
+        if len < 4 || raw.len() < len {
+            return None;
+        }
+        // The string set is a run of NUL-terminated strings ended by an extra NUL (two NULs if empty).
+        let mut strings = Vec::new();
+        let mut pos = len;
+        loop {
+            let end = raw[pos..].iter().position(|&b| b == 0)? + pos;
+            if end == pos {
+                pos += 1;
+                break;
+            }
+            strings.push(String::from_utf8_lossy(&raw[pos..end]).trim().to_string());
+            pos = end + 1;
+        }
+        if strings.is_empty() {
+            pos += 1;
+        }
+        Some((Structure { kind, data: raw[..len].to_vec(), strings }, pos.min(raw.len())))
+    }
+
+    pub fn byte(&self, offset: usize) -> Option<u8> {
+        self.data.get(offset).copied()
+    }
+
+    pub fn word(&self, offset: usize) -> Option<u16> {
+        Some(u16::from_le_bytes([*self.data.get(offset)?, *self.data.get(offset + 1)?]))
+    }
+
+    pub fn dword(&self, offset: usize) -> Option<u32> {
+        let b = self.data.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// The string referenced by the 1-based index stored at `offset`.
+    pub fn string(&self, offset: usize) -> Option<&str> {
+        let index = self.byte(offset)? as usize;
+        if index == 0 {
+            return None;
+        }
+        self.strings.get(index - 1).map(|s| s.as_str()).filter(|s| !s.is_empty())
+    }
+}
+
+/// Whether the per-structure entries can be read at all; they are root-only, so an empty
+/// `read_entries` result is otherwise ambiguous.
+pub fn entries_readable() -> bool {
+    // Type 0 (BIOS information) is mandatory
+    fs::read(Path::new(DMI_ENTRIES_DIR).join("0-0/raw")).is_ok()
+}
+
+/// All structures of one type, e.g. 17 for memory devices.
+pub fn read_entries(kind: u8) -> Vec<Structure> {
+    sysfs::list_dir_prefixed(DMI_ENTRIES_DIR, &format!("{}-", kind))
+        .into_iter()
+        .filter_map(|dir| fs::read(dir.join("raw")).ok())
+        .filter_map(|raw| Structure::parse(&raw).map(|(s, _)| s))
+        .collect()
+}
+
+/// Every structure in the raw SMBIOS table, in table order.
+pub fn read_table() -> Vec<Structure> {
+    fs::read(DMI_TABLE_PATH).map(|raw| parse_table(&raw)).unwrap_or_default()
+}
+
+/// Walk a raw table up to the end-of-table structure or the first malformed one.
+pub fn parse_table(raw: &[u8]) -> Vec<Structure> {
+    let mut structures = Vec::new();
+    let mut pos = 0;
+    while pos < raw.len() {
+        let Some((structure, len)) = Structure::parse(&raw[pos..]) else {
+            break;
+        };
+        pos += len;
+        if structure.kind == END_OF_TABLE {
+            break;
+        }
+        structures.push(structure);
+    }
+    structures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A structure with the given formatted area (after the 4-byte header) and strings.
+    fn raw(kind: u8, body: &[u8], strings: &[&str]) -> Vec<u8> {
+        let mut raw = vec![kind, (4 + body.len()) as u8, 0x00, 0x01];
+        raw.extend_from_slice(body);
+        for s in strings {
+            raw.extend_from_slice(s.as_bytes());
+            raw.push(0);
+        }
+        if strings.is_empty() {
+            raw.push(0);
+        }
+        raw.push(0);
+        raw
+    }
+
+    #[test]
+    fn structure_strings() {
+        // Type 1 (system): manufacturer, product, version (unset), serial
+        let data = raw(1, &[1, 2, 0, 3, 0x34, 0x12, 0x78, 0x56], &["LENOVO", "21CBCTO1WW ", "PF3ABCDE"]);
+        let (s, len) = Structure::parse(&data).unwrap();
+        assert_eq!(len, data.len());
+        assert_eq!(s.kind, 1);
+        assert_eq!(s.string(0x04), Some("LENOVO"));
+        assert_eq!(s.string(0x05), Some("21CBCTO1WW"), "strings are trimmed");
+        assert_eq!(s.string(0x06), None, "index 0 means no string");
+        assert_eq!(s.string(0x07), Some("PF3ABCDE"));
+        assert_eq!(s.word(0x08), Some(0x1234));
+        assert_eq!(s.dword(0x08), Some(0x5678_1234));
+        assert_eq!(s.byte(0x0C), None, "past the formatted area");
+    }
+
+    #[test]
+    fn structure_without_strings() {
+        let data = raw(16, &[3, 3, 0, 0, 0, 0, 0, 0], &[]);
+        let (s, len) = Structure::parse(&data).unwrap();
+        assert_eq!(len, 4 + 8 + 2);
+        assert_eq!(s.string(0x04), None);
+        assert!(Structure::parse(&[1, 2]).is_none(), "length below the header size");
+        assert!(Structure::parse(&[1, 12, 0, 0]).is_none(), "truncated formatted area");
+    }
+
+    #[test]
+    fn table_stops_at_end_marker() {
+        let mut table = raw(0, &[1, 2, 0, 0xF0], &["American Megatrends", "1.2.3"]);
+        table.extend(raw(1, &[1, 0, 0, 0], &["Framework"]));
+        table.extend(raw(END_OF_TABLE, &[], &[]));
+        table.extend(raw(2, &[1], &["after the end"]));
+        let structures = parse_table(&table);
+        assert_eq!(structures.iter().map(|s| s.kind).collect::<Vec<_>>(), [0, 1]);
+        assert_eq!(structures[0].string(0x05), Some("1.2.3"));
+        assert_eq!(structures[1].string(0x04), Some("Framework"));
+        assert!(parse_table(&table[..10]).is_empty(), "a cut-off structure ends the walk");
+    }
+}
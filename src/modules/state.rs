@@ -0,0 +1,41 @@
+// state.rs
+// Persistent state under $XDG_STATE_HOME/nephyra (revert records, snapshots)
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// $XDG_STATE_HOME/nephyra, defaulting to ~/.local/state/nephyra.
+pub fn state_dir() -> PathBuf {
+    if let Ok(dir) = env::var("XDG_STATE_HOME")
+        && !dir.is_empty()
+    {
+        return PathBuf::from(dir).join("nephyra");
+    }
+    let home = env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".local/state/nephyra")
+}
+
+/// Load a TOML state file by name, returning None if it is missing or unparsable.
+pub fn load<T: DeserializeOwned>(name: &str) -> Option<T> {
+    let contents = fs::read_to_string(state_dir().join(name)).ok()?;
+    toml::from_str(&contents).ok()
+}
+
+/// Save a value as a TOML state file and return the path it was written to.
+pub fn save<T: Serialize>(name: &str, value: &T) -> io::Result<PathBuf> {
+    let dir = state_dir();
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(name);
+    let contents = toml::to_string_pretty(value).map_err(io::Error::other)?;
+    fs::write(&path, contents)?;
+    Ok(path)
+}
+
+pub fn remove(name: &str) -> io::Result<()> {
+    fs::remove_file(state_dir().join(name))
+}
@@ -0,0 +1,399 @@
+// storage_health.rs
+// Per-drive health from smartctl --json, falling back to the NVMe SMART log (ioctl) and sysfs
+
+use std::fs::File;
+use std::os::fd::AsRawFd;
+use std::path::Path;
+use std::process::Command;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::config;
+use super::sysfs;
+
+const BLOCK_DIR: &str = "/sys/block";
+const NVME_CLASS_DIR: &str = "/sys/class/nvme";
+/// Block devices that are never physical drives.
+const VIRTUAL_PREFIXES: [&str; 6] = ["loop", "ram", "zram", "dm-", "md", "sr"];
+
+/// _IOWR('N', 0x41, struct nvme_passthru_cmd)
+const NVME_IOCTL_ADMIN_CMD: libc::c_ulong = 0xC048_4E41;
+const NVME_ADMIN_GET_LOG_PAGE: u8 = 0x02;
+const NVME_LOG_SMART: u32 = 0x02;
+const NVME_SMART_LOG_LEN: usize = 512;
+
+/// Warning thresholds, configurable in the `[storage_health]` section of config.toml.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Thresholds {
+    pub temperature_c: i64,
+    pub percentage_used: u64,
+    pub media_errors: u64,
+    pub reallocated_sectors: u64,
+    pub pending_sectors: u64,
+    pub unsafe_shutdowns: u64,
+    pub power_on_hours: u64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Thresholds {
+            temperature_c: 60,
+            percentage_used: 80,
+            media_errors: 0,
+            reallocated_sectors: 0,
+            pending_sectors: 0,
+            unsafe_shutdowns: 200,
+            power_on_hours: 50_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DriveHealth {
+    pub device: String,
+    pub model: Option<String>,
+    /// Where the data came from: "smartctl", "nvme ioctl" or "sysfs".
+    pub source: &'static str,
+    pub passed: Option<bool>,
+    pub temperature_c: Option<i64>,
+    pub percentage_used: Option<u64>,
+    pub media_errors: Option<u64>,
+    pub reallocated_sectors: Option<u64>,
+    pub pending_sectors: Option<u64>,
+    pub power_on_hours: Option<u64>,
+    pub unsafe_shutdowns: Option<u64>,
+}
+
+impl DriveHealth {
+    /// Human-readable warnings for every value over its threshold.
+    pub fn warnings(&self, t: &Thresholds) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.passed == Some(false) {
+            warnings.push("SMART overall health check FAILED; back up this drive now".to_string());
+        }
+        let mut over = |value: Option<u64>, limit: u64, what: &str| {
+            if let Some(v) = value.filter(|v| *v > limit) {
+                warnings.push(format!("{}: {} (threshold {})", what, v, limit));
+            }
+        };
+        over(self.percentage_used, t.percentage_used, "Endurance used %");
+        over(self.media_errors, t.media_errors, "Media/data integrity errors");
+        over(self.reallocated_sectors, t.reallocated_sectors, "Reallocated sectors");
+        over(self.pending_sectors, t.pending_sectors, "Pending sectors");
+        over(self.unsafe_shutdowns, t.unsafe_shutdowns, "Unsafe shutdowns");
+        over(self.power_on_hours, t.power_on_hours, "Power-on hours");
+        if let Some(temp) = self.temperature_c.filter(|c| *c > t.temperature_c) {
+            warnings.push(format!("Temperature: {}°C (threshold {}°C)", temp, t.temperature_c));
+        }
+        warnings
+    }
+}
+
+/// Physical block devices (sda, nvme0n1, vda, mmcblk0, ...).
+pub fn list_drives() -> Vec<String> {
+    sysfs::list_dir_prefixed(BLOCK_DIR, "")
+        .into_iter()
+        .filter_map(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+        .filter(|name| !VIRTUAL_PREFIXES.iter().any(|prefix| name.starts_with(prefix)))
+        .collect()
+}
+
+fn ata_attribute(json: &Value, id: u64) -> Option<u64> {
+    json["ata_smart_attributes"]["table"]
+        .as_array()?
+        .iter()
+        .find(|a| a["id"].as_u64() == Some(id))
+        .and_then(|a| a["raw"]["value"].as_u64())
+}
+
+/// Map `smartctl --json -a` output (ATA or NVMe) onto DriveHealth.
+pub fn from_smartctl_json(device: &str, json: &Value) -> DriveHealth {
+    let nvme = &json["nvme_smart_health_information_log"];
+    DriveHealth {
+        device: device.to_string(),
+        model: json["model_name"].as_str().map(|s| s.to_string()),
+        source: "smartctl",
+        passed: json["smart_status"]["passed"].as_bool(),
+        temperature_c: json["temperature"]["current"].as_i64().or_else(|| nvme["temperature"].as_i64()),
+        percentage_used: nvme["percentage_used"].as_u64(),
+        media_errors: nvme["media_errors"].as_u64(),
+        // 5 = Reallocated_Sector_Ct, 197 = Current_Pending_Sector
+        reallocated_sectors: ata_attribute(json, 5),
+        pending_sectors: ata_attribute(json, 197),
+        power_on_hours: json["power_on_time"]["hours"].as_u64().or_else(|| nvme["power_on_hours"].as_u64()),
+        // NVMe counts unsafe shutdowns directly; ATA drives use 192 (Power-Off_Retract_Count) or 174 (Unexpected_Power_Loss)
+        unsafe_shutdowns: nvme["unsafe_shutdowns"].as_u64().or_else(|| ata_attribute(json, 174)).or_else(|| ata_attribute(json, 192)),
+    }
+}
+
+fn smartctl(device: &str) -> Option<DriveHealth> {
+    let output = Command::new("smartctl").args(["--json", "-a"]).arg(format!("/dev/{}", device)).output().ok()?;
+    // smartctl's exit status is a bitmask that is non-zero for many healthy-but-noteworthy drives, so parse regardless
+    let json: Value = serde_json::from_slice(&output.stdout).ok()?;
+    if json["smart_status"].is_null() && json["nvme_smart_health_information_log"].is_null() {
+        return None;
+    }
+    Some(from_smartctl_json(device, &json))
+}
+
+fn le_u128(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&data[offset..offset + 16]);
+    u128::from_le_bytes(bytes).min(u64::MAX as u128) as u64
+}
+
+/// Decode the 512-byte NVMe SMART / Health Information log page (NVMe base spec, log identifier 02h).
+pub fn parse_nvme_smart_log(device: &str, data: &[u8]) -> Option<DriveHealth> {
+    if data.len() < NVME_SMART_LOG_LEN {
+        return None;
+    }
+    let critical_warning = data[0];
+    let kelvin = u16::from_le_bytes([data[1], data[2]]) as i64;
+    Some(DriveHealth {
+        device: device.to_string(),
+        source: "nvme ioctl",
+        // Any critical warning bit (spare below threshold, temperature, reliability, read-only, backup) fails the drive
+        passed: Some(critical_warning == 0),
+        temperature_c: if kelvin > 0 { Some(kelvin - 273) } else { None },
+        percentage_used: Some(data[5] as u64),
+        power_on_hours: Some(le_u128(data, 128)),
+        unsafe_shutdowns: Some(le_u128(data, 144)),
+        media_errors: Some(le_u128(data, 160)),
+        ..Default::default()
+    })
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct NvmePassthruCmd {
+    opcode: u8,
+    flags: u8,
+    rsvd1: u16,
+    nsid: u32,
+    cdw2: u32,
+    cdw3: u32,
+    metadata: u64,
+    addr: u64,
+    metadata_len: u32,
+    data_len: u32,
+    cdw10: u32,
+    cdw11: u32,
+    cdw12: u32,
+    cdw13: u32,
+    cdw14: u32,
+    cdw15: u32,
+    timeout_ms: u32,
+    result: u32,
+}
+
+/// Read the SMART log page from the NVMe controller character device (needs root).
+fn nvme_smart_log(controller: &str) -> Option<Vec<u8>> {
+    let file = File::open(format!("/dev/{}", controller)).ok()?;
+    let mut data = vec![0u8; NVME_SMART_LOG_LEN];
+    let mut cmd = NvmePassthruCmd {
+        opcode: NVME_ADMIN_GET_LOG_PAGE,
+        nsid: 0xFFFF_FFFF,
+        addr: data.as_mut_ptr() as u64,
+        data_len: NVME_SMART_LOG_LEN as u32,
+        // Number of dwords minus one in the upper half, log identifier in the lower byte
+        cdw10: (((NVME_SMART_LOG_LEN / 4 - 1) as u32) << 16) | NVME_LOG_SMART,
+        ..Default::default()
+    };
+    // SAFETY: cmd is a properly laid out nvme_passthru_cmd and addr points to a live buffer of data_len bytes.
+    let ret = unsafe { libc::ioctl(file.as_raw_fd(), NVME_IOCTL_ADMIN_CMD as _, &mut cmd as *mut NvmePassthruCmd) };
+    if ret == 0 { Some(data) } else { None }
+}
+
+/// NVMe controller ("nvme0") behind a namespace block device ("nvme0n1").
+fn nvme_controller(device: &str) -> Option<String> {
+    let index = device.strip_prefix("nvme")?.split('n').next()?;
+    let controller = format!("nvme{}", index);
+    Path::new(NVME_CLASS_DIR).join(&controller).exists().then_some(controller)
+}
+
+/// Temperature from the controller's hwmon node, readable without root.
+fn nvme_sysfs_temperature(controller: &str) -> Option<i64> {
+    let dir = Path::new(NVME_CLASS_DIR).join(controller);
+    sysfs::list_dir_prefixed(&dir, "hwmon")
+        .into_iter()
+        .chain(sysfs::list_dir_prefixed(dir.join("device/hwmon"), "hwmon"))
+        .find_map(|hwmon| sysfs::read_i64(hwmon.join("temp1_input")))
+        .map(|milli| milli / 1000)
+}
+
+pub fn read_health(device: &str) -> DriveHealth {
+    let model = sysfs::read_trimmed(Path::new(BLOCK_DIR).join(device).join("device/model"));
+    if let Some(mut health) = smartctl(device) {
+        health.model = health.model.or(model);
+        return health;
+    }
+    if let Some(controller) = nvme_controller(device) {
+        if let Some(mut health) = nvme_smart_log(&controller).and_then(|log| parse_nvme_smart_log(device, &log)) {
+            health.model = model;
+            return health;
+        }
+        return DriveHealth {
+            device: device.to_string(),
+            model,
+            source: "sysfs",
+            temperature_c: nvme_sysfs_temperature(&controller),
+            ..Default::default()
+        };
+    }
+    DriveHealth { device: device.to_string(), model, source: "sysfs", ..Default::default() }
+}
+
+fn print_drive(health: &DriveHealth, thresholds: &Thresholds) {
+    let status = match health.passed {
+        Some(true) => "✅ PASSED",
+        Some(false) => "❌ FAILED",
+        None => "❔ unknown",
+    };
+    println!("\n💽 {} {} — {} (via {})", health.device, health.model.as_deref().unwrap_or(""), status, health.source);
+    let show = |label: &str, value: Option<String>| {
+        if let Some(v) = value {
+            println!("   {}: {}", label, v);
+        }
+    };
+    show("Temperature", health.temperature_c.map(|c| format!("{}°C", c)));
+    show("Endurance used", health.percentage_used.map(|p| format!("{}%", p)));
+    show("Media errors", health.media_errors.map(|v| v.to_string()));
+    show("Reallocated sectors", health.reallocated_sectors.map(|v| v.to_string()));
+    show("Pending sectors", health.pending_sectors.map(|v| v.to_string()));
+    show("Power-on hours", health.power_on_hours.map(|v| v.to_string()));
+    show("Unsafe shutdowns", health.unsafe_shutdowns.map(|v| v.to_string()));
+    for warning in health.warnings(thresholds) {
+        println!("   ⚠️ {}", warning);
+    }
+}
+
+/// Entry point for `nephyra storage`.
+pub fn run() {
+    println!("🩺 Storage health");
+    let thresholds: Thresholds = config::load_section("storage_health");
+    let drives = list_drives();
+    if drives.is_empty() {
+        println!("No physical drives found.");
+        return;
+    }
+    let mut incomplete = false;
+    for device in &drives {
+        let health = read_health(device);
+        incomplete |= health.source == "sysfs";
+        print_drive(&health, &thresholds);
+    }
+    if incomplete {
+        if sysfs::is_root() {
+            println!("\nℹ️ Install smartmontools (smartctl) for full SMART data on every drive.");
+        } else {
+            println!("\nℹ️ SMART data needs root; re-run with sudo (and install smartmontools) for full results.");
+        }
+    }
+    println!("ℹ️ Thresholds can be changed in the [storage_health] section of {}", config::config_path().display());
+}
+
+pub fn get_summary() -> String {
+    let thresholds: Thresholds = config::load_section("storage_health");
+    let parts: Vec<String> = list_drives()
+        .iter()
+        .map(|d| {
+            let health = read_health(d);
+            let warnings = health.warnings(&thresholds).len();
+            match (health.passed, warnings) {
+                (Some(false), _) => format!("{} ❌ failed", d),
+                (_, 0) if health.source == "sysfs" => format!("{} ❔ no SMART data", d),
+                (_, 0) => format!("{} ✅", d),
+                (_, n) => format!("{} ⚠️ {} warning(s)", d, n),
+            }
+        })
+        .collect();
+    format!("Storage Health: {}", if parts.is_empty() { "no drives".to_string() } else { parts.join(", ") })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn smartctl_nvme() {
+        let json: Value = serde_json::from_str(
+            r#"{
+                "device": {"name": "/dev/nvme0", "type": "nvme", "protocol": "NVMe"},
+                "model_name": "Samsung SSD 980 PRO 1TB",
+                "smart_status": {"passed": true, "nvme": {"value": 0}},
+                "nvme_smart_health_information_log": {
+                    "critical_warning": 0, "temperature": 41, "available_spare": 100, "percentage_used": 3,
+                    "power_on_hours": 4210, "unsafe_shutdowns": 57, "media_errors": 0
+                },
+                "temperature": {"current": 41},
+                "power_on_time": {"hours": 4210}
+            }"#,
+        )
+        .unwrap();
+        let health = from_smartctl_json("nvme0n1", &json);
+        assert_eq!(health.model.as_deref(), Some("Samsung SSD 980 PRO 1TB"));
+        assert_eq!(health.passed, Some(true));
+        assert_eq!(health.temperature_c, Some(41));
+        assert_eq!(health.percentage_used, Some(3));
+        assert_eq!(health.power_on_hours, Some(4210));
+        assert_eq!(health.unsafe_shutdowns, Some(57));
+        assert_eq!(health.media_errors, Some(0));
+        assert_eq!(health.reallocated_sectors, None);
+        assert!(health.warnings(&Thresholds::default()).is_empty());
+    }
+
+    #[test]
+    fn smartctl_ata() {
+        let json: Value = serde_json::from_str(
+            r#"{
+                "model_name": "WDC WD40EFRX-68N32N0",
+                "smart_status": {"passed": false},
+                "ata_smart_attributes": {"table": [
+                    {"id": 5, "name": "Reallocated_Sector_Ct", "raw": {"value": 8, "string": "8"}},
+                    {"id": 192, "name": "Power-Off_Retract_Count", "raw": {"value": 31, "string": "31"}},
+                    {"id": 197, "name": "Current_Pending_Sector", "raw": {"value": 2, "string": "2"}}
+                ]},
+                "power_on_time": {"hours": 61234},
+                "temperature": {"current": 35}
+            }"#,
+        )
+        .unwrap();
+        let health = from_smartctl_json("sda", &json);
+        assert_eq!(health.passed, Some(false));
+        assert_eq!(health.reallocated_sectors, Some(8));
+        assert_eq!(health.pending_sectors, Some(2));
+        assert_eq!(health.unsafe_shutdowns, Some(31));
+        assert_eq!(health.percentage_used, None);
+        let warnings = health.warnings(&Thresholds::default());
+        assert_eq!(warnings.len(), 4, "{:?}", warnings);
+        assert!(warnings[0].starts_with("SMART overall health check FAILED"));
+    }
+
+    #[test]
+    fn nvme_smart_log_page() {
+        let mut log = vec![0u8; NVME_SMART_LOG_LEN];
+        log[0] = 0x04; // reliability degraded
+        log[1..3].copy_from_slice(&318u16.to_le_bytes()); // 45 °C
+        log[5] = 12;
+        log[128..144].copy_from_slice(&9876u128.to_le_bytes());
+        log[144..160].copy_from_slice(&21u128.to_le_bytes());
+        log[160..176].copy_from_slice(&3u128.to_le_bytes());
+        let health = parse_nvme_smart_log("nvme0n1", &log).unwrap();
+        assert_eq!(health.source, "nvme ioctl");
+        assert_eq!(health.passed, Some(false));
+        assert_eq!(health.temperature_c, Some(45));
+        assert_eq!(health.percentage_used, Some(12));
+        assert_eq!(health.power_on_hours, Some(9876));
+        assert_eq!(health.unsafe_shutdowns, Some(21));
+        assert_eq!(health.media_errors, Some(3));
+
+        log[0] = 0;
+        log[1..3].copy_from_slice(&0u16.to_le_bytes());
+        let health = parse_nvme_smart_log("nvme0n1", &log).unwrap();
+        assert_eq!(health.passed, Some(true));
+        assert_eq!(health.temperature_c, None, "0 K means no sensor");
+        assert!(parse_nvme_smart_log("nvme0n1", &log[..256]).is_none());
+    }
+}
@@ -0,0 +1,121 @@
+// sysfs.rs
+// Small helpers for reading sysfs/procfs attribute files
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Read an attribute file and return its trimmed contents, or None if it is missing or unreadable.
+pub fn read_trimmed<P: AsRef<Path>>(path: P) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+/// The file name a symlink points to, e.g. the driver name behind `device/driver`.
+pub fn link_name<P: AsRef<Path>>(path: P) -> Option<String> {
+    fs::read_link(path).ok()?.file_name().map(|n| n.to_string_lossy().to_string())
+}
+
+/// The bracketed entry of a kernel choice list, e.g. "madvise" from "always [madvise] never".
+pub fn bracketed_choice(value: &str) -> Option<String> {
+    let start = value.find('[')?;
+    let end = value[start..].find(']')? + start;
+    Some(value[start + 1..end].to_string())
+}
+
+/// Format a KiB amount as GiB from 1 GiB upwards and as MiB below that.
+pub fn format_kib(kib: u64) -> String {
+    if kib >= 1024 * 1024 {
+        format!("{:.2} GiB", kib as f64 / 1024.0 / 1024.0)
+    } else {
+        format!("{:.0} MiB", kib as f64 / 1024.0)
+    }
+}
+
+/// Read an attribute file containing a single unsigned integer.
+pub fn read_u64<P: AsRef<Path>>(path: P) -> Option<u64> {
+    read_trimmed(path)?.parse().ok()
+}
+
+/// Read an attribute file containing a single signed integer (e.g. `current_now` on some firmware).
+pub fn read_i64<P: AsRef<Path>>(path: P) -> Option<i64> {
+    read_trimmed(path)?.parse().ok()
+}
+
+/// List the entries of a directory whose file name starts with `prefix`, sorted by name.
+pub fn list_dir_prefixed<P: AsRef<Path>>(dir: P, prefix: &str) -> Vec<PathBuf> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|rd| {
+            rd.flatten()
+                .filter(|e| e.file_name().to_string_lossy().starts_with(prefix))
+                .map(|e| e.path())
+                .collect()
+        })
+        .unwrap_or_default();
+    entries.sort();
+    entries
+}
+
+/// Write a value to a sysfs attribute.
+pub fn write_attr<P: AsRef<Path>>(path: P, value: &str) -> std::io::Result<()> {
+    fs::write(path, value)
+}
+
+/// Whether the process runs with an effective UID of 0. /proc/self is owned by the effective UID.
+pub fn is_root() -> bool {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata("/proc/self").map(|m| m.uid() == 0).unwrap_or(false)
+}
+
+/// Parse a kernel CPU list such as "0-3,8,10-11" into individual CPU numbers.
+pub fn parse_cpu_list(list: &str) -> Vec<u32> {
+    let mut cpus = Vec::new();
+    for part in list.trim().split(',').filter(|p| !p.is_empty()) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                if let (Ok(start), Ok(end)) = (start.parse::<u32>(), end.parse::<u32>()) {
+                    cpus.extend(start..=end);
+                }
+            }
+            None => cpus.extend(part.parse::<u32>().ok()),
+        }
+    }
+    cpus
+}
+
+/// A per-process directory under the system temp dir for tests that need real files.
+#[cfg(test)]
+pub fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("nephyra-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpu_lists() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11\n"), [0, 1, 2, 3, 8, 10, 11]);
+        assert_eq!(parse_cpu_list("5"), [5]);
+        assert_eq!(parse_cpu_list("0-0"), [0]);
+        assert!(parse_cpu_list("").is_empty());
+        assert!(parse_cpu_list("\n").is_empty());
+        assert_eq!(parse_cpu_list("0,x,2-a,4"), [0, 4], "malformed parts are skipped");
+    }
+
+    #[test]
+    fn bracketed_choices() {
+        assert_eq!(bracketed_choice("always [madvise] never").as_deref(), Some("madvise"));
+        assert_eq!(bracketed_choice("s2idle [deep]").as_deref(), Some("deep"));
+        assert_eq!(bracketed_choice("C [PD] PD_PPS").as_deref(), Some("PD"));
+        assert_eq!(bracketed_choice("] none [integrity]").as_deref(), Some("integrity"));
+        assert_eq!(bracketed_choice("s2idle deep"), None);
+    }
+
+    #[test]
+    fn kib_sizes() {
+        assert_eq!(format_kib(512 * 1024), "512 MiB");
+        assert_eq!(format_kib(16 * 1024 * 1024), "16.00 GiB");
+        assert_eq!(format_kib(1536 * 1024), "1.50 GiB");
+    }
+}
@@ -1,9 +1,13 @@
 // system_report.rs
 
 use super::bootloader_check;
+use super::firmware_health;
 use super::hardware_info;
 use super::kernel_check;
+use super::network_info;
 use super::power_status;
+use super::sensors;
+use super::storage_health;
 
 pub fn run() {
     println!("\n🧠 Nephyra System Report (Standard)");
@@ -11,6 +15,10 @@ pub fn run() {
     println!("{}", kernel_check::get_summary());
     println!("{}", hardware_info::get_summary());
     println!("{}", power_status::get_summary());
+    println!("{}", sensors::get_summary());
+    println!("{}", storage_health::get_summary());
+    println!("{}", network_info::get_summary());
+    println!("{}", firmware_health::get_summary());
     println!("{}", bootloader_check::get_summary());
     println!("-----------------------------------");
     println!("For detailed info, run: nephyra <module>");
@@ -0,0 +1,957 @@
+// universal_packages.rs
+// Package managers that live next to the system one: Flatpak, Snap, Nix, Homebrew, cargo install and pipx,
+// plus AppImages and Distrobox containers, which have no package database to query
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde_json::Value;
+
+use super::package_manager::{self, InstallReason, Package, PackageManager, Upgrade, args_with, lines_set, parse_size, query};
+
+fn home() -> PathBuf {
+    PathBuf::from(env::var("HOME").unwrap_or_else(|_| ".".to_string()))
+}
+
+fn cargo_home() -> PathBuf {
+    env::var("CARGO_HOME").map(PathBuf::from).unwrap_or_else(|_| home().join(".cargo"))
+}
+
+/// The path component following `marker` once symlinks are resolved, e.g. the app id in
+/// /var/lib/flatpak/app/<id>/… or the formula in …/Cellar/<name>/….
+fn component_after(path: &Path, marker: &str) -> Option<String> {
+    let resolved = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let parts: Vec<String> = resolved.components().map(|c| c.as_os_str().to_string_lossy().to_string()).collect();
+    let pos = parts.iter().position(|p| p == marker)?;
+    parts.get(pos + 1).cloned()
+}
+
+/// Split "name-1.2.3" at the first dash followed by a digit.
+pub fn split_name_version(full: &str) -> (String, String) {
+    let bytes = full.as_bytes();
+    match (1..bytes.len()).find(|&i| bytes[i - 1] == b'-' && bytes[i].is_ascii_digit()) {
+        Some(pos) => (full[..pos - 1].to_string(), full[pos..].to_string()),
+        None => (full.to_string(), String::new()),
+    }
+}
+
+// ---------------------------------------------------------------------------------------------
+// Flatpak
+
+pub struct Flatpak;
+
+/// `flatpak list --columns=application,version,origin,size`: tab-separated rows.
+pub fn parse_flatpak_list(output: &str, apps: &[String]) -> Vec<Package> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let fields: Vec<&str> = l.split('\t').collect();
+            if fields.len() < 4 || fields[0].is_empty() {
+                return None;
+            }
+            let explicit = apps.iter().any(|a| a == fields[0]);
+            Some(Package {
+                name: fields[0].to_string(),
+                version: fields[1].to_string(),
+                repo: Some(fields[2].to_string()).filter(|r| !r.is_empty()),
+                reason: Some(if explicit { InstallReason::Explicit } else { InstallReason::Dependency }),
+                size: parse_size(fields[3]),
+            })
+        })
+        .collect()
+}
+
+/// Runtimes no installed app uses. `runtimes` is `flatpak list --runtime --columns=application,branch,size`,
+/// `apps` is `flatpak list --app --columns=runtime,application` ("org.gnome.Platform/x86_64/46\torg.gnome.Maps").
+/// Extensions of a used runtime or of an app (GL drivers, codecs, .Locale) count as used whatever their branch.
+pub fn parse_flatpak_unused(runtimes: &str, apps: &str) -> Vec<Package> {
+    let mut used: Vec<(&str, &str)> = Vec::new();
+    let mut parents: Vec<&str> = Vec::new();
+    for line in apps.lines() {
+        let mut fields = line.trim().split('\t');
+        let mut runtime = fields.next().unwrap_or_default().split('/');
+        if let Some(id) = runtime.next().filter(|id| !id.is_empty()) {
+            used.push((id, runtime.next_back().unwrap_or_default()));
+            parents.push(id);
+        }
+        if let Some(app) = fields.next().filter(|app| !app.is_empty()) {
+            parents.push(app);
+        }
+    }
+    runtimes
+        .lines()
+        .filter_map(|l| {
+            let fields: Vec<&str> = l.split('\t').collect();
+            let (id, branch) = (*fields.first()?, *fields.get(1)?);
+            if id.is_empty() {
+                return None;
+            }
+            let in_use = used.contains(&(id, branch)) || parents.iter().any(|p| id.strip_prefix(p).is_some_and(|rest| rest.starts_with('.')));
+            (!in_use).then(|| Package {
+                name: id.to_string(),
+                version: branch.to_string(),
+                reason: Some(InstallReason::Dependency),
+                size: fields.get(2).and_then(|s| parse_size(s)),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// `flatpak remote-ls --updates --cached --columns=application,version,origin`.
+pub fn parse_flatpak_updates(output: &str, installed: &[Package]) -> Vec<Upgrade> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let fields: Vec<&str> = l.split('\t').collect();
+            if fields.len() < 3 || fields[0].is_empty() {
+                return None;
+            }
+            Some(Upgrade {
+                name: fields[0].to_string(),
+                current: installed.iter().find(|p| p.name == fields[0]).map(|p| p.version.clone()).filter(|v| !v.is_empty()),
+                available: fields[1].to_string(),
+                repo: Some(fields[2].to_string()),
+            })
+        })
+        .collect()
+}
+
+impl PackageManager for Flatpak {
+    fn name(&self) -> &'static str {
+        "flatpak"
+    }
+
+    fn installed(&self) -> Vec<Package> {
+        let apps: Vec<String> = query("flatpak", &["list", "--app", "--columns=application"]).unwrap_or_default().lines().map(|l| l.trim().to_string()).collect();
+        parse_flatpak_list(&query("flatpak", &["list", "--columns=application,version,origin,size"]).unwrap_or_default(), &apps)
+    }
+
+    fn info(&self, name: &str) -> Option<Package> {
+        self.installed().into_iter().find(|p| p.name == name)
+    }
+
+    fn owner_of(&self, path: &Path) -> Option<String> {
+        component_after(path, "app").or_else(|| component_after(path, "runtime"))
+    }
+
+    fn orphans(&self) -> Vec<Package> {
+        let runtimes = query("flatpak", &["list", "--runtime", "--columns=application,branch,size"]).unwrap_or_default();
+        let used = query("flatpak", &["list", "--app", "--columns=runtime,application"]).unwrap_or_default();
+        parse_flatpak_unused(&runtimes, &used)
+    }
+
+    fn upgradable(&self) -> Vec<Upgrade> {
+        let installed = self.installed();
+        // --cached answers from the appstream/summary data of the last update instead of the network
+        let output = query("flatpak", &["remote-ls", "--updates", "--cached", "--columns=application,version,origin"]).unwrap_or_default();
+        parse_flatpak_updates(&output, &installed)
+    }
+
+    fn install_args(&self, names: &[String]) -> Vec<String> {
+        args_with(&["flatpak", "install"], names)
+    }
+
+    fn remove_args(&self, names: &[String]) -> Vec<String> {
+        args_with(&["flatpak", "uninstall"], names)
+    }
+
+    fn needs_root(&self) -> bool {
+        // flatpak escalates through polkit for system installations itself
+        false
+    }
+
+    fn orphan_cleanup(&self, _orphans: &[Package]) -> Vec<String> {
+        vec!["flatpak uninstall --unused".to_string()]
+    }
+}
+
+// ---------------------------------------------------------------------------------------------
+// Snap
+
+pub struct Snap;
+
+/// Rows of `snap list --all` / `snap refresh --list`, header excluded.
+fn snap_rows(output: &str) -> Vec<Vec<&str>> {
+    output.lines().skip(1).map(|l| l.split_whitespace().collect::<Vec<_>>()).filter(|f| f.len() >= 3).collect()
+}
+
+/// `snap list --all`: "Name Version Rev Tracking Publisher Notes"; disabled revisions are kept
+/// around after refreshes and returned separately as the second list.
+pub fn parse_snap_list(output: &str) -> (Vec<Package>, Vec<Package>) {
+    let mut active = Vec::new();
+    let mut disabled = Vec::new();
+    for fields in snap_rows(output) {
+        let package = Package {
+            name: fields[0].to_string(),
+            version: format!("{} (rev {})", fields[1], fields[2]),
+            repo: fields.get(3).map(|t| t.to_string()),
+            reason: Some(InstallReason::Explicit),
+            size: None,
+        };
+        if fields.last().is_some_and(|n| n.contains("disabled")) {
+            disabled.push(package);
+        } else {
+            active.push(package);
+        }
+    }
+    (active, disabled)
+}
+
+/// The revision from a version produced by `parse_snap_list`.
+fn snap_revision(version: &str) -> Option<&str> {
+    version.rsplit_once("(rev ")?.1.strip_suffix(')')
+}
+
+/// `snap refresh --list`: "Name Version Rev Size Publisher Notes".
+pub fn parse_snap_refresh(output: &str, installed: &[Package]) -> Vec<Upgrade> {
+    snap_rows(output)
+        .into_iter()
+        .map(|fields| Upgrade {
+            name: fields[0].to_string(),
+            current: installed.iter().find(|p| p.name == fields[0]).map(|p| p.version.clone()),
+            available: format!("{} (rev {})", fields[1], fields[2]),
+            repo: None,
+        })
+        .collect()
+}
+
+impl PackageManager for Snap {
+    fn name(&self) -> &'static str {
+        "snap"
+    }
+
+    fn installed(&self) -> Vec<Package> {
+        parse_snap_list(&query("snap", &["list", "--all"]).unwrap_or_default()).0
+    }
+
+    fn info(&self, name: &str) -> Option<Package> {
+        self.installed().into_iter().find(|p| p.name == name)
+    }
+
+    fn owner_of(&self, path: &Path) -> Option<String> {
+        component_after(path, "snap")
+    }
+
+    fn orphans(&self) -> Vec<Package> {
+        parse_snap_list(&query("snap", &["list", "--all"]).unwrap_or_default()).1
+    }
+
+    fn upgradable(&self) -> Vec<Upgrade> {
+        let installed = self.installed();
+        parse_snap_refresh(&query("snap", &["refresh", "--list"]).unwrap_or_default(), &installed)
+    }
+
+    fn install_args(&self, names: &[String]) -> Vec<String> {
+        args_with(&["snap", "install"], names)
+    }
+
+    fn remove_args(&self, names: &[String]) -> Vec<String> {
+        args_with(&["snap", "remove"], names)
+    }
+
+    fn orphan_cleanup(&self, orphans: &[Package]) -> Vec<String> {
+        // Removing by name would drop the active revision too
+        orphans
+            .iter()
+            .filter_map(|p| Some(format!("sudo snap remove {} --revision={}", p.name, snap_revision(&p.version)?)))
+            .collect()
+    }
+}
+
+// ---------------------------------------------------------------------------------------------
+// Nix (nix profile, falling back to nix-env)
+
+pub struct Nix;
+
+/// Name and version from a store path such as /nix/store/<hash>-hello-2.12.1.
+pub fn nix_store_name(path: &str) -> Option<(String, String)> {
+    let base = path.rsplit('/').next()?;
+    let (_hash, rest) = base.split_once('-')?;
+    Some(split_name_version(rest))
+}
+
+/// `nix profile list --json`: `elements` is a map keyed by name (format 3) or a list (format 2).
+pub fn parse_nix_profile(json: &str) -> Vec<Package> {
+    let Ok(value) = serde_json::from_str::<Value>(json) else {
+        return Vec::new();
+    };
+    let element = |key: Option<&str>, e: &Value| -> Option<Package> {
+        let store_path = e["storePaths"].as_array()?.first()?.as_str()?;
+        let (name, version) = nix_store_name(store_path)?;
+        Some(Package {
+            name: key.map(|k| k.to_string()).unwrap_or(name),
+            version,
+            repo: e["originalUrl"].as_str().map(|u| u.to_string()),
+            reason: Some(InstallReason::Explicit),
+            size: None,
+        })
+    };
+    match &value["elements"] {
+        Value::Object(map) => map.iter().filter_map(|(k, e)| element(Some(k), e)).collect(),
+        Value::Array(list) => list.iter().filter_map(|e| element(None, e)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// `nix-env -q`: "name-version" per line.
+pub fn parse_nix_env(output: &str) -> Vec<Package> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(|l| {
+            let (name, version) = split_name_version(l);
+            Package { name, version, reason: Some(InstallReason::Explicit), ..Default::default() }
+        })
+        .collect()
+}
+
+/// `nix-env -u --dry-run` (stderr): "upgrading 'hello-2.10' to 'hello-2.12'".
+pub fn parse_nix_env_upgrades(output: &str) -> Vec<Upgrade> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let quoted: Vec<&str> = l.split('\'').collect();
+            if !l.trim_start().starts_with("upgrading") || quoted.len() < 4 {
+                return None;
+            }
+            let (name, current) = split_name_version(quoted[1]);
+            let (_, available) = split_name_version(quoted[3]);
+            Some(Upgrade { name, current: Some(current), available, repo: None })
+        })
+        .collect()
+}
+
+/// `nix-store --gc --print-dead`: store paths the next garbage collection deletes.
+pub fn parse_nix_dead(output: &str) -> Vec<Package> {
+    output
+        .lines()
+        .filter(|l| l.starts_with("/nix/store/") && !l.ends_with(".drv"))
+        .filter_map(|l| {
+            let (name, version) = nix_store_name(l)?;
+            Some(Package { name, version, reason: Some(InstallReason::Dependency), ..Default::default() })
+        })
+        .collect()
+}
+
+impl Nix {
+    /// Profiles created by `nix profile` carry a manifest.json; nix-env ones do not.
+    fn uses_profile() -> bool {
+        home().join(".nix-profile/manifest.json").exists()
+    }
+}
+
+impl PackageManager for Nix {
+    fn name(&self) -> &'static str {
+        "nix"
+    }
+
+    fn installed(&self) -> Vec<Package> {
+        if Nix::uses_profile() {
+            parse_nix_profile(&query("nix", &["--extra-experimental-features", "nix-command flakes", "profile", "list", "--json"]).unwrap_or_default())
+        } else {
+            parse_nix_env(&query("nix-env", &["-q"]).unwrap_or_default())
+        }
+    }
+
+    fn info(&self, name: &str) -> Option<Package> {
+        self.installed().into_iter().find(|p| p.name == name)
+    }
+
+    fn owner_of(&self, path: &Path) -> Option<String> {
+        let resolved = fs::canonicalize(path).ok()?;
+        let store = component_after(&resolved, "store")?;
+        nix_store_name(&store).map(|(name, _)| name)
+    }
+
+    fn orphans(&self) -> Vec<Package> {
+        parse_nix_dead(&query("nix-store", &["--gc", "--print-dead"]).unwrap_or_default())
+    }
+
+    fn upgradable(&self) -> Vec<Upgrade> {
+        // `nix profile upgrade` has no dry run; only nix-env can say what would change
+        if Nix::uses_profile() {
+            return Vec::new();
+        }
+        Command::new("nix-env")
+            .args(["-u", "--dry-run"])
+            .env("LC_ALL", "C")
+            .output()
+            .map(|o| parse_nix_env_upgrades(&String::from_utf8_lossy(&o.stderr)))
+            .unwrap_or_default()
+    }
+
+    fn install_args(&self, names: &[String]) -> Vec<String> {
+        if Nix::uses_profile() {
+            let refs: Vec<String> = names.iter().map(|n| format!("nixpkgs#{}", n)).collect();
+            args_with(&["nix", "profile", "install"], &refs)
+        } else {
+            args_with(&["nix-env", "-iA"], &names.iter().map(|n| format!("nixpkgs.{}", n)).collect::<Vec<_>>())
+        }
+    }
+
+    fn remove_args(&self, names: &[String]) -> Vec<String> {
+        if Nix::uses_profile() { args_with(&["nix", "profile", "remove"], names) } else { args_with(&["nix-env", "-e"], names) }
+    }
+
+    fn needs_root(&self) -> bool {
+        false
+    }
+
+    fn orphan_cleanup(&self, _orphans: &[Package]) -> Vec<String> {
+        vec!["nix-collect-garbage".to_string()]
+    }
+}
+
+// ---------------------------------------------------------------------------------------------
+// Homebrew on Linux
+
+pub struct Brew;
+
+/// `brew list --versions`: "name 1.0 1.1" (every installed version kept in the Cellar).
+pub fn parse_brew_list(output: &str, leaves: &[String]) -> Vec<Package> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let mut fields = l.split_whitespace();
+            let name = fields.next()?;
+            let version = fields.last().unwrap_or_default();
+            let explicit = leaves.iter().any(|leaf| leaf == name);
+            Some(Package {
+                name: name.to_string(),
+                version: version.to_string(),
+                reason: Some(if explicit { InstallReason::Explicit } else { InstallReason::Dependency }),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// `brew autoremove --dry-run`: the formula names after the "Would autoremove" header.
+pub fn parse_brew_autoremove(output: &str) -> Vec<Package> {
+    output
+        .lines()
+        .skip_while(|l| !l.contains("Would autoremove"))
+        .skip(1)
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with("==>"))
+        .map(|name| Package { name: name.to_string(), reason: Some(InstallReason::Dependency), ..Default::default() })
+        .collect()
+}
+
+/// `brew outdated --verbose`: "name (1.0) < 1.1" or "name (1.0, 1.0_1) != 1.1".
+pub fn parse_brew_outdated(output: &str) -> Vec<Upgrade> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let (name, rest) = l.split_once(" (")?;
+            let (current, rest) = rest.split_once(')')?;
+            let available = rest.split_whitespace().last()?;
+            Some(Upgrade {
+                name: name.to_string(),
+                current: current.split(", ").last().map(|c| c.to_string()),
+                available: available.to_string(),
+                repo: None,
+            })
+        })
+        .collect()
+}
+
+impl PackageManager for Brew {
+    fn name(&self) -> &'static str {
+        "brew"
+    }
+
+    fn installed(&self) -> Vec<Package> {
+        let leaves: Vec<String> = lines_set(&query("brew", &["leaves"]).unwrap_or_default()).into_iter().collect();
+        parse_brew_list(&query("brew", &["list", "--versions"]).unwrap_or_default(), &leaves)
+    }
+
+    fn info(&self, name: &str) -> Option<Package> {
+        self.installed().into_iter().find(|p| p.name == name)
+    }
+
+    fn owner_of(&self, path: &Path) -> Option<String> {
+        component_after(path, "Cellar")
+    }
+
+    fn orphans(&self) -> Vec<Package> {
+        parse_brew_autoremove(&query("brew", &["autoremove", "--dry-run"]).unwrap_or_default())
+    }
+
+    fn upgradable(&self) -> Vec<Upgrade> {
+        parse_brew_outdated(&query("brew", &["outdated", "--verbose"]).unwrap_or_default())
+    }
+
+    fn install_args(&self, names: &[String]) -> Vec<String> {
+        args_with(&["brew", "install"], names)
+    }
+
+    fn remove_args(&self, names: &[String]) -> Vec<String> {
+        args_with(&["brew", "uninstall"], names)
+    }
+
+    fn needs_root(&self) -> bool {
+        false
+    }
+
+    fn orphan_cleanup(&self, _orphans: &[Package]) -> Vec<String> {
+        vec!["brew autoremove".to_string()]
+    }
+}
+
+// ---------------------------------------------------------------------------------------------
+// cargo install
+
+pub struct Cargo;
+
+/// `cargo install --list`: "name v1.2.3:" (optionally " (path)") followed by indented binaries.
+/// Returns each crate with the binaries it installed.
+pub fn parse_cargo_list(output: &str) -> Vec<(Package, Vec<String>)> {
+    let mut crates: Vec<(Package, Vec<String>)> = Vec::new();
+    for line in output.lines() {
+        if let Some(binary) = line.strip_prefix("    ") {
+            if let Some((_, binaries)) = crates.last_mut() {
+                binaries.push(binary.trim().to_string());
+            }
+            continue;
+        }
+        let Some(header) = line.strip_suffix(':') else {
+            continue;
+        };
+        let mut fields = header.split_whitespace();
+        let (Some(name), Some(version)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        let source = fields.next().map(|s| s.trim_matches(|c| c == '(' || c == ')').to_string());
+        crates.push((
+            Package {
+                name: name.to_string(),
+                version: version.trim_start_matches('v').to_string(),
+                repo: source.or_else(|| Some("crates.io".to_string())),
+                reason: Some(InstallReason::Explicit),
+                size: None,
+            },
+            Vec::new(),
+        ));
+    }
+    crates
+}
+
+/// `cargo install-update --list` (cargo-update): "name v1.0.0 v1.1.0 Yes" rows.
+pub fn parse_cargo_install_update(output: &str) -> Vec<Upgrade> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let fields: Vec<&str> = l.split_whitespace().collect();
+            let [name, current, available, "Yes"] = fields.as_slice() else {
+                return None;
+            };
+            Some(Upgrade {
+                name: name.to_string(),
+                current: Some(current.trim_start_matches('v').to_string()),
+                available: available.trim_start_matches('v').to_string(),
+                repo: Some("crates.io".to_string()),
+            })
+        })
+        .collect()
+}
+
+impl PackageManager for Cargo {
+    fn name(&self) -> &'static str {
+        "cargo"
+    }
+
+    fn installed(&self) -> Vec<Package> {
+        parse_cargo_list(&query("cargo", &["install", "--list"]).unwrap_or_default()).into_iter().map(|(p, _)| p).collect()
+    }
+
+    fn info(&self, name: &str) -> Option<Package> {
+        self.installed().into_iter().find(|p| p.name == name)
+    }
+
+    fn owner_of(&self, path: &Path) -> Option<String> {
+        let binary = path.file_name()?.to_string_lossy().to_string();
+        parse_cargo_list(&query("cargo", &["install", "--list"])?)
+            .into_iter()
+            .find(|(_, binaries)| binaries.contains(&binary))
+            .map(|(p, _)| p.name)
+    }
+
+    fn orphans(&self) -> Vec<Package> {
+        // Installed crates are self-contained binaries; nothing depends on anything
+        Vec::new()
+    }
+
+    fn upgradable(&self) -> Vec<Upgrade> {
+        // Plain cargo cannot check for newer versions; the cargo-update plugin can
+        if !package_manager::command_exists("cargo-install-update") {
+            return Vec::new();
+        }
+        parse_cargo_install_update(&query("cargo", &["install-update", "--list"]).unwrap_or_default())
+    }
+
+    fn install_args(&self, names: &[String]) -> Vec<String> {
+        args_with(&["cargo", "install"], names)
+    }
+
+    fn remove_args(&self, names: &[String]) -> Vec<String> {
+        args_with(&["cargo", "uninstall"], names)
+    }
+
+    fn needs_root(&self) -> bool {
+        false
+    }
+}
+
+// ---------------------------------------------------------------------------------------------
+// pipx
+
+pub struct Pipx;
+
+/// `pipx list --json`: venvs keyed by name with the main package version in the metadata.
+pub fn parse_pipx_list(json: &str) -> Vec<Package> {
+    let Ok(value) = serde_json::from_str::<Value>(json) else {
+        return Vec::new();
+    };
+    let Some(venvs) = value["venvs"].as_object() else {
+        return Vec::new();
+    };
+    venvs
+        .iter()
+        .map(|(name, venv)| {
+            let main = &venv["metadata"]["main_package"];
+            Package {
+                name: name.clone(),
+                version: main["package_version"].as_str().unwrap_or_default().to_string(),
+                repo: main["package_or_url"].as_str().filter(|s| s != name).map(|s| s.to_string()),
+                reason: Some(InstallReason::Explicit),
+                size: None,
+            }
+        })
+        .collect()
+}
+
+impl PackageManager for Pipx {
+    fn name(&self) -> &'static str {
+        "pipx"
+    }
+
+    fn installed(&self) -> Vec<Package> {
+        parse_pipx_list(&query("pipx", &["list", "--json"]).unwrap_or_default())
+    }
+
+    fn info(&self, name: &str) -> Option<Package> {
+        self.installed().into_iter().find(|p| p.name == name)
+    }
+
+    fn owner_of(&self, path: &Path) -> Option<String> {
+        // ~/.local/bin entries link into ~/.local/share/pipx/venvs/<name>/bin (or ~/.local/pipx)
+        component_after(path, "venvs")
+    }
+
+    fn orphans(&self) -> Vec<Package> {
+        // Every app has its own venv, so there is nothing shared to leave behind
+        Vec::new()
+    }
+
+    fn upgradable(&self) -> Vec<Upgrade> {
+        // pipx has no offline check for newer releases
+        Vec::new()
+    }
+
+    fn install_args(&self, names: &[String]) -> Vec<String> {
+        args_with(&["pipx", "install"], names)
+    }
+
+    fn remove_args(&self, names: &[String]) -> Vec<String> {
+        args_with(&["pipx", "uninstall"], names)
+    }
+
+    fn needs_root(&self) -> bool {
+        false
+    }
+}
+
+// ---------------------------------------------------------------------------------------------
+// AppImage and Distrobox: listed only, neither has orphans or an offline update check
+
+/// Where AppImages usually end up (AppImageLauncher, Gear Lever and manual downloads).
+const APPIMAGE_DIRS: [&str; 4] = ["Applications", "AppImages", ".local/bin", "Downloads"];
+
+/// AppImage files in the usual per-user locations, with their size.
+pub fn appimages() -> Vec<Package> {
+    let mut found = Vec::new();
+    for dir in APPIMAGE_DIRS.iter().map(|d| home().join(d)) {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.to_lowercase().ends_with(".appimage") {
+                continue;
+            }
+            let stem = &name[..name.len() - ".appimage".len()];
+            let (name, version) = split_name_version(stem);
+            found.push(Package {
+                name,
+                version,
+                repo: Some(dir.display().to_string()),
+                reason: Some(InstallReason::Explicit),
+                size: entry.metadata().ok().map(|m| m.len()),
+            });
+        }
+    }
+    found
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Container {
+    pub name: String,
+    pub status: String,
+    pub image: String,
+}
+
+/// `distrobox list --no-color`: "ID | NAME | STATUS | IMAGE" table.
+pub fn parse_distrobox_list(output: &str) -> Vec<Container> {
+    output
+        .lines()
+        .skip(1)
+        .filter_map(|l| {
+            let cols: Vec<&str> = l.split('|').map(str::trim).collect();
+            let [_id, name, status, image] = cols.as_slice() else {
+                return None;
+            };
+            Some(Container { name: name.to_string(), status: status.to_string(), image: image.to_string() })
+        })
+        .collect()
+}
+
+/// Distrobox containers; their packages belong to the container's own package manager.
+pub fn distrobox_containers() -> Vec<Container> {
+    if !package_manager::command_exists("distrobox") {
+        return Vec::new();
+    }
+    parse_distrobox_list(&query("distrobox", &["list", "--no-color"]).unwrap_or_default())
+}
+
+/// Secondary package managers present on this machine, in a stable order.
+pub fn detect() -> Vec<Box<dyn PackageManager>> {
+    let mut managers: Vec<Box<dyn PackageManager>> = Vec::new();
+    if package_manager::command_exists("flatpak") {
+        managers.push(Box::new(Flatpak));
+    }
+    // The snap command can exist without snapd; /snap only appears once snapd has run
+    if package_manager::command_exists("snap") && Path::new("/snap").exists() {
+        managers.push(Box::new(Snap));
+    }
+    if package_manager::command_exists("nix-env") || package_manager::command_exists("nix") {
+        managers.push(Box::new(Nix));
+    }
+    if package_manager::command_exists("brew") {
+        managers.push(Box::new(Brew));
+    }
+    if package_manager::command_exists("cargo") && cargo_home().join(".crates.toml").exists() {
+        managers.push(Box::new(Cargo));
+    }
+    if package_manager::command_exists("pipx") {
+        managers.push(Box::new(Pipx));
+    }
+    managers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flatpak_unused_runtimes() {
+        let runtimes = "\
+org.gnome.Platform\t46\t1.1 GB
+org.gnome.Platform\t44\t980.3 MB
+org.gnome.Platform.Locale\t44\t18.4 kB
+org.freedesktop.Platform\t23.08\t520.0 MB
+org.freedesktop.Platform.GL.default\t23.08\t410.2 MB
+org.freedesktop.Platform.openh264\t2.2.0\t1.0 MB
+org.gnome.Maps.Locale\tstable\t12.0 kB
+org.kde.Platform\t5.15-23.08\t1.2 GB
+";
+        let apps = "org.gnome.Platform/x86_64/46\torg.gnome.Maps\norg.freedesktop.Platform/x86_64/23.08\tcom.valvesoftware.Steam\n";
+        let unused = parse_flatpak_unused(runtimes, apps);
+        let names: Vec<(&str, &str)> = unused.iter().map(|p| (p.name.as_str(), p.version.as_str())).collect();
+        assert_eq!(names, [("org.gnome.Platform", "44"), ("org.kde.Platform", "5.15-23.08")]);
+        assert_eq!(unused[0].size, Some(980_300_000));
+        assert_eq!(parse_flatpak_unused(runtimes, "").len(), 8);
+    }
+
+    #[test]
+    fn name_version_split() {
+        assert_eq!(split_name_version("hello-2.12.1"), ("hello".to_string(), "2.12.1".to_string()));
+        assert_eq!(split_name_version("python3.11-black-24.2.0"), ("python3.11-black".to_string(), "24.2.0".to_string()));
+        assert_eq!(split_name_version("nix-index-0.1.7"), ("nix-index".to_string(), "0.1.7".to_string()));
+        assert_eq!(split_name_version("source"), ("source".to_string(), String::new()));
+        assert_eq!(
+            nix_store_name("/nix/store/63l345l7dgcfz789w1y93j1540czafqh-hello-2.12.1"),
+            Some(("hello".to_string(), "2.12.1".to_string()))
+        );
+    }
+
+    #[test]
+    fn snap_list_all() {
+        let output = "\
+Name     Version         Rev    Tracking       Publisher   Notes
+core22   20240111        1122   latest/stable  canonical✓  base
+core22   20231123        1033   latest/stable  canonical✓  base,disabled
+firefox  123.0-1         3836   latest/stable  mozilla✓    -
+snapd    2.61.2          21184  latest/stable  canonical✓  snapd
+";
+        let (active, disabled) = parse_snap_list(output);
+        assert_eq!(active.len(), 3);
+        assert_eq!(active[1].name, "firefox");
+        assert_eq!(active[1].version, "123.0-1 (rev 3836)");
+        assert_eq!(active[1].repo.as_deref(), Some("latest/stable"));
+        assert_eq!(disabled.len(), 1);
+        assert_eq!(disabled[0].version, "20231123 (rev 1033)");
+        assert_eq!(Snap.orphan_cleanup(&disabled), ["sudo snap remove core22 --revision=1033"]);
+
+        let refresh = "Name     Version  Rev   Size   Publisher  Notes
+firefox  124.0-2  3901  270MB  mozilla✓   -
+";
+        let upgrades = parse_snap_refresh(refresh, &active);
+        assert_eq!(upgrades.len(), 1);
+        assert_eq!(upgrades[0].current.as_deref(), Some("123.0-1 (rev 3836)"));
+        assert_eq!(upgrades[0].available, "124.0-2 (rev 3901)");
+    }
+
+    #[test]
+    fn nix_profile_formats() {
+        let v3 = r#"{"elements":{"hello":{"active":true,"attrPath":"legacyPackages.x86_64-linux.hello","originalUrl":"flake:nixpkgs","outputs":null,"priority":5,"storePaths":["/nix/store/63l345l7dgcfz789w1y93j1540czafqh-hello-2.12.1"],"url":"github:NixOS/nixpkgs/1536926ef5621b09bba54035ae2bb6d806d72ac8"}},"version":3}"#;
+        let packages = parse_nix_profile(v3);
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages[0].name, "hello");
+        assert_eq!(packages[0].version, "2.12.1");
+        assert_eq!(packages[0].repo.as_deref(), Some("flake:nixpkgs"));
+
+        let v2 = r#"{"elements":[{"active":true,"attrPath":"legacyPackages.x86_64-linux.ripgrep","originalUrl":"flake:nixpkgs","storePaths":["/nix/store/zc4ml0b5f1qv8mi1vv0s7rbs4zcq4nb5-ripgrep-14.1.0"]}],"version":2}"#;
+        let packages = parse_nix_profile(v2);
+        assert_eq!(packages[0].name, "ripgrep");
+        assert_eq!(packages[0].version, "14.1.0");
+        assert!(parse_nix_profile("error: not a profile").is_empty());
+    }
+
+    #[test]
+    fn nix_env_queries() {
+        let packages = parse_nix_env("hello-2.10
+nix-index-0.1.7
+
+");
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[1].name, "nix-index");
+
+        let stderr = "upgrading 'hello-2.10' to 'hello-2.12.1'
+upgrading 'nix-index-0.1.5' to 'nix-index-0.1.7'
+warning: something
+";
+        let upgrades = parse_nix_env_upgrades(stderr);
+        assert_eq!(upgrades.len(), 2);
+        assert_eq!(upgrades[0].name, "hello");
+        assert_eq!(upgrades[0].current.as_deref(), Some("2.10"));
+        assert_eq!(upgrades[0].available, "2.12.1");
+        assert_eq!(upgrades[1].name, "nix-index");
+
+        let dead = "finding garbage collector roots...
+/nix/store/9krlzvny65gdc8s7kpb6lkx8cd02c25b-zlib-1.3.1
+/nix/store/ka2l6rqq5zjqsq5awvy2n3b3dcy3qq7y-hello-2.10.drv
+";
+        let orphans = parse_nix_dead(dead);
+        assert_eq!(orphans.len(), 1);
+        assert_eq!(orphans[0].name, "zlib");
+    }
+
+    #[test]
+    fn brew_queries() {
+        let packages = parse_brew_list("git 2.43.0 2.44.0
+pcre2 10.42
+", &["git".to_string()]);
+        assert_eq!(packages[0].version, "2.44.0", "the newest version in the Cellar counts");
+        assert_eq!(packages[0].reason, Some(InstallReason::Explicit));
+        assert_eq!(packages[1].reason, Some(InstallReason::Dependency));
+
+        let upgrades = parse_brew_outdated("git (2.43.0) < 2.44.0
+python@3.12 (3.12.1, 3.12.1_1) != 3.12.2
+");
+        assert_eq!(upgrades.len(), 2);
+        assert_eq!(upgrades[0].name, "git");
+        assert_eq!(upgrades[0].current.as_deref(), Some("2.43.0"));
+        assert_eq!(upgrades[0].available, "2.44.0");
+        assert_eq!(upgrades[1].name, "python@3.12");
+        assert_eq!(upgrades[1].current.as_deref(), Some("3.12.1_1"));
+        assert_eq!(upgrades[1].available, "3.12.2");
+
+        let autoremove = "==> Would autoremove 2 unneeded formulae:\nlibyaml\nm4\n";
+        let orphans = parse_brew_autoremove(autoremove);
+        assert_eq!(orphans.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), ["libyaml", "m4"]);
+    }
+
+    #[test]
+    fn cargo_install_list() {
+        let output = "\
+cargo-update v13.3.0:
+    cargo-install-update
+    cargo-install-update-config
+ripgrep v14.1.0:
+    rg
+nephyra v0.1.0 (/home/user/src/nephyra):
+    Nephyra
+";
+        let crates = parse_cargo_list(output);
+        assert_eq!(crates.len(), 3);
+        assert_eq!(crates[0].0.name, "cargo-update");
+        assert_eq!(crates[0].0.version, "13.3.0");
+        assert_eq!(crates[0].1, ["cargo-install-update", "cargo-install-update-config"]);
+        assert_eq!(crates[1].0.repo.as_deref(), Some("crates.io"));
+        assert_eq!(crates[2].0.repo.as_deref(), Some("/home/user/src/nephyra"));
+        assert_eq!(crates[2].1, ["Nephyra"]);
+
+        let update = "\
+Package       Installed  Latest   Needs update
+cargo-update  v13.3.0    v13.4.0  Yes
+ripgrep       v14.1.0    v14.1.0  No
+";
+        let upgrades = parse_cargo_install_update(update);
+        assert_eq!(upgrades.len(), 1);
+        assert_eq!(upgrades[0].current.as_deref(), Some("13.3.0"));
+        assert_eq!(upgrades[0].available, "13.4.0");
+    }
+
+    #[test]
+    fn pipx_list_json() {
+        let json = r#"{"pipx_spec_version":"0.1","venvs":{
+            "black":{"metadata":{"main_package":{"package":"black","package_or_url":"black","package_version":"24.2.0"}}},
+            "poetry":{"metadata":{"main_package":{"package":"poetry","package_or_url":"git+https://github.com/python-poetry/poetry","package_version":"1.8.0"}}}}}"#;
+        let packages = parse_pipx_list(json);
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].name, "black");
+        assert_eq!(packages[0].version, "24.2.0");
+        assert_eq!(packages[0].repo, None, "a plain PyPI name is not a source");
+        assert_eq!(packages[1].repo.as_deref(), Some("git+https://github.com/python-poetry/poetry"));
+        assert!(parse_pipx_list("").is_empty());
+    }
+
+    #[test]
+    fn distrobox_list() {
+        let output = "\
+ID           | NAME                 | STATUS             | IMAGE
+d0f8dc1b6dae | fedora-39            | Up 2 hours         | registry.fedoraproject.org/fedora-toolbox:39
+8a1c2e3f4b5d | arch                 | Exited (0) 3 days ago | quay.io/toolbx/arch-toolbox:latest
+";
+        let containers = parse_distrobox_list(output);
+        assert_eq!(containers.len(), 2);
+        assert_eq!(containers[0].name, "fedora-39");
+        assert_eq!(containers[0].status, "Up 2 hours");
+        assert_eq!(containers[1].image, "quay.io/toolbx/arch-toolbox:latest");
+    }
+}
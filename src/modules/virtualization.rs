@@ -0,0 +1,210 @@
+// virtualization.rs
+// Hypervisor and container detection following systemd-detect-virt's identifiers and probe order
+
+use std::fs;
+use std::path::Path;
+
+use super::cpu_info;
+use super::dmi_info;
+use super::sysfs;
+
+/// CPUID leaf 0x40000000 vendor signatures mapped to systemd-detect-virt identifiers.
+const CPUID_VENDORS: [(&str, &str); 10] = [
+    ("KVMKVMKVM", "kvm"),
+    ("Linux KVM Hv", "kvm"),
+    ("TCGTCGTCGTCG", "qemu"),
+    ("VMwareVMware", "vmware"),
+    ("Microsoft Hv", "microsoft"),
+    ("VBoxVBoxVBox", "oracle"),
+    ("XenVMMXenVMM", "xen"),
+    ("bhyve bhyve ", "bhyve"),
+    ("ACRNACRNACRN", "acrn"),
+    (" lrpepyh  vr", "parallels"),
+];
+/// Path fragments of /proc/1/cgroup that reveal the container runtime.
+const CGROUP_RUNTIMES: [(&str, &str); 5] = [
+    ("/docker/", "docker"),
+    ("/libpod-", "podman"),
+    ("/kubepods", "kubernetes"),
+    ("/lxc/", "lxc"),
+    ("/machine.slice/systemd-nspawn", "systemd-nspawn"),
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct VirtInfo {
+    /// Hypervisor identifier ("kvm", "vmware", "microsoft", ...), None on bare metal.
+    pub hypervisor: Option<String>,
+    /// Which probe identified the hypervisor.
+    pub hypervisor_source: Option<&'static str>,
+    /// Container runtime identifier ("docker", "podman", "lxc", "wsl", ...).
+    pub container: Option<String>,
+    /// Hardware virtualization extension available to this system (vmx or svm).
+    pub hw_extension: Option<&'static str>,
+    /// Whether KVM allows nested guests; None when the KVM module is not loaded.
+    pub nested: Option<bool>,
+}
+
+impl VirtInfo {
+    pub fn in_container(&self) -> bool {
+        self.container.is_some()
+    }
+
+    pub fn in_vm(&self) -> bool {
+        self.hypervisor.is_some()
+    }
+
+    /// Short environment label, e.g. "bare metal", "kvm guest" or "docker container on kvm".
+    pub fn label(&self) -> String {
+        match (&self.container, &self.hypervisor) {
+            (Some(c), Some(h)) => format!("{} container on {}", c, h),
+            (Some(c), None) => format!("{} container", c),
+            (None, Some(h)) => format!("{} guest", h),
+            (None, None) => "bare metal".to_string(),
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn cpuid_hypervisor() -> Option<String> {
+    use std::arch::x86_64::__cpuid;
+    // Leaf 1 ECX bit 31 is reserved for hypervisors to announce themselves
+    if __cpuid(1).ecx & (1 << 31) == 0 {
+        return None;
+    }
+    let leaf = __cpuid(0x4000_0000);
+    let bytes: Vec<u8> = [leaf.ebx, leaf.ecx, leaf.edx].iter().flat_map(|r| r.to_le_bytes()).collect();
+    let signature = String::from_utf8_lossy(&bytes).trim_end_matches('\0').to_string();
+    let id = CPUID_VENDORS
+        .iter()
+        .find(|(sig, _)| signature.starts_with(sig))
+        .map(|(_, id)| id.to_string())
+        .unwrap_or_else(|| "vm-other".to_string());
+    Some(id)
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn cpuid_hypervisor() -> Option<String> {
+    None
+}
+
+/// Detect the hypervisor: CPUID first, then DMI (which also names KVM guests that hide the CPUID
+/// leaf), then /sys/hypervisor for Xen PV guests, then the cpuinfo flag on non-x86 kernels.
+pub fn detect_hypervisor() -> Option<(String, &'static str)> {
+    let cpuid = cpuid_hypervisor();
+    // Like systemd, let DMI refine a generic CPUID answer (e.g. "kvm" on Amazon EC2)
+    if let Some(dmi) = dmi_info::read_dmi_info().virtual_vendor()
+        && cpuid.as_deref().is_none_or(|c| c == "vm-other" || dmi == "amazon")
+    {
+        return Some((dmi.to_string(), "DMI"));
+    }
+    if let Some(id) = cpuid {
+        return Some((id, "CPUID"));
+    }
+    if let Some(kind) = sysfs::read_trimmed("/sys/hypervisor/type")
+        && !kind.is_empty()
+    {
+        return Some((kind, "/sys/hypervisor"));
+    }
+    if cpu_info::read_cpu_info().has_flag("hypervisor") {
+        return Some(("vm-other".to_string(), "cpuinfo"));
+    }
+    None
+}
+
+/// The `container=` variable that init systems inside containers receive.
+fn pid1_container_env() -> Option<String> {
+    let environ = fs::read("/proc/1/environ").ok()?;
+    environ
+        .split(|&b| b == 0)
+        .filter_map(|var| std::str::from_utf8(var).ok())
+        .find_map(|var| var.strip_prefix("container=").map(|v| v.to_string()))
+        .filter(|v| !v.is_empty())
+}
+
+fn cgroup_runtime(cgroup: &str) -> Option<&'static str> {
+    CGROUP_RUNTIMES.iter().find(|(needle, _)| cgroup.contains(needle)).map(|(_, id)| *id)
+}
+
+/// Detect the container runtime using the same markers as systemd-detect-virt --container.
+pub fn detect_container() -> Option<String> {
+    // WSL runs a real kernel but shares it with Windows; systemd treats it as a container
+    if let Some(release) = sysfs::read_trimmed("/proc/sys/kernel/osrelease") {
+        let release = release.to_lowercase();
+        if release.contains("microsoft") || release.contains("wsl") {
+            return Some("wsl".to_string());
+        }
+    }
+    if let Some(kind) = sysfs::read_trimmed("/run/systemd/container").filter(|k| !k.is_empty()) {
+        return Some(kind);
+    }
+    if let Some(kind) = pid1_container_env() {
+        return Some(kind);
+    }
+    if Path::new("/run/.containerenv").exists() {
+        return Some("podman".to_string());
+    }
+    if Path::new("/.dockerenv").exists() {
+        return Some("docker".to_string());
+    }
+    if let Some(runtime) = fs::read_to_string("/proc/1/cgroup").ok().as_deref().and_then(cgroup_runtime) {
+        return Some(runtime.to_string());
+    }
+    if Path::new("/proc/vz").exists() && !Path::new("/proc/bc").exists() {
+        return Some("openvz".to_string());
+    }
+    None
+}
+
+/// KVM's nested parameter: "Y"/"1" when guests may run their own hypervisors.
+fn nested_enabled() -> Option<bool> {
+    ["/sys/module/kvm_intel/parameters/nested", "/sys/module/kvm_amd/parameters/nested"]
+        .iter()
+        .find_map(sysfs::read_trimmed)
+        .map(|v| v == "Y" || v == "1")
+}
+
+pub fn detect() -> VirtInfo {
+    let cpu = cpu_info::read_cpu_info();
+    let hypervisor = detect_hypervisor();
+    VirtInfo {
+        hypervisor_source: hypervisor.as_ref().map(|(_, source)| *source),
+        hypervisor: hypervisor.map(|(id, _)| id),
+        container: detect_container(),
+        hw_extension: if cpu.has_flag("vmx") {
+            Some("vmx")
+        } else if cpu.has_flag("svm") {
+            Some("svm")
+        } else {
+            None
+        },
+        nested: nested_enabled(),
+    }
+}
+
+/// Print the virtualization section used by `nephyra hardware`.
+pub fn print_details(info: &VirtInfo) {
+    println!("\n🧊 Environment: {}", info.label());
+    if let (Some(hv), Some(source)) = (&info.hypervisor, info.hypervisor_source) {
+        println!("   Hypervisor: {} (detected via {})", hv, source);
+    }
+    if let Some(container) = &info.container {
+        println!("   Container: {}", container);
+        println!("   ℹ️ The kernel, bootloader and firmware belong to the host; related advice is skipped.");
+    }
+    match (info.hw_extension, info.nested) {
+        (Some(ext), Some(nested)) => println!(
+            "   Hardware virtualization: {} | Nested guests: {}",
+            ext,
+            if nested { "enabled" } else { "disabled" }
+        ),
+        (Some(ext), None) if info.in_vm() => println!("   Hardware virtualization: {} (exposed by the hypervisor, nested guests possible)", ext),
+        (Some(ext), None) => println!("   Hardware virtualization: {} (KVM module not loaded)", ext),
+        (None, _) if info.in_vm() => println!("   Hardware virtualization: not exposed to this guest (no nested virtualization)"),
+        (None, _) => println!("   Hardware virtualization: unavailable or disabled in firmware"),
+    }
+}
+
+/// One-line environment summary.
+pub fn summary(info: &VirtInfo) -> String {
+    format!("Environment: {}", info.label())
+}